@@ -1,8 +1,7 @@
 use codespan_reporting::diagnostic::Severity;
 use codespan_reporting::files::SimpleFiles;
 use codespan_reporting::term::termcolor::{BufferedStandardStream, ColorChoice};
-use pikelet::lang::{core, surface};
-use pikelet::pass::surface_to_core;
+use pikelet::lang::core;
 use std::io::Write;
 use std::path::PathBuf;
 
@@ -22,14 +21,8 @@ pub fn run(options: Options) -> anyhow::Result<()> {
     let mut writer = BufferedStandardStream::stderr(ColorChoice::Always);
     let reporting_config = codespan_reporting::term::Config::default();
 
-    let globals = core::Globals::default();
     let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
     let mut files = SimpleFiles::new();
-    let mut surface_to_core = surface_to_core::Context::new(&globals, messages_tx.clone());
-    let mut core_typing = match options.validate_core {
-        true => Some(core::typing::Context::new(&globals, messages_tx.clone())),
-        false => None,
-    };
 
     let mut is_ok = true;
 
@@ -38,10 +31,9 @@ pub fn run(options: Options) -> anyhow::Result<()> {
         let file_id = files.add(file_name.display().to_string(), source);
         let file = files.get(file_id).unwrap();
 
-        let surface_term = surface::Term::from_str(file_id, file.source(), &messages_tx);
-
-        let (core_term, _) = surface_to_core.synth_type(&surface_term);
-        if let Some(core_typing) = &mut core_typing {
+        let (globals, _, core_term) = crate::elaborate_file(file_id, file.source(), &messages_tx);
+        if options.validate_core {
+            let mut core_typing = core::typing::Context::new(&globals, messages_tx.clone());
             let _ = core_typing.synth_type(&core_term);
         }
 
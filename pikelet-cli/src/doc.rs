@@ -0,0 +1,69 @@
+use codespan_reporting::diagnostic::Severity;
+use codespan_reporting::files::SimpleFiles;
+use codespan_reporting::term::termcolor::{BufferedStandardStream, ColorChoice};
+use pikelet::pass::core_to_doc;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Generate documentation for a Pikelet source file.
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// The format of the generated documentation.
+    #[structopt(
+        long = "format",
+        default_value = "markdown",
+        possible_values = &["markdown", "html"],
+    )]
+    format: String,
+    /// The file to write the documentation to. Defaults to the standard output.
+    #[structopt(long = "output", short = "o")]
+    output: Option<PathBuf>,
+    /// The Pikelet source file to be documented.
+    #[structopt(name = "FILE")]
+    file_name: PathBuf,
+}
+
+pub fn run(options: Options) -> anyhow::Result<()> {
+    let pretty_alloc = pretty::BoxAllocator;
+    let mut writer = BufferedStandardStream::stderr(ColorChoice::Always);
+    let reporting_config = codespan_reporting::term::Config::default();
+
+    let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
+    let mut files = SimpleFiles::new();
+
+    let source = std::fs::read_to_string(&options.file_name)?;
+    let file_id = files.add(options.file_name.display().to_string(), source);
+    let file = files.get(file_id).unwrap();
+
+    let (globals, docs, core_term) = crate::elaborate_file(file_id, file.source(), &messages_tx);
+
+    let mut is_ok = true;
+    for message in messages_rx.try_iter() {
+        let diagnostic = message.to_diagnostic(&pretty_alloc);
+        is_ok &= diagnostic.severity < Severity::Error;
+
+        codespan_reporting::term::emit(&mut writer, &reporting_config, &files, &diagnostic)?;
+        writer.flush()?;
+    }
+
+    if !is_ok {
+        return Err(anyhow::anyhow!("errors found in supplied source file"));
+    }
+
+    let title = match options.file_name.file_stem() {
+        Some(file_stem) => file_stem.to_string_lossy().into_owned(),
+        None => options.file_name.display().to_string(),
+    };
+    let format = match options.format.as_str() {
+        "html" => core_to_doc::Format::Html,
+        _ => core_to_doc::Format::Markdown,
+    };
+    let output = core_to_doc::from_term(&globals, &title, &docs.join("\n"), &core_term, format);
+
+    match &options.output {
+        Some(output_path) => std::fs::write(output_path, output)?,
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
@@ -1,6 +1,12 @@
 use anyhow::anyhow;
+use crossbeam_channel::Sender;
+use pikelet::lang::{core, surface, FileId};
+use pikelet::pass::surface_to_core;
+use pikelet::reporting::Message;
 
 pub mod check;
+pub mod doc;
+pub mod export;
 pub mod repl;
 
 /// The Pikelet command line interface.
@@ -9,6 +15,12 @@ pub enum Options {
     /// Check some Pikelet source files.
     #[structopt(name = "check")]
     Check(check::Options),
+    /// Generate documentation for a Pikelet source file.
+    #[structopt(name = "doc")]
+    Doc(doc::Options),
+    /// Export the data in a Pikelet source file as JSON.
+    #[structopt(name = "export")]
+    Export(export::Options),
     /// Runs the structured editor.
     #[cfg(feature = "editor")]
     #[structopt(name = "editor")]
@@ -26,6 +38,8 @@ pub enum Options {
 pub fn run(options: Options) -> anyhow::Result<()> {
     match options {
         Options::Check(options) => check::run(options),
+        Options::Doc(options) => doc::run(options),
+        Options::Export(options) => export::run(options),
         #[cfg(feature = "editor")]
         Options::Editor => {
             // FIXME: `iced::Error` is not `Send + Sync`, and so is incompatible with `anyhow::Result`.
@@ -44,3 +58,23 @@ fn term_width() -> usize {
         None => std::usize::MAX,
     }
 }
+
+/// Parse and elaborate a source file, returning the documentation of the
+/// module along with the elaborated term. Each file is elaborated with a fresh
+/// set of globals, so that the declarations in one file are not visible in
+/// the files that are elaborated after it.
+fn elaborate_file(
+    file_id: FileId,
+    source: &str,
+    messages_tx: &Sender<Message>,
+) -> (core::Globals, surface::DocLines, core::Term) {
+    let module = surface::Module::from_str(file_id, source, messages_tx);
+
+    let mut globals = core::Globals::default();
+    surface_to_core::define_module_decls(&mut globals, messages_tx, &module);
+
+    let mut surface_to_core = surface_to_core::Context::new(&globals, messages_tx.clone());
+    let (core_term, _) = surface_to_core.synth_type(&module.term);
+
+    (globals, module.docs, core_term)
+}
@@ -0,0 +1,172 @@
+//! Tests for checking multiple source files with a single command.
+
+use std::process::{Command, Output};
+
+fn check(file_names: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pikelet"))
+        .current_dir("..")
+        .arg("check")
+        .arg("--validate-core")
+        .args(file_names)
+        .output()
+        .unwrap()
+}
+
+/// Check a source file, written to a temporary directory.
+fn check_source(name: &str, source: &str) -> Output {
+    let file_name =
+        std::env::temp_dir().join(format!("pikelet-{}-{}.pi", name, std::process::id()));
+    std::fs::write(&file_name, source).unwrap();
+    let output = check(&[file_name.to_str().unwrap()]);
+    std::fs::remove_file(&file_name).unwrap();
+    output
+}
+
+/// The messages of the errors reported in the output of a command, with
+/// terminal colors removed.
+fn error_messages(output: &Output) -> Vec<String> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut stripped = String::new();
+    let mut chars = stderr.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\u{1b}' => while !matches!(chars.next(), Some('m') | None) {},
+            ch => stripped.push(ch),
+        }
+    }
+
+    (stripped.lines())
+        .filter_map(|line| line.strip_prefix("error: "))
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn data_decls_are_local_to_files() {
+    let output = check(&["tests/data-types.pi", "tests/data-types.pi"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr),
+    );
+}
+
+#[test]
+fn operator_decls_are_local_to_files() {
+    let output = check(&["tests/operators.pi", "tests/operators.pi"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr),
+    );
+}
+
+#[test]
+fn holes_are_errors() {
+    let output = check_source(
+        "holes",
+        r#"
+            record {
+                compose = fun A B C a-b b-c a => ?goal,
+            } : Record {
+                compose : Fun (A B C : Type) -> (A -> B) -> (B -> C) -> (A -> C),
+            }
+        "#,
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stderr);
+    assert_eq!(error_messages(&output), ["unfilled hole `?goal`"]);
+    assert!(stderr.contains("expected `C`"), "{}", stderr);
+    assert!(stderr.contains("local variables in scope:"), "{}", stderr);
+    assert!(stderr.contains("a-b : A -> B"), "{}", stderr);
+    assert!(stderr.contains("a : A"), "{}", stderr);
+}
+
+#[test]
+fn solved_holes_are_not_errors() {
+    let output = check_source(
+        "solved-holes",
+        r#"
+            record {
+                x : ?t = (1 : U8),
+                y : U8 = x,
+            }
+        "#,
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("found hole `?t`"), "{}", stderr);
+}
+
+#[test]
+fn syntax_errors_in_entries_keep_their_labels() {
+    let output = check_source(
+        "entry-errors",
+        "record { x = 1 : U32, y = ) } : Record { x : U32, y : U32 }",
+    );
+
+    assert!(!output.status.success());
+    assert_eq!(error_messages(&output), ["unexpected token )"]);
+}
+
+#[test]
+fn multiple_syntax_errors_are_reported() {
+    let output = check_source(
+        "syntax-errors",
+        r#"
+            record {
+                a = 1 : U32,
+                b = (2 : U32) ),
+                c = [1, ), 3] : List U32,
+                d = "hello",
+            } : Record {
+                a : U32,
+                b : ),
+                c : List U32,
+                d : String,
+                e : U32 = ( ],
+            }
+        "#,
+    );
+
+    assert!(!output.status.success());
+    assert_eq!(
+        error_messages(&output),
+        [
+            "unexpected token )",
+            "unexpected token )",
+            "unexpected token )",
+            "unexpected token ]",
+        ],
+    );
+}
+
+#[test]
+fn array_index_out_of_bounds_is_an_error() {
+    let output = check_source(
+        "array-index",
+        "array-index 3 ([1, 2, 3] : Array 3 U32) (record {})",
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stderr);
+    assert_eq!(error_messages(&output), ["mismatched types"]);
+    assert!(
+        stderr.contains("expected `U32-Lt 3 3`, found `Record {}`"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn optional_entries_reject_named_holes() {
+    let output = check_source(
+        "optional-entries",
+        "record {} : Record { subtitle ?name : String }",
+    );
+
+    assert!(!output.status.success());
+    assert_eq!(error_messages(&output), ["unexpected token ?name"]);
+}
@@ -0,0 +1,40 @@
+//! Tests for generating documentation from source files.
+
+use std::process::Command;
+
+#[test]
+fn module_docs_are_shown_beneath_the_title() {
+    let file_name = std::env::temp_dir().join(format!("settings-{}.pi", std::process::id()));
+    let source = r#"
+        ||| Settings for the application.
+        record {
+            ||| The title of the window.
+            title = "Voyager",
+        } : Record {
+            ||| The title of the window.
+            title : String,
+        }
+    "#;
+    std::fs::write(&file_name, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_pikelet"))
+        .arg("doc")
+        .arg(&file_name)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&file_name).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr),
+    );
+    assert!(
+        stdout.starts_with(&format!(
+            "# settings-{}\n\nSettings for the application.\n\n## ",
+            std::process::id(),
+        )),
+        "{}",
+        stdout,
+    );
+}
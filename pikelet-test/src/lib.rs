@@ -85,6 +85,7 @@ fn run_test_impl(pikelet_exe: &str, test: &Test<TestData>) -> Outcome {
             check: CheckConfig {
                 enable: true,
                 validate_core: true,
+                expect_errors: false,
             },
         }
     };
@@ -102,7 +103,7 @@ fn run_test_impl(pikelet_exe: &str, test: &Test<TestData>) -> Outcome {
 
         match output {
             Ok(output) => {
-                if !output.status.success() {
+                if output.status.success() == config.check.expect_errors {
                     failures.push(Failure {
                         name: "exit status".to_owned(),
                         details: output.status.to_string(),
@@ -114,7 +115,7 @@ fn run_test_impl(pikelet_exe: &str, test: &Test<TestData>) -> Outcome {
                         details: String::from_utf8_lossy(&output.stdout).into(),
                     });
                 }
-                if !output.stderr.is_empty() {
+                if output.stderr.is_empty() == config.check.expect_errors {
                     failures.push(Failure {
                         name: "stderr".to_owned(),
                         details: String::from_utf8_lossy(&output.stderr).into(),
@@ -154,6 +155,8 @@ struct CheckConfig {
     enable: bool,
     #[serde(default = "true_value")]
     validate_core: bool,
+    #[serde(default = "false_value")]
+    expect_errors: bool,
 }
 
 impl Default for CheckConfig {
@@ -161,6 +164,7 @@ impl Default for CheckConfig {
         CheckConfig {
             enable: false,
             validate_core: true,
+            expect_errors: false,
         }
     }
 }
@@ -0,0 +1,230 @@
+//! A high-level API for loading Pikelet configuration files into Rust values.
+//!
+//! This handles the plumbing of parsing, elaborating, and marshalling terms,
+//! checking the source against the type provided by the [`HasType`] impl of
+//! the target Rust type:
+//!
+//! ```rust
+//! let value: Vec<u32> = pikelet::from_str("[1, 2, 3]").unwrap();
+//! assert_eq!(value, vec![1, 2, 3]);
+//! ```
+//!
+//! [`HasType`]: crate::lang::core::marshall::HasType
+
+use codespan_reporting::diagnostic::{Diagnostic, Severity};
+use codespan_reporting::files::SimpleFiles;
+use codespan_reporting::term::termcolor::{NoColor, WriteColor};
+use std::fmt;
+use std::path::Path;
+
+use crate::lang::core::marshall::TryFromTerm;
+use crate::lang::{core, surface, FileId};
+use crate::pass::surface_to_core;
+use crate::reporting::Message;
+
+/// Errors produced when loading a configuration file.
+#[derive(Debug)]
+pub enum Error {
+    /// The source file could not be read.
+    Io(std::io::Error),
+    /// The source contained errors.
+    Messages {
+        files: SimpleFiles<String, String>,
+        messages: Vec<Message>,
+    },
+    /// The elaborated term could not be converted into the Rust type.
+    Marshall { type_name: &'static str },
+}
+
+impl Error {
+    /// Convert the error into a list of diagnostics.
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic<FileId>> {
+        let pretty_alloc = pretty::BoxAllocator;
+
+        match self {
+            Error::Io(error) => vec![Diagnostic::error().with_message(error.to_string())],
+            Error::Messages { messages, .. } => messages
+                .iter()
+                .map(|message| message.to_diagnostic(&pretty_alloc))
+                .collect(),
+            Error::Marshall { type_name } => vec![Diagnostic::bug().with_message(format!(
+                "failed to convert the elaborated term to `{}`",
+                type_name,
+            ))],
+        }
+    }
+
+    /// Pretty print the error to the given writer.
+    pub fn emit(
+        &self,
+        writer: &mut dyn WriteColor,
+        config: &codespan_reporting::term::Config,
+    ) -> Result<(), codespan_reporting::files::Error> {
+        let empty_files = SimpleFiles::new();
+        let files = match self {
+            Error::Messages { files, .. } => files,
+            _ => &empty_files,
+        };
+
+        for diagnostic in self.to_diagnostics() {
+            codespan_reporting::term::emit(writer, config, files, &diagnostic)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buffer = NoColor::new(Vec::new());
+        let config = codespan_reporting::term::Config::default();
+        self.emit(&mut buffer, &config).map_err(|_| fmt::Error)?;
+        write!(
+            f,
+            "{}",
+            String::from_utf8_lossy(&buffer.into_inner()).trim_end()
+        )
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+/// Load a value from a Pikelet source string.
+pub fn from_str<T: TryFromTerm>(source: &str) -> Result<T, Error> {
+    from_str_with_globals(&core::Globals::default(), source)
+}
+
+/// Load a value from a Pikelet source file.
+pub fn from_path<T: TryFromTerm>(path: impl AsRef<Path>) -> Result<T, Error> {
+    from_path_with_globals(&core::Globals::default(), path)
+}
+
+/// Load a value from a Pikelet source string, using the supplied globals.
+///
+/// Declarations in the source are defined in a copy of the globals, and are
+/// not visible to subsequent loads.
+pub fn from_str_with_globals<T: TryFromTerm>(
+    globals: &core::Globals,
+    source: &str,
+) -> Result<T, Error> {
+    from_named_str(globals, "<input>", source)
+}
+
+/// Load a value from a Pikelet source file, using the supplied globals.
+pub fn from_path_with_globals<T: TryFromTerm>(
+    globals: &core::Globals,
+    path: impl AsRef<Path>,
+) -> Result<T, Error> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+    from_named_str(globals, &path.display().to_string(), &source)
+}
+
+fn from_named_str<T: TryFromTerm>(
+    globals: &core::Globals,
+    name: &str,
+    source: &str,
+) -> Result<T, Error> {
+    let pretty_alloc = pretty::BoxAllocator;
+    let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
+    let mut files = SimpleFiles::new();
+
+    let file_id = files.add(name.to_owned(), source.to_owned());
+    let module = surface::Module::from_str(file_id, source, &messages_tx);
+
+    let mut globals = globals.clone();
+    surface_to_core::define_module_decls(&mut globals, &messages_tx, &module);
+
+    let mut surface_to_core = surface_to_core::Context::new(&globals, messages_tx.clone());
+    let r#type = surface_to_core.eval(&T::r#type());
+    let core_term = surface_to_core.check_type(&module.term, &r#type);
+
+    let messages = messages_rx.try_iter().collect::<Vec<_>>();
+    let is_ok = (messages.iter())
+        .all(|message| message.to_diagnostic(&pretty_alloc).severity < Severity::Error);
+    if !is_ok {
+        return Err(Error::Messages { files, messages });
+    }
+
+    let core_term = surface_to_core.normalize(&core_term);
+    T::try_from_term(&core_term).map_err(|_| Error::Marshall {
+        type_name: std::any::type_name::<T>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_ok() {
+        assert_eq!(from_str::<u32>("42").ok(), Some(42));
+        assert_eq!(
+            from_str::<Vec<bool>>("[true, false]").ok(),
+            Some(vec![true, false]),
+        );
+        assert_eq!(
+            from_str::<[String; 2]>(r#"["a", "b"]"#).ok(),
+            Some(["a".to_owned(), "b".to_owned()]),
+        );
+    }
+
+    #[test]
+    fn from_str_mismatched_types() {
+        let error = from_str::<u32>(r#""hello""#).unwrap_err();
+        assert!(matches!(error, Error::Messages { .. }));
+        assert!(error.to_string().contains("error"));
+    }
+
+    #[test]
+    fn from_str_decls_are_local() {
+        let source = r#"
+            data Color { red, green, blue }
+
+            list-length ([red, blue] : List Color)
+        "#;
+        assert_eq!(from_str::<u32>(source).ok(), Some(2));
+
+        let source = r#"
+            infixl 6 + = nat-add
+
+            nat-to-string (1 + 2)
+        "#;
+        assert_eq!(from_str::<String>(source).ok(), Some("3".to_owned()));
+
+        // Declarations made by previous loads are not visible
+        assert!(from_str::<u32>("list-length ([red] : List Color)").is_err());
+        assert!(from_str::<String>("nat-to-string (1 + 2)").is_err());
+    }
+
+    fn foreign_globals() -> core::Globals {
+        let mut globals = core::Globals::default();
+        globals.define_foreign("path-join", |base: String, path: String| {
+            format!("{}/{}", base, path)
+        });
+        globals
+    }
+
+    #[test]
+    fn foreign_function_reduces() {
+        let globals = foreign_globals();
+        let source = r#"path-join (path-join "a" "b") "c""#;
+        assert_eq!(
+            from_str_with_globals::<String>(&globals, source).ok(),
+            Some("a/b/c".to_owned()),
+        );
+    }
+}
@@ -93,3 +93,34 @@ impl<Data> Located<Data> {
         Located::new(Location::generated(), data)
     }
 }
+
+/// Whether an input to a function is supplied explicitly, or is inferred
+/// during elaboration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Plicity {
+    /// Explicit inputs, for example `(x : A)`.
+    Explicit,
+    /// Implicit inputs, for example `{A : Type}`.
+    Implicit,
+}
+
+/// How applications of infix operators that have the same precedence are
+/// grouped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Associativity {
+    /// Left associative operators, for example `a - b - c` is `(a - b) - c`.
+    Left,
+    /// Right associative operators, for example `a :: b :: c` is `a :: (b :: c)`.
+    Right,
+    /// Non-associative operators, which must be explicitly grouped.
+    None,
+}
+
+/// The associativity and precedence of an infix operator.
+///
+/// Operators with higher precedences bind more tightly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Fixity {
+    pub associativity: Associativity,
+    pub precedence: u8,
+}
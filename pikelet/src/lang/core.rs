@@ -4,11 +4,17 @@
 //! language.
 
 use fxhash::FxHashMap;
+use num_bigint::{BigInt, BigUint};
+use once_cell::sync::OnceCell;
 use std::fmt;
 use std::sync::Arc;
 
-use crate::lang::Located;
+use crate::lang::core::data::DataDecl;
+use crate::lang::core::semantics::Value;
+use crate::lang::{Fixity, Located, Plicity};
 
+pub mod builtins;
+pub mod data;
 pub mod marshall;
 pub mod semantics;
 pub mod typing;
@@ -25,6 +31,8 @@ pub enum Constant {
     U32(u32),
     /// 64-bit unsigned integers.
     U64(u64),
+    /// 128-bit unsigned integers.
+    U128(u128),
     /// 8-bit signed [two's complement] integers.
     ///
     /// [two's complement]: https://en.wikipedia.org/wiki/Two%27s_complement
@@ -41,6 +49,14 @@ pub enum Constant {
     ///
     /// [two's complement]: https://en.wikipedia.org/wiki/Two%27s_complement
     S64(i64),
+    /// 128-bit signed [two's complement] integers.
+    ///
+    /// [two's complement]: https://en.wikipedia.org/wiki/Two%27s_complement
+    S128(i128),
+    /// Arbitrary-precision natural numbers.
+    Nat(BigUint),
+    /// Arbitrary-precision signed integers.
+    Int(BigInt),
     /// 32-bit [IEEE-754] floating point numbers.
     ///
     /// [IEEE-754]: https://en.wikipedia.org/wiki/IEEE_754
@@ -55,6 +71,8 @@ pub enum Constant {
     ///
     /// [UTF-8]: http://www.unicode.org/glossary/#UTF_8
     String(String),
+    /// Sequences of bytes.
+    Bytes(Vec<u8>),
 }
 
 pub type Term = Located<TermData>;
@@ -76,18 +94,27 @@ pub enum TermData {
     /// Function types.
     ///
     /// Also known as: pi type, dependent product type.
-    FunctionType(Option<String>, Arc<Term>, Arc<Term>),
+    FunctionType(Plicity, Option<String>, Arc<Term>, Arc<Term>),
     /// Function terms.
     ///
     /// Also known as: lambda abstraction, anonymous function.
-    FunctionTerm(String, Arc<Term>),
+    FunctionTerm(Plicity, String, Arc<Term>),
     /// Function eliminations.
     ///
     /// Also known as: function application.
-    FunctionElim(Arc<Term>, Arc<Term>),
+    FunctionElim(Arc<Term>, Plicity, Arc<Term>),
 
     /// Record types.
-    RecordType(Arc<[String]>, Arc<[Arc<Term>]>),
+    ///
+    /// Each entry has a label, some documentation (which is empty if the
+    /// entry is undocumented), a type, and an optional default term that is
+    /// used when the entry is omitted from a record term.
+    RecordType(
+        Arc<[String]>,
+        Arc<[String]>,
+        Arc<[Arc<Term>]>,
+        Arc<[Option<Arc<Term>>]>,
+    ),
     /// Record terms.
     RecordTerm(Arc<[String]>, Arc<[Arc<Term>]>),
     /// Record eliminations.
@@ -95,6 +122,38 @@ pub enum TermData {
     /// Also known as: record projection, field lookup.
     RecordElim(Arc<Term>, String),
 
+    /// Variant types.
+    ///
+    /// Each entry has a label, and the type of the payload associated with
+    /// that label.
+    ///
+    /// Also known as: tagged union, sum type.
+    VariantType(Arc<[String]>, Arc<[Arc<Term>]>),
+    /// Variant terms.
+    ///
+    /// Also known as: injection.
+    VariantTerm(String, Arc<Term>),
+    /// Variant eliminations.
+    ///
+    /// Each branch has a label, and a term that binds the payload of the
+    /// variant. The branches are in the same order as the entries of the
+    /// variant type.
+    ///
+    /// Also known as: match, case analysis.
+    VariantElim(Arc<Term>, Arc<[String]>, Arc<[Arc<Term>]>),
+
+    /// Data eliminations.
+    ///
+    /// These eliminate a target term of a [user-defined data type][data],
+    /// using a motive, and a method for each constructor of the data type.
+    /// They are normally only found in the definitions of generated
+    /// eliminators.
+    ///
+    /// Also known as: recursion, induction.
+    ///
+    /// [data]: crate::lang::core::data
+    DataElim(Arc<Term>, String, Arc<Term>, Arc<[Arc<Term>]>),
+
     /// Array terms.
     ArrayTerm(Vec<Arc<Term>>),
     /// List terms.
@@ -103,24 +162,344 @@ pub enum TermData {
     /// Constants.
     Constant(Constant),
 
+    /// Metavariables.
+    ///
+    /// These are only created during elaboration, and should be replaced with
+    /// their solutions before terms are passed on to later stages.
+    Meta(Arc<MetaVar>),
+
     /// Error sentinel.
     Error,
 }
 
+impl Term {
+    /// Shift the local variables in a term that are bound outside of `cutoff`
+    /// binders by `amount`.
+    pub fn shift(&self, cutoff: u32, amount: u32) -> Term {
+        let shift = |term: &Arc<Term>, binders: u32| -> Arc<Term> {
+            Arc::new(term.shift(cutoff + binders, amount))
+        };
+        let shift_entries = |terms: &Arc<[Arc<Term>]>| -> Arc<[Arc<Term>]> {
+            (terms.iter().enumerate())
+                .map(|(index, term)| shift(term, index as u32))
+                .collect()
+        };
+
+        let data = match &self.data {
+            TermData::Var(index) if index.to_usize() as u32 >= cutoff => {
+                TermData::Var(VarIndex::new(index.to_usize() as u32 + amount))
+            }
+            TermData::Global(_)
+            | TermData::Var(_)
+            | TermData::TypeType
+            | TermData::Constant(_)
+            | TermData::Meta(_)
+            | TermData::Error => self.data.clone(),
+
+            TermData::Ann(term, r#type) => TermData::Ann(shift(term, 0), shift(r#type, 0)),
+            TermData::FunctionType(plicity, name, input_type, output_type) => {
+                TermData::FunctionType(
+                    *plicity,
+                    name.clone(),
+                    shift(input_type, 0),
+                    shift(output_type, 1),
+                )
+            }
+            TermData::FunctionTerm(plicity, name, output_term) => {
+                TermData::FunctionTerm(*plicity, name.clone(), shift(output_term, 1))
+            }
+            TermData::FunctionElim(head_term, plicity, input_term) => {
+                TermData::FunctionElim(shift(head_term, 0), *plicity, shift(input_term, 0))
+            }
+            TermData::RecordType(labels, docs, types, defaults) => TermData::RecordType(
+                labels.clone(),
+                docs.clone(),
+                shift_entries(types),
+                (defaults.iter().enumerate())
+                    .map(|(index, default)| Some(shift(default.as_ref()?, index as u32)))
+                    .collect(),
+            ),
+            TermData::RecordTerm(labels, terms) => {
+                TermData::RecordTerm(labels.clone(), shift_entries(terms))
+            }
+            TermData::RecordElim(head_term, label) => {
+                TermData::RecordElim(shift(head_term, 0), label.clone())
+            }
+            TermData::VariantType(labels, types) => TermData::VariantType(
+                labels.clone(),
+                types.iter().map(|r#type| shift(r#type, 0)).collect(),
+            ),
+            TermData::VariantTerm(label, term) => {
+                TermData::VariantTerm(label.clone(), shift(term, 0))
+            }
+            TermData::VariantElim(head_term, labels, branches) => TermData::VariantElim(
+                shift(head_term, 0),
+                labels.clone(),
+                branches.iter().map(|branch| shift(branch, 1)).collect(),
+            ),
+            TermData::DataElim(head_term, name, motive, methods) => TermData::DataElim(
+                shift(head_term, 0),
+                name.clone(),
+                shift(motive, 0),
+                methods.iter().map(|method| shift(method, 0)).collect(),
+            ),
+            TermData::ArrayTerm(terms) => {
+                TermData::ArrayTerm(terms.iter().map(|term| shift(term, 0)).collect())
+            }
+            TermData::ListTerm(terms) => {
+                TermData::ListTerm(terms.iter().map(|term| shift(term, 0)).collect())
+            }
+        };
+
+        Term::new(self.location, data)
+    }
+
+    /// Returns `true` if the term refers to the local variable that is bound
+    /// `index` binders outside of the term.
+    pub fn has_var(&self, index: u32) -> bool {
+        let has_var = |term: &Arc<Term>, binders: u32| term.has_var(index + binders);
+        let entries_have_var = |terms: &Arc<[Arc<Term>]>| {
+            (terms.iter().enumerate()).any(|(binders, term)| has_var(term, binders as u32))
+        };
+
+        match &self.data {
+            TermData::Var(var_index) => var_index.to_usize() as u32 == index,
+            TermData::Global(_)
+            | TermData::TypeType
+            | TermData::Constant(_)
+            | TermData::Meta(_)
+            | TermData::Error => false,
+
+            TermData::Ann(term, r#type) => has_var(term, 0) || has_var(r#type, 0),
+            TermData::FunctionType(_, _, input_type, output_type) => {
+                has_var(input_type, 0) || has_var(output_type, 1)
+            }
+            TermData::FunctionTerm(_, _, output_term) => has_var(output_term, 1),
+            TermData::FunctionElim(head_term, _, input_term) => {
+                has_var(head_term, 0) || has_var(input_term, 0)
+            }
+            TermData::RecordType(_, _, types, defaults) => {
+                entries_have_var(types)
+                    || (defaults.iter().enumerate()).any(|(binders, default)| {
+                        matches!(default, Some(default) if has_var(default, binders as u32))
+                    })
+            }
+            TermData::RecordTerm(_, terms) => entries_have_var(terms),
+            TermData::RecordElim(head_term, _) => has_var(head_term, 0),
+            TermData::VariantType(_, types) => types.iter().any(|r#type| has_var(r#type, 0)),
+            TermData::VariantTerm(_, term) => has_var(term, 0),
+            TermData::VariantElim(head_term, _, branches) => {
+                has_var(head_term, 0) || branches.iter().any(|branch| has_var(branch, 1))
+            }
+            TermData::DataElim(head_term, _, motive, methods) => {
+                has_var(head_term, 0)
+                    || has_var(motive, 0)
+                    || methods.iter().any(|method| has_var(method, 0))
+            }
+            TermData::ArrayTerm(terms) | TermData::ListTerm(terms) => {
+                terms.iter().any(|term| has_var(term, 0))
+            }
+        }
+    }
+}
+
 impl From<Constant> for TermData {
     fn from(constant: Constant) -> TermData {
         TermData::Constant(constant)
     }
 }
 
+/// A metavariable, standing in for a term that is not yet known.
+///
+/// Metavariables are created by the elaborator, for example to stand in for
+/// implicit inputs to functions, and are solved by unification. Solutions are
+/// closed values, and are shared between every occurrence of the metavariable.
+pub struct MetaVar {
+    id: usize,
+    solution: OnceCell<Arc<Value>>,
+}
+
+impl MetaVar {
+    /// Create a new, unsolved metavariable.
+    pub fn new(id: usize) -> MetaVar {
+        MetaVar {
+            id,
+            solution: OnceCell::new(),
+        }
+    }
+
+    /// The identifier of the metavariable, for use in diagnostic messages.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The solution of the metavariable, if it has been solved.
+    pub fn solution(&self) -> Option<&Arc<Value>> {
+        self.solution.get()
+    }
+
+    /// Solve the metavariable. Returns `false` if it was already solved.
+    pub fn solve(&self, value: Arc<Value>) -> bool {
+        self.solution.set(value).is_ok()
+    }
+}
+
+impl PartialEq for MetaVar {
+    fn eq(&self, other: &MetaVar) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for MetaVar {}
+
+impl fmt::Debug for MetaVar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetaVar")
+            .field("id", &self.id)
+            .field("is_solved", &self.solution.get().is_some())
+            .finish()
+    }
+}
+
+/// The implementation of a [`Primitive`] operation.
+type PrimitiveFn = dyn Fn(&Globals, &[Arc<Value>]) -> Option<Arc<Value>> + Send + Sync;
+
+/// A primitive operation, implemented in Rust.
+///
+/// Applications of primitives are reduced once they have been supplied with
+/// `arity` inputs. If the primitive cannot be applied to the inputs (for
+/// example if they are not yet known), the application remains stuck.
+#[derive(Clone)]
+pub struct Primitive {
+    arity: usize,
+    apply: Arc<PrimitiveFn>,
+}
+
+impl Primitive {
+    pub fn new(
+        arity: usize,
+        apply: impl Fn(&Globals, &[Arc<Value>]) -> Option<Arc<Value>> + Send + Sync + 'static,
+    ) -> Primitive {
+        Primitive {
+            arity,
+            apply: Arc::new(apply),
+        }
+    }
+
+    /// The number of inputs required before the primitive can be applied.
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    /// Attempt to apply the primitive to some inputs.
+    pub fn apply(&self, globals: &Globals, inputs: &[Arc<Value>]) -> Option<Arc<Value>> {
+        match inputs.len() == self.arity {
+            true => (self.apply)(globals, inputs),
+            false => None,
+        }
+    }
+}
+
+impl fmt::Debug for Primitive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Primitive")
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+/// The built-in types that literals can be parsed as.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LiteralType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    S8,
+    S16,
+    S32,
+    S64,
+    S128,
+    Nat,
+    Int,
+    F32,
+    F64,
+    Char,
+    String,
+    Bytes,
+}
+
+impl LiteralType {
+    /// Lookup the literal type that corresponds to a built-in global.
+    pub fn from_global(name: &str) -> Option<LiteralType> {
+        match name {
+            "U8" => Some(LiteralType::U8),
+            "U16" => Some(LiteralType::U16),
+            "U32" => Some(LiteralType::U32),
+            "U64" => Some(LiteralType::U64),
+            "U128" => Some(LiteralType::U128),
+            "S8" => Some(LiteralType::S8),
+            "S16" => Some(LiteralType::S16),
+            "S32" => Some(LiteralType::S32),
+            "S64" => Some(LiteralType::S64),
+            "S128" => Some(LiteralType::S128),
+            "Nat" => Some(LiteralType::Nat),
+            "Int" => Some(LiteralType::Int),
+            "F32" => Some(LiteralType::F32),
+            "F64" => Some(LiteralType::F64),
+            "Char" => Some(LiteralType::Char),
+            "String" => Some(LiteralType::String),
+            "Bytes" => Some(LiteralType::Bytes),
+            _ => None,
+        }
+    }
+
+    /// The name of the built-in global that corresponds to this literal type.
+    pub fn global_name(self) -> &'static str {
+        match self {
+            LiteralType::U8 => "U8",
+            LiteralType::U16 => "U16",
+            LiteralType::U32 => "U32",
+            LiteralType::U64 => "U64",
+            LiteralType::U128 => "U128",
+            LiteralType::S8 => "S8",
+            LiteralType::S16 => "S16",
+            LiteralType::S32 => "S32",
+            LiteralType::S64 => "S64",
+            LiteralType::S128 => "S128",
+            LiteralType::Nat => "Nat",
+            LiteralType::Int => "Int",
+            LiteralType::F32 => "F32",
+            LiteralType::F64 => "F64",
+            LiteralType::Char => "Char",
+            LiteralType::String => "String",
+            LiteralType::Bytes => "Bytes",
+        }
+    }
+}
+
 /// An environment of global definitions.
+#[derive(Clone)]
 pub struct Globals {
     entries: FxHashMap<String, (Arc<Term>, Option<Arc<Term>>)>,
+    primitives: FxHashMap<String, Primitive>,
+    literal_conversions: FxHashMap<String, (LiteralType, String)>,
+    data_decls: FxHashMap<String, Arc<DataDecl>>,
+    constructors: FxHashMap<String, (String, usize)>,
+    operators: FxHashMap<String, Fixity>,
 }
 
 impl Globals {
     pub fn new(entries: FxHashMap<String, (Arc<Term>, Option<Arc<Term>>)>) -> Globals {
-        Globals { entries }
+        Globals {
+            entries,
+            primitives: FxHashMap::default(),
+            literal_conversions: FxHashMap::default(),
+            data_decls: FxHashMap::default(),
+            constructors: FxHashMap::default(),
+            operators: FxHashMap::default(),
+        }
     }
 
     pub fn get(&self, name: &str) -> Option<&(Arc<Term>, Option<Arc<Term>>)> {
@@ -130,6 +509,140 @@ impl Globals {
     pub fn entries(&self) -> impl Iterator<Item = (&String, &(Arc<Term>, Option<Arc<Term>>))> {
         self.entries.iter()
     }
+
+    /// Lookup the primitive operation associated with a global.
+    pub fn get_primitive(&self, name: &str) -> Option<&Primitive> {
+        self.primitives.get(name)
+    }
+
+    /// Define a global that is implemented by a primitive operation.
+    pub fn define_primitive(
+        &mut self,
+        name: impl Into<String>,
+        r#type: Arc<Term>,
+        primitive: Primitive,
+    ) {
+        let name = name.into();
+        self.entries.insert(name.clone(), (r#type, None));
+        self.primitives.insert(name, primitive);
+    }
+
+    /// Define a global that is implemented by a Rust function, using the
+    /// [marshalling API][marshall] to derive its type and convert its inputs
+    /// and outputs.
+    ///
+    /// For example:
+    ///
+    /// ```rust
+    /// use pikelet::lang::core::Globals;
+    ///
+    /// let mut globals = Globals::default();
+    /// globals.define_foreign("path-join", |base: String, path: String| {
+    ///     format!("{}/{}", base, path)
+    /// });
+    /// ```
+    pub fn define_foreign<Inputs, F: marshall::ForeignFunction<Inputs>>(
+        &mut self,
+        name: impl Into<String>,
+        function: F,
+    ) {
+        self.define_primitive(name, F::r#type(), function.into_primitive());
+    }
+
+    /// Define an abstract global type that has no definition.
+    pub fn define_type(&mut self, name: impl Into<String>) {
+        let r#type = Arc::new(Term::generated(TermData::TypeType));
+        self.entries.insert(name.into(), (r#type, None));
+    }
+
+    /// Lookup the literal conversion associated with a global type.
+    ///
+    /// Returns the type the literal should be parsed as, and the name of the
+    /// global function used to convert it to the global type.
+    pub fn get_literal_conversion(&self, type_name: &str) -> Option<&(LiteralType, String)> {
+        self.literal_conversions.get(type_name)
+    }
+
+    /// Allow literals to be used as elements of a global type.
+    ///
+    /// Literals checked against the global type are first parsed as the
+    /// `literal_type`, and are then supplied to the global function named
+    /// `function_name`. For example:
+    ///
+    /// ```rust
+    /// use pikelet::lang::core::{Globals, LiteralType};
+    ///
+    /// let mut globals = Globals::default();
+    /// globals.define_type("Path");
+    /// // ... define a primitive `string-to-path : String -> Path` ...
+    /// globals.define_literal_conversion("Path", LiteralType::String, "string-to-path");
+    /// ```
+    pub fn define_literal_conversion(
+        &mut self,
+        type_name: impl Into<String>,
+        literal_type: LiteralType,
+        function_name: impl Into<String>,
+    ) {
+        let conversion = (literal_type, function_name.into());
+        self.literal_conversions
+            .insert(type_name.into(), conversion);
+    }
+
+    /// Lookup the fixity of an infix operator.
+    pub fn get_operator(&self, name: &str) -> Option<&Fixity> {
+        self.operators.get(name)
+    }
+
+    /// Define an infix operator, named by its symbol.
+    ///
+    /// Applications of the operator, like `a + b`, are elaborated to
+    /// applications of the global named `+` to the operands.
+    pub fn define_operator(
+        &mut self,
+        name: impl Into<String>,
+        fixity: Fixity,
+        r#type: Arc<Term>,
+        term: Arc<Term>,
+    ) {
+        let name = name.into();
+        self.entries.insert(name.clone(), (r#type, Some(term)));
+        self.operators.insert(name, fixity);
+    }
+
+    /// Lookup a data declaration.
+    pub fn get_data(&self, name: &str) -> Option<&DataDecl> {
+        self.data_decls.get(name).map(Arc::as_ref)
+    }
+
+    /// Lookup the data declaration that a constructor belongs to, along with
+    /// the index of the constructor in the declaration.
+    pub fn get_constructor(&self, name: &str) -> Option<(&DataDecl, usize)> {
+        let (data_name, index) = self.constructors.get(name)?;
+        Some((self.get_data(data_name)?, *index))
+    }
+
+    /// Define a [user-defined data type][data].
+    ///
+    /// This adds globals for the data type, its constructors, and its
+    /// generated eliminator.
+    ///
+    /// [data]: crate::lang::core::data
+    pub fn define_data(&mut self, decl: DataDecl) {
+        let decl = Arc::new(decl);
+
+        self.entries
+            .insert(decl.name.clone(), (decl.r#type(), None));
+        for (index, constructor) in decl.constructors.iter().enumerate() {
+            let r#type = decl.constructor_type(index);
+            self.entries
+                .insert(constructor.name.clone(), (r#type, None));
+            (self.constructors).insert(constructor.name.clone(), (decl.name.clone(), index));
+        }
+        let elim = (decl.elim_type(), Some(decl.elim_term()));
+        self.entries.insert(decl.elim_name(), elim);
+
+        self.data_decls.insert(decl.name.clone(), decl);
+    }
 }
 
 impl Default for Globals {
@@ -140,6 +653,7 @@ impl Default for Globals {
         let type_type = || Arc::new(Term::generated(TermData::TypeType));
         let function_type = |input_type, output_type| {
             Arc::new(Term::generated(TermData::FunctionType(
+                Plicity::Explicit,
                 None,
                 input_type,
                 output_type,
@@ -152,14 +666,19 @@ impl Default for Globals {
         entries.insert("U16".to_owned(), (global("Type"), None));
         entries.insert("U32".to_owned(), (global("Type"), None));
         entries.insert("U64".to_owned(), (global("Type"), None));
+        entries.insert("U128".to_owned(), (global("Type"), None));
         entries.insert("S8".to_owned(), (global("Type"), None));
         entries.insert("S16".to_owned(), (global("Type"), None));
         entries.insert("S32".to_owned(), (global("Type"), None));
         entries.insert("S64".to_owned(), (global("Type"), None));
+        entries.insert("S128".to_owned(), (global("Type"), None));
+        entries.insert("Nat".to_owned(), (global("Type"), None));
+        entries.insert("Int".to_owned(), (global("Type"), None));
         entries.insert("F32".to_owned(), (global("Type"), None));
         entries.insert("F64".to_owned(), (global("Type"), None));
         entries.insert("Char".to_owned(), (global("Type"), None));
         entries.insert("String".to_owned(), (global("Type"), None));
+        entries.insert("Bytes".to_owned(), (global("Type"), None));
         entries.insert("true".to_owned(), (global("Bool"), None));
         entries.insert("false".to_owned(), (global("Bool"), None));
         entries.insert(
@@ -174,7 +693,13 @@ impl Default for Globals {
             (function_type(type_type(), type_type()), None),
         );
 
-        Globals::new(entries)
+        let mut globals = Globals::new(entries);
+        builtins::define_list_primitives(&mut globals);
+        builtins::define_array_primitives(&mut globals);
+        builtins::define_integer_primitives(&mut globals);
+        builtins::define_string_primitives(&mut globals);
+        builtins::define_bytes_primitives(&mut globals);
+        globals
     }
 }
 
@@ -200,6 +725,11 @@ impl Default for Globals {
 pub struct VarIndex(u32);
 
 impl VarIndex {
+    /// Create a variable index from a `u32`.
+    pub fn new(index: u32) -> VarIndex {
+        VarIndex(index)
+    }
+
     /// Convert the variable index to a `usize`.
     pub fn to_usize(self) -> usize {
         self.0 as usize
@@ -257,6 +787,11 @@ impl VarLevel {
 pub struct EnvSize(u32);
 
 impl EnvSize {
+    /// Create an environment size from a `u32`.
+    pub fn new(size: u32) -> EnvSize {
+        EnvSize(size)
+    }
+
     /// Convert the  size to a `usize`.
     pub fn to_usize(self) -> usize {
         self.0 as usize
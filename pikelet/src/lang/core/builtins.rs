@@ -0,0 +1,457 @@
+//! Primitive operations on the built-in types.
+//!
+//! Applications of these operations reduce once they are supplied with all of
+//! their inputs, and the values they operate on are known.
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::Zero;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::lang::core::marshall::Bytes;
+use crate::lang::core::semantics::{self, RecordClosure, Value};
+use crate::lang::core::{Constant, Env, Globals, Primitive, Term, TermData, VarIndex};
+use crate::lang::Plicity;
+
+fn global(name: &str) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::Global(name.to_owned())))
+}
+
+fn var(index: u32) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::Var(VarIndex::new(index))))
+}
+
+fn type_type() -> Arc<Term> {
+    Arc::new(Term::generated(TermData::TypeType))
+}
+
+fn app(head_term: Arc<Term>, input_term: Arc<Term>) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::FunctionElim(
+        head_term,
+        Plicity::Explicit,
+        input_term,
+    )))
+}
+
+/// Bind an implicit input, named `name`.
+fn implicit(name: &str, input_type: Arc<Term>, output_type: Arc<Term>) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::FunctionType(
+        Plicity::Implicit,
+        Some(name.to_owned()),
+        input_type,
+        output_type,
+    )))
+}
+
+/// Bind an explicit input, named `name`.
+fn explicit(name: &str, input_type: Arc<Term>, output_type: Arc<Term>) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::FunctionType(
+        Plicity::Explicit,
+        Some(name.to_owned()),
+        input_type,
+        output_type,
+    )))
+}
+
+/// A non-dependent function type. The variables in the output type are bound
+/// in the same scope as the input type.
+fn arrow(input_type: Arc<Term>, output_type: Arc<Term>) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::FunctionType(
+        Plicity::Explicit,
+        None,
+        input_type,
+        Arc::new(output_type.shift(0, 1)),
+    )))
+}
+
+fn list_type(entry_type: Arc<Term>) -> Arc<Term> {
+    app(global("List"), entry_type)
+}
+
+fn array_type(len: Arc<Term>, entry_type: Arc<Term>) -> Arc<Term> {
+    app(app(global("Array"), len), entry_type)
+}
+
+fn apply(globals: &Globals, function: &Arc<Value>, inputs: &[&Arc<Value>]) -> Arc<Value> {
+    (inputs.iter()).fold(function.clone(), |function, input| {
+        semantics::apply_function_elim(globals, function, Plicity::Explicit, (*input).clone())
+    })
+}
+
+fn u32_value(value: usize) -> Option<Arc<Value>> {
+    let value = u32::try_from(value).ok()?;
+    Some(Arc::new(Value::Constant(Constant::U32(value))))
+}
+
+/// Define the primitive operations on lists.
+pub fn define_list_primitives(globals: &mut Globals) {
+    // list-length : Fun {A : Type} -> List A -> U32
+    globals.define_primitive(
+        "list-length",
+        implicit("A", type_type(), arrow(list_type(var(0)), global("U32"))),
+        Primitive::new(2, |globals, inputs| match inputs[1].force(globals) {
+            Value::ListTerm(entries) => u32_value(entries.len()),
+            _ => None,
+        }),
+    );
+
+    // list-map : Fun {A B : Type} -> (A -> B) -> List A -> List B
+    globals.define_primitive(
+        "list-map",
+        implicit(
+            "A",
+            type_type(),
+            implicit(
+                "B",
+                type_type(),
+                arrow(
+                    arrow(var(1), var(0)),
+                    arrow(list_type(var(1)), list_type(var(0))),
+                ),
+            ),
+        ),
+        Primitive::new(4, |globals, inputs| match inputs[3].force(globals) {
+            Value::ListTerm(entries) => Some(Arc::new(Value::ListTerm(
+                (entries.iter())
+                    .map(|entry| apply(globals, &inputs[2], &[entry]))
+                    .collect(),
+            ))),
+            _ => None,
+        }),
+    );
+
+    // list-filter : Fun {A : Type} -> (A -> Bool) -> List A -> List A
+    globals.define_primitive(
+        "list-filter",
+        implicit(
+            "A",
+            type_type(),
+            arrow(
+                arrow(var(0), global("Bool")),
+                arrow(list_type(var(0)), list_type(var(0))),
+            ),
+        ),
+        Primitive::new(3, |globals, inputs| match inputs[2].force(globals) {
+            Value::ListTerm(entries) => {
+                let mut filtered_entries = Vec::with_capacity(entries.len());
+                for entry in entries {
+                    let keep_entry = apply(globals, &inputs[1], &[entry]);
+                    match keep_entry.force(globals).try_global() {
+                        Some(("true", [])) => filtered_entries.push(entry.clone()),
+                        Some(("false", [])) => {}
+                        // Remain stuck if the predicate does not reduce
+                        _ => return None,
+                    }
+                }
+                Some(Arc::new(Value::ListTerm(filtered_entries)))
+            }
+            _ => None,
+        }),
+    );
+
+    // list-fold : Fun {A B : Type} -> B -> (B -> A -> B) -> List A -> B
+    globals.define_primitive(
+        "list-fold",
+        implicit(
+            "A",
+            type_type(),
+            implicit(
+                "B",
+                type_type(),
+                arrow(
+                    var(0),
+                    arrow(
+                        arrow(var(0), arrow(var(1), var(0))),
+                        arrow(list_type(var(1)), var(0)),
+                    ),
+                ),
+            ),
+        ),
+        Primitive::new(5, |globals, inputs| match inputs[4].force(globals) {
+            Value::ListTerm(entries) => Some(
+                (entries.iter()).fold(inputs[2].clone(), |accumulator, entry| {
+                    apply(globals, &inputs[3], &[&accumulator, entry])
+                }),
+            ),
+            _ => None,
+        }),
+    );
+
+    // list-append : Fun {A : Type} -> List A -> List A -> List A
+    globals.define_primitive(
+        "list-append",
+        implicit(
+            "A",
+            type_type(),
+            arrow(
+                list_type(var(0)),
+                arrow(list_type(var(0)), list_type(var(0))),
+            ),
+        ),
+        Primitive::new(3, |globals, inputs| {
+            match (inputs[1].force(globals), inputs[2].force(globals)) {
+                (Value::ListTerm(entries0), Value::ListTerm(entries1)) => Some(Arc::new(
+                    Value::ListTerm(entries0.iter().chain(entries1).cloned().collect()),
+                )),
+                _ => None,
+            }
+        }),
+    );
+}
+
+/// Define the primitive operations on arrays.
+pub fn define_array_primitives(globals: &mut Globals) {
+    // array-length : Fun {len : U32} {A : Type} -> Array len A -> U32
+    globals.define_primitive(
+        "array-length",
+        implicit(
+            "len",
+            global("U32"),
+            implicit(
+                "A",
+                type_type(),
+                arrow(array_type(var(1), var(0)), global("U32")),
+            ),
+        ),
+        Primitive::new(3, |_, inputs| Some(inputs[0].clone())),
+    );
+
+    // array-map : Fun {len : U32} {A B : Type} -> (A -> B) -> Array len A -> Array len B
+    globals.define_primitive(
+        "array-map",
+        implicit(
+            "len",
+            global("U32"),
+            implicit(
+                "A",
+                type_type(),
+                implicit(
+                    "B",
+                    type_type(),
+                    arrow(
+                        arrow(var(1), var(0)),
+                        arrow(array_type(var(2), var(1)), array_type(var(2), var(0))),
+                    ),
+                ),
+            ),
+        ),
+        Primitive::new(5, |globals, inputs| match inputs[4].force(globals) {
+            Value::ArrayTerm(entries) => Some(Arc::new(Value::ArrayTerm(
+                (entries.iter())
+                    .map(|entry| apply(globals, &inputs[3], &[entry]))
+                    .collect(),
+            ))),
+            _ => None,
+        }),
+    );
+
+    // U32-Lt : U32 -> U32 -> Type
+    //
+    // Evidence that one number is less than another. This reduces to the unit
+    // record type if it holds, and to the empty variant type otherwise.
+    globals.define_primitive(
+        "U32-Lt",
+        arrow(global("U32"), arrow(global("U32"), type_type())),
+        Primitive::new(2, |globals, inputs| {
+            match (inputs[0].force(globals), inputs[1].force(globals)) {
+                (
+                    Value::Constant(Constant::U32(value0)),
+                    Value::Constant(Constant::U32(value1)),
+                ) => Some(Arc::new(match value0 < value1 {
+                    true => Value::RecordType(
+                        Arc::new([]),
+                        RecordClosure::new(Env::new(), Arc::new([])),
+                    ),
+                    false => Value::VariantType(Arc::new([]), Vec::new()),
+                })),
+                _ => None,
+            }
+        }),
+    );
+
+    // array-index : Fun {len : U32} {A : Type} (index : U32) -> Array len A -> U32-Lt index len -> A
+    //
+    // Index into an array. Callers pass `record {}` as the evidence that the
+    // index is in bounds, which only checks if `U32-Lt index len` reduces to
+    // the unit record type. If the index or the length is not a literal then
+    // `U32-Lt index len` is stuck, so the evidence must be taken as an input
+    // instead. An out-of-bounds index is not rejected in this case: indexing
+    // stays stuck until the index and the array are known.
+    globals.define_primitive(
+        "array-index",
+        implicit(
+            "len",
+            global("U32"),
+            implicit(
+                "A",
+                type_type(),
+                explicit(
+                    "index",
+                    global("U32"),
+                    arrow(
+                        array_type(var(2), var(1)),
+                        arrow(app(app(global("U32-Lt"), var(0)), var(2)), var(1)),
+                    ),
+                ),
+            ),
+        ),
+        Primitive::new(5, |globals, inputs| {
+            match (inputs[2].force(globals), inputs[3].force(globals)) {
+                (Value::Constant(Constant::U32(index)), Value::ArrayTerm(entries)) => {
+                    let index = usize::try_from(*index).ok()?;
+                    entries.get(index).cloned()
+                }
+                _ => None,
+            }
+        }),
+    );
+}
+
+/// Define a division-like operation on arbitrary-precision integers, which
+/// remains stuck if the divisor is zero.
+fn define_division_primitive(
+    globals: &mut Globals,
+    name: &str,
+    type_name: &str,
+    operation: fn(&Constant, &Constant) -> Option<Constant>,
+) {
+    globals.define_primitive(
+        name,
+        arrow(
+            global(type_name),
+            arrow(global(type_name), global(type_name)),
+        ),
+        Primitive::new(2, move |globals, inputs| {
+            match (inputs[0].force(globals), inputs[1].force(globals)) {
+                (Value::Constant(constant0), Value::Constant(constant1)) => {
+                    let constant = operation(constant0, constant1)?;
+                    Some(Arc::new(Value::Constant(constant)))
+                }
+                _ => None,
+            }
+        }),
+    );
+}
+
+/// Define the primitive operations on arbitrary-precision integers.
+pub fn define_integer_primitives(globals: &mut Globals) {
+    globals.define_foreign("nat-add", |nat0: BigUint, nat1: BigUint| nat0 + nat1);
+    // Truncated subtraction, so that the result remains a natural number
+    globals.define_foreign("nat-sub", |nat0: BigUint, nat1: BigUint| {
+        match nat0 < nat1 {
+            true => BigUint::zero(),
+            false => nat0 - nat1,
+        }
+    });
+    globals.define_foreign("nat-mul", |nat0: BigUint, nat1: BigUint| nat0 * nat1);
+    define_division_primitive(globals, "nat-div", "Nat", |nat0, nat1| match (nat0, nat1) {
+        (Constant::Nat(nat0), Constant::Nat(nat1)) if !nat1.is_zero() => {
+            Some(Constant::Nat(nat0 / nat1))
+        }
+        _ => None,
+    });
+    define_division_primitive(globals, "nat-rem", "Nat", |nat0, nat1| match (nat0, nat1) {
+        (Constant::Nat(nat0), Constant::Nat(nat1)) if !nat1.is_zero() => {
+            Some(Constant::Nat(nat0 % nat1))
+        }
+        _ => None,
+    });
+    globals.define_foreign("nat-eq", |nat0: BigUint, nat1: BigUint| nat0 == nat1);
+    globals.define_foreign("nat-lt", |nat0: BigUint, nat1: BigUint| nat0 < nat1);
+    globals.define_foreign("nat-to-int", <BigInt as From<BigUint>>::from);
+
+    globals.define_foreign("int-add", |int0: BigInt, int1: BigInt| int0 + int1);
+    globals.define_foreign("int-sub", |int0: BigInt, int1: BigInt| int0 - int1);
+    globals.define_foreign("int-mul", |int0: BigInt, int1: BigInt| int0 * int1);
+    define_division_primitive(globals, "int-div", "Int", |int0, int1| match (int0, int1) {
+        (Constant::Int(int0), Constant::Int(int1)) if !int1.is_zero() => {
+            Some(Constant::Int(int0 / int1))
+        }
+        _ => None,
+    });
+    define_division_primitive(globals, "int-rem", "Int", |int0, int1| match (int0, int1) {
+        (Constant::Int(int0), Constant::Int(int1)) if !int1.is_zero() => {
+            Some(Constant::Int(int0 % int1))
+        }
+        _ => None,
+    });
+    globals.define_foreign("int-neg", |int: BigInt| -int);
+    globals.define_foreign("int-eq", |int0: BigInt, int1: BigInt| int0 == int1);
+    globals.define_foreign("int-lt", |int0: BigInt, int1: BigInt| int0 < int1);
+    globals.define_foreign("int-abs", |int: BigInt| int.magnitude().clone());
+}
+
+/// Define the primitive operations on byte strings.
+pub fn define_bytes_primitives(globals: &mut Globals) {
+    // bytes-length : Bytes -> U32
+    globals.define_primitive(
+        "bytes-length",
+        arrow(global("Bytes"), global("U32")),
+        Primitive::new(1, |globals, inputs| match inputs[0].force(globals) {
+            Value::Constant(Constant::Bytes(bytes)) => u32_value(bytes.len()),
+            _ => None,
+        }),
+    );
+    globals.define_foreign("bytes-eq", |bytes0: Bytes, bytes1: Bytes| bytes0 == bytes1);
+}
+
+/// Define the primitive operations on strings, along with conversions of the
+/// other built-in types to strings.
+pub fn define_string_primitives(globals: &mut Globals) {
+    globals.define_foreign("string-append", |string0: String, string1: String| {
+        string0 + &string1
+    });
+    // string-length : String -> U32
+    globals.define_primitive(
+        "string-length",
+        arrow(global("String"), global("U32")),
+        Primitive::new(1, |globals, inputs| match inputs[0].force(globals) {
+            Value::Constant(Constant::String(string)) => u32_value(string.chars().count()),
+            _ => None,
+        }),
+    );
+    globals.define_foreign("string-eq", |string0: String, string1: String| {
+        string0 == string1
+    });
+
+    globals.define_foreign("bool-to-string", |value: bool| value.to_string());
+    globals.define_foreign("u8-to-string", |value: u8| value.to_string());
+    globals.define_foreign("u16-to-string", |value: u16| value.to_string());
+    globals.define_foreign("u32-to-string", |value: u32| value.to_string());
+    globals.define_foreign("u64-to-string", |value: u64| value.to_string());
+    globals.define_foreign("u128-to-string", |value: u128| value.to_string());
+    globals.define_foreign("s8-to-string", |value: i8| value.to_string());
+    globals.define_foreign("s16-to-string", |value: i16| value.to_string());
+    globals.define_foreign("s32-to-string", |value: i32| value.to_string());
+    globals.define_foreign("s64-to-string", |value: i64| value.to_string());
+    globals.define_foreign("s128-to-string", |value: i128| value.to_string());
+    globals.define_foreign("nat-to-string", |value: BigUint| value.to_string());
+    globals.define_foreign("int-to-string", |value: BigInt| value.to_string());
+    globals.define_foreign("f32-to-string", |value: f32| value.to_string());
+    globals.define_foreign("f64-to-string", |value: f64| value.to_string());
+    globals.define_foreign("char-to-string", |value: char| value.to_string());
+}
+
+/// The name of the primitive that converts elements of a built-in type to
+/// strings, if one exists.
+pub fn to_string_name(type_name: &str) -> Option<&'static str> {
+    match type_name {
+        "Bool" => Some("bool-to-string"),
+        "U8" => Some("u8-to-string"),
+        "U16" => Some("u16-to-string"),
+        "U32" => Some("u32-to-string"),
+        "U64" => Some("u64-to-string"),
+        "U128" => Some("u128-to-string"),
+        "S8" => Some("s8-to-string"),
+        "S16" => Some("s16-to-string"),
+        "S32" => Some("s32-to-string"),
+        "S64" => Some("s64-to-string"),
+        "S128" => Some("s128-to-string"),
+        "Nat" => Some("nat-to-string"),
+        "Int" => Some("int-to-string"),
+        "F32" => Some("f32-to-string"),
+        "F64" => Some("f64-to-string"),
+        "Char" => Some("char-to-string"),
+        _ => None,
+    }
+}
@@ -0,0 +1,435 @@
+//! User-defined inductive data types.
+//!
+//! A [data declaration][DataDecl] introduces a number of globals:
+//!
+//! - a type constructor, for example `List : Fun (A : Type) -> Type`
+//! - a global for each constructor, for example
+//!   `cons : Fun {A : Type} (head : A) (tail : List A) -> List A`
+//! - a dependent eliminator, for example `List-elim`, that is defined in terms
+//!   of [data eliminations][TermData::DataElim]
+//!
+//! Data types can be parameterised, but the parameters must be uniform: every
+//! constructor returns the data type applied to the same parameters, and
+//! recursive fields must also use these parameters. Indexed families are not
+//! yet supported.
+
+use std::sync::Arc;
+
+use crate::lang::core::{Term, TermData, VarIndex};
+use crate::lang::Plicity;
+
+/// A data declaration.
+#[derive(Clone, Debug)]
+pub struct DataDecl {
+    /// The name of the data type.
+    pub name: String,
+    /// The parameters of the data type.
+    ///
+    /// The type of each parameter is bound in the scope of the parameters
+    /// that precede it.
+    pub params: Vec<(String, Arc<Term>)>,
+    /// The constructors of the data type.
+    pub constructors: Vec<DataConstructor>,
+}
+
+/// A constructor of a [data type][DataDecl].
+#[derive(Clone, Debug)]
+pub struct DataConstructor {
+    /// The name of the constructor.
+    pub name: String,
+    /// The fields of the constructor.
+    ///
+    /// The type of each field is bound in the scope of the parameters of the
+    /// data type, followed by the fields that precede it. Recursive
+    /// occurrences of the data type are referred to as globals.
+    pub fields: Vec<(String, Arc<Term>)>,
+}
+
+/// The ways in which a data type can occur illegally in the type of a field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Occurrence {
+    /// The data type occurs in a strictly positive position, but not as the
+    /// type of the field itself. For example `Fun (n : Nat) -> Tree` or
+    /// `List Tree`.
+    Nested,
+    /// The data type occurs to the left of a function arrow. For example
+    /// `Fun (x : Term) -> Term`.
+    Negative,
+}
+
+/// An error found when checking that a data declaration is [strictly
+/// positive][`DataDecl::check_positivity`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PositivityError {
+    /// The index of the constructor containing the occurrence.
+    pub constructor_index: usize,
+    /// The index of the field containing the occurrence.
+    pub field_index: usize,
+    /// How the data type occurs in the type of the field.
+    pub occurrence: Occurrence,
+}
+
+/// The name of the eliminator that is generated for a data type.
+pub fn elim_name(data_name: &str) -> String {
+    format!("{}-elim", data_name)
+}
+
+/// The name of the input to the eliminator that handles a constructor.
+fn method_name(constructor_name: &str) -> String {
+    format!("on-{}", constructor_name)
+}
+
+fn var(index: usize) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::Var(VarIndex::new(index as u32))))
+}
+
+fn global(name: &str) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::Global(name.to_owned())))
+}
+
+fn function_type(
+    plicity: Plicity,
+    name: Option<&str>,
+    input_type: Arc<Term>,
+    output_type: Arc<Term>,
+) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::FunctionType(
+        plicity,
+        name.map(str::to_owned),
+        input_type,
+        output_type,
+    )))
+}
+
+fn function_elim(head_term: Arc<Term>, plicity: Plicity, input_term: Arc<Term>) -> Arc<Term> {
+    Arc::new(Term::generated(TermData::FunctionElim(
+        head_term, plicity, input_term,
+    )))
+}
+
+impl DataDecl {
+    /// The name of the eliminator that is generated for the data type.
+    pub fn elim_name(&self) -> String {
+        elim_name(&self.name)
+    }
+
+    /// Apply the data type to its parameters, where `binders` is the number
+    /// of binders between the parameters and the application.
+    fn applied_type(&self, binders: usize) -> Arc<Term> {
+        (self.params.iter().enumerate()).fold(global(&self.name), |head_term, (index, _)| {
+            let param = var(binders + self.params.len() - 1 - index);
+            function_elim(head_term, Plicity::Explicit, param)
+        })
+    }
+
+    /// Returns `true` if the type of a field is a recursive occurrence of the
+    /// data type, applied to its parameters. `field_index` is the number of
+    /// fields that precede the field in the constructor.
+    pub fn is_recursive_field(&self, field_index: usize, field_type: &Term) -> bool {
+        let mut field_type = field_type;
+        for param_index in (0..self.params.len()).rev() {
+            match &field_type.data {
+                TermData::FunctionElim(head_term, Plicity::Explicit, input_term) => {
+                    match &input_term.data {
+                        TermData::Var(index)
+                            if index.to_usize()
+                                == field_index + self.params.len() - 1 - param_index => {}
+                        _ => return false,
+                    }
+                    field_type = head_term;
+                }
+                _ => return false,
+            }
+        }
+
+        matches!(&field_type.data, TermData::Global(name) if *name == self.name)
+    }
+
+    /// Check that the data type only occurs in the fields of its constructors
+    /// as the type of a field, applied to its parameters.
+    ///
+    /// This ensures that the data type is [strictly positive], which is
+    /// required for the generated eliminator to be consistent.
+    ///
+    /// [strictly positive]: https://counterexamples.org/strict-positivity.html
+    pub fn check_positivity(&self) -> Result<(), PositivityError> {
+        for (constructor_index, constructor) in self.constructors.iter().enumerate() {
+            for (field_index, (_, field_type)) in constructor.fields.iter().enumerate() {
+                if self.is_recursive_field(field_index, field_type) {
+                    continue;
+                }
+                if let Some(occurrence) = self.find_occurrence(field_type, true) {
+                    return Err(PositivityError {
+                        constructor_index,
+                        field_index,
+                        occurrence,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find the worst occurrence of the data type in a term, where
+    /// `is_positive` is `true` if the term is to the left of an even number of
+    /// function arrows.
+    fn find_occurrence(&self, term: &Term, is_positive: bool) -> Option<Occurrence> {
+        let find = |term: &Arc<Term>| self.find_occurrence(term, is_positive);
+        let find_all = |terms: &[Arc<Term>]| terms.iter().filter_map(find).max();
+
+        match &term.data {
+            TermData::Global(name) if *name == self.name => match is_positive {
+                true => Some(Occurrence::Nested),
+                false => Some(Occurrence::Negative),
+            },
+            TermData::Global(_)
+            | TermData::Var(_)
+            | TermData::TypeType
+            | TermData::Constant(_)
+            | TermData::Meta(_)
+            | TermData::Error => None,
+
+            TermData::FunctionType(_, _, input_type, output_type) => Option::max(
+                self.find_occurrence(input_type, !is_positive),
+                find(output_type),
+            ),
+            TermData::Ann(term0, term1) | TermData::FunctionElim(term0, _, term1) => {
+                Option::max(find(term0), find(term1))
+            }
+            TermData::FunctionTerm(_, _, term)
+            | TermData::RecordElim(term, _)
+            | TermData::VariantTerm(_, term) => find(term),
+            TermData::RecordType(_, _, types, defaults) => Option::max(
+                find_all(types),
+                defaults.iter().flatten().filter_map(find).max(),
+            ),
+            TermData::RecordTerm(_, terms) | TermData::VariantType(_, terms) => find_all(terms),
+            TermData::VariantElim(head_term, _, branches) => {
+                Option::max(find(head_term), find_all(branches))
+            }
+            TermData::DataElim(head_term, _, motive, methods) => Option::max(
+                Option::max(find(head_term), find(motive)),
+                find_all(methods),
+            ),
+            TermData::ArrayTerm(terms) | TermData::ListTerm(terms) => find_all(terms),
+        }
+    }
+
+    /// The type of the data type.
+    ///
+    /// ```text
+    /// Fun (params...) -> Type
+    /// ```
+    pub fn r#type(&self) -> Arc<Term> {
+        let type_type = Arc::new(Term::generated(TermData::TypeType));
+        (self.params.iter().rev()).fold(type_type, |output_type, (name, r#type)| {
+            function_type(Plicity::Explicit, Some(name), r#type.clone(), output_type)
+        })
+    }
+
+    /// The type of the constructor at `index`.
+    ///
+    /// ```text
+    /// Fun {params...} (fields...) -> Name params...
+    /// ```
+    pub fn constructor_type(&self, index: usize) -> Arc<Term> {
+        let fields = &self.constructors[index].fields;
+        let output_type = self.applied_type(fields.len());
+
+        let output_type = (fields.iter().rev()).fold(output_type, |output_type, (name, r#type)| {
+            function_type(Plicity::Explicit, Some(name), r#type.clone(), output_type)
+        });
+        (self.params.iter().rev()).fold(output_type, |output_type, (name, r#type)| {
+            function_type(Plicity::Implicit, Some(name), r#type.clone(), output_type)
+        })
+    }
+
+    /// The type of the method that is supplied to the eliminator for the
+    /// constructor at `index`, bound in the scope of the parameters, the
+    /// motive, and the methods of the preceding constructors.
+    ///
+    /// ```text
+    /// Fun (fields...) (field-ih : motive field)... -> motive (constructor {params...} fields...)
+    /// ```
+    fn method_type(&self, index: usize) -> Arc<Term> {
+        let constructor = &self.constructors[index];
+        let field_count = constructor.fields.len();
+        let recursive_fields = (constructor.fields.iter().enumerate())
+            .filter(|(field_index, (_, r#type))| self.is_recursive_field(*field_index, r#type))
+            .map(|(field_index, _)| field_index)
+            .collect::<Vec<_>>();
+
+        // The output type, bound under the fields and the induction hypotheses.
+        let binders = field_count + recursive_fields.len();
+        let constructor_term =
+            (0..self.params.len()).fold(global(&constructor.name), |head_term, param_index| {
+                let param = var(self.params.len() + index + binders - param_index);
+                function_elim(head_term, Plicity::Implicit, param)
+            });
+        let constructor_term = (0..field_count).fold(constructor_term, |head_term, field_index| {
+            let field = var(binders - 1 - field_index);
+            function_elim(head_term, Plicity::Explicit, field)
+        });
+        let output_type = function_elim(var(index + binders), Plicity::Explicit, constructor_term);
+
+        // The induction hypotheses, bound under the fields and the preceding
+        // induction hypotheses.
+        let output_type = (recursive_fields.iter().enumerate().rev()).fold(
+            output_type,
+            |output_type, (ih_index, field_index)| {
+                let motive = var(index + field_count + ih_index);
+                let field = var(field_count + ih_index - 1 - field_index);
+                let ih_type = function_elim(motive, Plicity::Explicit, field);
+                let ih_name = format!("{}-ih", constructor.fields[*field_index].0);
+                function_type(Plicity::Explicit, Some(&ih_name), ih_type, output_type)
+            },
+        );
+
+        // The fields, shifted past the motive and the preceding methods.
+        (constructor.fields.iter().enumerate().rev()).fold(
+            output_type,
+            |output_type, (field_index, (name, r#type))| {
+                let r#type = Arc::new(r#type.shift(field_index as u32, index as u32 + 1));
+                function_type(Plicity::Explicit, Some(name), r#type, output_type)
+            },
+        )
+    }
+
+    /// The type of the generated eliminator.
+    ///
+    /// ```text
+    /// Fun {params...}
+    ///     (motive : Name params... -> Type)
+    ///     (methods...)
+    ///     (target : Name params...)
+    ///     -> motive target
+    /// ```
+    pub fn elim_type(&self) -> Arc<Term> {
+        let constructor_count = self.constructors.len();
+
+        let output_type = function_elim(var(constructor_count + 1), Plicity::Explicit, var(0));
+        let target_type = self.applied_type(constructor_count + 1);
+        let output_type =
+            function_type(Plicity::Explicit, Some("target"), target_type, output_type);
+
+        let output_type = (self.constructors.iter().enumerate().rev()).fold(
+            output_type,
+            |output_type, (index, constructor)| {
+                let method_type = self.method_type(index);
+                let method_name = method_name(&constructor.name);
+                function_type(
+                    Plicity::Explicit,
+                    Some(&method_name),
+                    method_type,
+                    output_type,
+                )
+            },
+        );
+
+        let type_type = Arc::new(Term::generated(TermData::TypeType));
+        let motive_type = function_type(Plicity::Explicit, None, self.applied_type(0), type_type);
+        let output_type =
+            function_type(Plicity::Explicit, Some("motive"), motive_type, output_type);
+
+        (self.params.iter().rev()).fold(output_type, |output_type, (name, r#type)| {
+            function_type(Plicity::Implicit, Some(name), r#type.clone(), output_type)
+        })
+    }
+
+    /// The definition of the generated eliminator.
+    ///
+    /// ```text
+    /// fun {params...} motive methods... target => <data elimination>
+    /// ```
+    pub fn elim_term(&self) -> Arc<Term> {
+        let constructor_count = self.constructors.len();
+        let methods = (0..constructor_count)
+            .map(|index| var(constructor_count - index))
+            .collect();
+        let output_term = Arc::new(Term::generated(TermData::DataElim(
+            var(0),
+            self.name.clone(),
+            var(constructor_count + 1),
+            methods,
+        )));
+
+        let function_term = |plicity, name: &str, output_term| {
+            Arc::new(Term::generated(TermData::FunctionTerm(
+                plicity,
+                name.to_owned(),
+                output_term,
+            )))
+        };
+
+        let output_term = function_term(Plicity::Explicit, "target", output_term);
+        let output_term =
+            (self.constructors.iter().rev()).fold(output_term, |output_term, constructor| {
+                function_term(
+                    Plicity::Explicit,
+                    &method_name(&constructor.name),
+                    output_term,
+                )
+            });
+        let output_term = function_term(Plicity::Explicit, "motive", output_term);
+        (self.params.iter().rev()).fold(output_term, |output_term, (name, _)| {
+            function_term(Plicity::Implicit, name, output_term)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nat_decl(succ_field_type: Arc<Term>) -> DataDecl {
+        DataDecl {
+            name: "Nat".to_owned(),
+            params: Vec::new(),
+            constructors: vec![
+                DataConstructor {
+                    name: "zero".to_owned(),
+                    fields: Vec::new(),
+                },
+                DataConstructor {
+                    name: "succ".to_owned(),
+                    fields: vec![("pred".to_owned(), succ_field_type)],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn positivity() {
+        let nat = || global("Nat");
+        let arrow = |input_type, output_type| {
+            function_type(Plicity::Explicit, None, input_type, output_type)
+        };
+
+        assert_eq!(nat_decl(nat()).check_positivity(), Ok(()));
+        assert_eq!(
+            nat_decl(arrow(global("U32"), nat())).check_positivity(),
+            Err(PositivityError {
+                constructor_index: 1,
+                field_index: 0,
+                occurrence: Occurrence::Nested,
+            }),
+        );
+        assert_eq!(
+            nat_decl(arrow(arrow(nat(), global("U32")), global("U32"))).check_positivity(),
+            Err(PositivityError {
+                constructor_index: 1,
+                field_index: 0,
+                occurrence: Occurrence::Nested,
+            }),
+        );
+        assert_eq!(
+            nat_decl(arrow(nat(), nat())).check_positivity(),
+            Err(PositivityError {
+                constructor_index: 1,
+                field_index: 0,
+                occurrence: Occurrence::Negative,
+            }),
+        );
+    }
+}
@@ -1,8 +1,30 @@
 //! Marshalling API between Rust types and Pikelet's core language.
 
+use num_bigint::{BigInt, BigUint};
 use std::sync::Arc;
 
-use crate::lang::core::{Constant, Term, TermData};
+use crate::lang::core::semantics::{self, Head, Value};
+use crate::lang::core::{Constant, Env, Globals, Primitive, Term, TermData};
+use crate::lang::Plicity;
+
+/// A sequence of bytes, marshalled as an element of the `Bytes` type.
+///
+/// `Vec<u8>` is marshalled as a `List U8`, so this wrapper is needed in order
+/// to use the more compact representation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bytes(pub Vec<u8>);
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Bytes {
+        Bytes(bytes)
+    }
+}
+
+impl From<Bytes> for Vec<u8> {
+    fn from(bytes: Bytes) -> Vec<u8> {
+        bytes.0
+    }
+}
 
 pub trait HasType {
     fn r#type() -> Arc<Term>;
@@ -23,10 +45,14 @@ impl_has_type!(u8, Term::generated(TermData::Global("U8".to_owned())));
 impl_has_type!(u16, Term::generated(TermData::Global("U16".to_owned())));
 impl_has_type!(u32, Term::generated(TermData::Global("U32".to_owned())));
 impl_has_type!(u64, Term::generated(TermData::Global("U64".to_owned())));
+impl_has_type!(u128, Term::generated(TermData::Global("U128".to_owned())));
 impl_has_type!(i8, Term::generated(TermData::Global("S8".to_owned())));
 impl_has_type!(i16, Term::generated(TermData::Global("S16".to_owned())));
 impl_has_type!(i32, Term::generated(TermData::Global("S32".to_owned())));
 impl_has_type!(i64, Term::generated(TermData::Global("S64".to_owned())));
+impl_has_type!(i128, Term::generated(TermData::Global("S128".to_owned())));
+impl_has_type!(BigUint, Term::generated(TermData::Global("Nat".to_owned())));
+impl_has_type!(BigInt, Term::generated(TermData::Global("Int".to_owned())));
 impl_has_type!(f32, Term::generated(TermData::Global("F32".to_owned())));
 impl_has_type!(f64, Term::generated(TermData::Global("F64".to_owned())));
 impl_has_type!(char, Term::generated(TermData::Global("Char".to_owned())));
@@ -35,11 +61,13 @@ impl_has_type!(
     Term::generated(TermData::Global("String".to_owned()))
 );
 impl_has_type!(str, Term::generated(TermData::Global("String".to_owned())));
+impl_has_type!(Bytes, Term::generated(TermData::Global("Bytes".to_owned())));
 
 impl<T: HasType> HasType for Vec<T> {
     fn r#type() -> Arc<Term> {
         Arc::new(Term::generated(TermData::FunctionElim(
             Arc::new(Term::generated(TermData::Global("List".to_owned()))),
+            Plicity::Explicit,
             T::r#type(),
         )))
     }
@@ -49,9 +77,11 @@ impl<T: HasType, const LEN: usize /* TODO: u32 */> HasType for [T; LEN] {
     fn r#type() -> Arc<Term> {
         Arc::new(Term::generated(TermData::FunctionElim(
             Arc::new(Term::generated(TermData::FunctionElim(
-                Arc::new(Term::generated(TermData::Global("List".to_owned()))),
+                Arc::new(Term::generated(TermData::Global("Array".to_owned()))),
+                Plicity::Explicit,
                 Arc::new(Term::generated(TermData::from(Constant::U32(LEN as u32)))), // FIXME: this could overflow!
             ))),
+            Plicity::Explicit,
             T::r#type(),
         )))
     }
@@ -94,16 +124,27 @@ impl_try_from_term!(u8, |TermData::Constant(Constant::U8(value))| Ok(*value));
 impl_try_from_term!(u16, |TermData::Constant(Constant::U16(value))| Ok(*value));
 impl_try_from_term!(u32, |TermData::Constant(Constant::U32(value))| Ok(*value));
 impl_try_from_term!(u64, |TermData::Constant(Constant::U64(value))| Ok(*value));
+impl_try_from_term!(u128, |TermData::Constant(Constant::U128(value))| Ok(*value));
 impl_try_from_term!(i8, |TermData::Constant(Constant::S8(value))| Ok(*value));
 impl_try_from_term!(i16, |TermData::Constant(Constant::S16(value))| Ok(*value));
 impl_try_from_term!(i32, |TermData::Constant(Constant::S32(value))| Ok(*value));
 impl_try_from_term!(i64, |TermData::Constant(Constant::S64(value))| Ok(*value));
+impl_try_from_term!(i128, |TermData::Constant(Constant::S128(value))| Ok(*value));
+impl_try_from_term!(BigUint, |TermData::Constant(Constant::Nat(value))| Ok(
+    value.clone(),
+));
+impl_try_from_term!(BigInt, |TermData::Constant(Constant::Int(value))| Ok(
+    value.clone(),
+));
 impl_try_from_term!(f32, |TermData::Constant(Constant::F32(value))| Ok(*value));
 impl_try_from_term!(f64, |TermData::Constant(Constant::F64(value))| Ok(*value));
 impl_try_from_term!(char, |TermData::Constant(Constant::Char(value))| Ok(*value));
 impl_try_from_term!(String, |TermData::Constant(Constant::String(value))| Ok(
     value.clone(),
 ));
+impl_try_from_term!(Bytes, |TermData::Constant(Constant::Bytes(value))| Ok(
+    Bytes(value.clone()),
+));
 
 impl<T: TryFromTerm> TryFromTerm for Vec<T> {
     type Error = ();
@@ -183,10 +224,16 @@ impl_to_term!(u8, |value| TermData::from(Constant::U8(*value)));
 impl_to_term!(u16, |value| TermData::from(Constant::U16(*value)));
 impl_to_term!(u32, |value| TermData::from(Constant::U32(*value)));
 impl_to_term!(u64, |value| TermData::from(Constant::U64(*value)));
+impl_to_term!(u128, |value| TermData::from(Constant::U128(*value)));
 impl_to_term!(i8, |value| TermData::from(Constant::S8(*value)));
 impl_to_term!(i16, |value| TermData::from(Constant::S16(*value)));
 impl_to_term!(i32, |value| TermData::from(Constant::S32(*value)));
 impl_to_term!(i64, |value| TermData::from(Constant::S64(*value)));
+impl_to_term!(i128, |value| TermData::from(Constant::S128(*value)));
+impl_to_term!(BigUint, |value| TermData::from(Constant::Nat(
+    value.clone()
+)));
+impl_to_term!(BigInt, |value| TermData::from(Constant::Int(value.clone())));
 impl_to_term!(f32, |value| TermData::from(Constant::F32(*value)));
 impl_to_term!(f64, |value| TermData::from(Constant::F64(*value)));
 impl_to_term!(char, |value| TermData::from(Constant::Char(*value)));
@@ -196,6 +243,9 @@ impl_to_term!(String, |value| TermData::from(Constant::String(
 impl_to_term!(str, |value| TermData::from(Constant::String(
     value.to_owned()
 )));
+impl_to_term!(Bytes, |value| TermData::from(Constant::Bytes(
+    value.0.clone()
+)));
 
 impl<T: ToTerm> ToTerm for Vec<T> {
     fn to_term(&self) -> Term {
@@ -212,3 +262,80 @@ impl<T: ToTerm, const LEN: usize> ToTerm for [T; LEN] {
         ))
     }
 }
+
+/// Attempt to convert a [`Value`] into a closed `Term`, returning `None` if the
+/// value is not yet known (for example if it depends on a local variable).
+fn value_to_term(globals: &Globals, value: &Value) -> Option<Term> {
+    match value.force(globals) {
+        Value::Stuck(Head::Global(name), spine) if spine.is_empty() => {
+            Some(Term::generated(TermData::Global(name.clone())))
+        }
+        Value::ArrayTerm(value_entries) => Some(Term::generated(TermData::ArrayTerm(
+            (value_entries.iter())
+                .map(|value_entry| value_to_term(globals, value_entry).map(Arc::new))
+                .collect::<Option<_>>()?,
+        ))),
+        Value::ListTerm(value_entries) => Some(Term::generated(TermData::ListTerm(
+            (value_entries.iter())
+                .map(|value_entry| value_to_term(globals, value_entry).map(Arc::new))
+                .collect::<Option<_>>()?,
+        ))),
+        Value::Constant(constant) => Some(Term::generated(TermData::from(constant.clone()))),
+        _ => None,
+    }
+}
+
+/// Rust functions that can be called from Pikelet.
+///
+/// Inputs are converted from values using [`TryFromTerm`], and the output is
+/// converted back using [`ToTerm`]. The `Inputs` parameter is used to
+/// distinguish implementations for functions of different arities.
+pub trait ForeignFunction<Inputs>: Send + Sync + 'static {
+    /// The type of the function.
+    fn r#type() -> Arc<Term>;
+
+    /// Convert the function into a primitive operation.
+    fn into_primitive(self) -> Primitive;
+}
+
+macro_rules! impl_foreign_function {
+    ($($Input:ident),+) => {
+        impl<F, $($Input,)+ Output> ForeignFunction<($($Input,)+)> for F
+        where
+            F: Fn($($Input),+) -> Output + Send + Sync + 'static,
+            $($Input: TryFromTerm,)+
+            Output: ToTerm,
+        {
+            fn r#type() -> Arc<Term> {
+                let input_types = [$($Input::r#type()),+];
+                (input_types.iter().rev()).fold(Output::r#type(), |output_type, input_type| {
+                    Arc::new(Term::generated(TermData::FunctionType(
+                        Plicity::Explicit,
+                        None,
+                        input_type.clone(),
+                        output_type,
+                    )))
+                })
+            }
+
+            #[allow(non_snake_case)]
+            fn into_primitive(self) -> Primitive {
+                let arity = [$(stringify!($Input)),+].len();
+
+                Primitive::new(arity, move |globals, inputs| match inputs {
+                    [$($Input),+] => {
+                        $(let $Input = $Input::try_from_term(&value_to_term(globals, $Input)?).ok()?;)+
+                        let output = self($($Input),+).to_term();
+                        Some(semantics::eval(globals, &mut Env::new(), &output))
+                    }
+                    _ => None,
+                })
+            }
+        }
+    };
+}
+
+impl_foreign_function!(A);
+impl_foreign_function!(A, B);
+impl_foreign_function!(A, B, C);
+impl_foreign_function!(A, B, C, D);
@@ -7,7 +7,10 @@ use once_cell::sync::OnceCell;
 use std::cell::RefCell;
 use std::sync::Arc;
 
-use crate::lang::core::{Constant, Env, EnvSize, Globals, Term, TermData, VarLevel};
+use crate::lang::core::{
+    Constant, Env, EnvSize, Globals, MetaVar, Primitive, Term, TermData, VarLevel,
+};
+use crate::lang::Plicity;
 
 /// Values in the core language.
 #[derive(Clone, Debug)]
@@ -46,17 +49,22 @@ pub enum Value {
     /// Function types.
     ///
     /// Also known as: pi type, dependent product type.
-    FunctionType(Option<String>, Arc<Value>, FunctionClosure),
+    FunctionType(Plicity, Option<String>, Arc<Value>, FunctionClosure),
     /// Function terms.
     ///
     /// Also known as: lambda abstraction, anonymous function.
-    FunctionTerm(String, FunctionClosure),
+    FunctionTerm(Plicity, String, FunctionClosure),
 
     /// Record types.
     RecordType(Arc<[String]>, RecordClosure),
     /// Record terms.
     RecordTerm(Arc<[String]>, RecordClosure),
 
+    /// Variant types.
+    VariantType(Arc<[String]>, Vec<Arc<Value>>),
+    /// Variant terms.
+    VariantTerm(String, Arc<Value>),
+
     /// Array terms.
     ArrayTerm(Vec<Arc<Value>>),
     /// List terms.
@@ -80,6 +88,11 @@ impl Value {
         Value::Stuck(Head::Var(level), elims.into())
     }
 
+    /// Create a metavariable.
+    pub fn meta(meta: Arc<MetaVar>, elims: impl Into<Vec<Elim>>) -> Value {
+        Value::Stuck(Head::Meta(meta), elims.into())
+    }
+
     /// Attempt to match against a stuck global.
     ///
     /// This can help to clean up pattern matches in lieu of
@@ -111,6 +124,8 @@ pub enum Head {
     Global(String),
     /// Local variables.
     Var(VarLevel),
+    /// Metavariables that have not yet been solved.
+    Meta(Arc<MetaVar>),
 }
 
 /// An eliminator that is part of the spine of a [stuck value][`Value::Stuck`].
@@ -124,7 +139,7 @@ pub enum Elim {
     /// [`apply_function_elim`] function.
     ///
     /// Also known as: function application.
-    Function(Arc<LazyValue>),
+    Function(Plicity, Arc<LazyValue>),
     /// Record eliminators.
     ///
     /// This eliminator can be applied to a [`Value`] with the
@@ -132,6 +147,17 @@ pub enum Elim {
     ///
     /// Also known as: record projections, field lookup.
     Record(String),
+    /// Variant eliminators.
+    ///
+    /// Also known as: match, case analysis.
+    Variant(BranchClosure),
+    /// Data eliminators.
+    ///
+    /// Each eliminator has the name of the data type, the motive, and the
+    /// methods for each of the constructors of the data type.
+    ///
+    /// Also known as: recursion, induction.
+    Data(String, Arc<Value>, Arc<[Arc<Value>]>),
 }
 
 /// Function closure, capturing the current values in scope.
@@ -159,11 +185,67 @@ impl FunctionClosure {
 pub struct RecordClosure {
     values: Env<Arc<Value>>,
     entries: Arc<[Arc<Term>]>,
+    defaults: Arc<[Option<Arc<Term>>]>,
 }
 
 impl RecordClosure {
     pub fn new(values: Env<Arc<Value>>, entries: Arc<[Arc<Term>]>) -> RecordClosure {
-        RecordClosure { values, entries }
+        RecordClosure::with_defaults(values, entries, Arc::new([]))
+    }
+
+    /// Construct a record closure for a record type, where some of the
+    /// entries might have default terms.
+    pub fn with_defaults(
+        values: Env<Arc<Value>>,
+        entries: Arc<[Arc<Term>]>,
+        defaults: Arc<[Option<Arc<Term>>]>,
+    ) -> RecordClosure {
+        RecordClosure {
+            values,
+            entries,
+            defaults,
+        }
+    }
+
+    /// The number of entries in the record closure.
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Evaluate the entry at `index`, using `inputs` as the values of the
+    /// entries that precede it.
+    pub fn apply_entry(
+        &self,
+        globals: &Globals,
+        index: usize,
+        inputs: &[Arc<Value>],
+    ) -> Arc<Value> {
+        let mut values = self.values.clone();
+        for input in inputs {
+            values.push(input.clone());
+        }
+        eval(globals, &mut values, &self.entries[index])
+    }
+
+    /// Returns `true` if the entry at `index` has a default term.
+    pub fn has_default(&self, index: usize) -> bool {
+        matches!(self.defaults.get(index), Some(Some(_)))
+    }
+
+    /// Evaluate the default term of the entry at `index`, using `inputs` as
+    /// the values of the entries that precede it.
+    pub fn apply_default(
+        &self,
+        globals: &Globals,
+        index: usize,
+        inputs: &[Arc<Value>],
+    ) -> Option<Arc<Value>> {
+        let default_term = self.defaults.get(index)?.as_ref()?;
+        let mut values = self.values.clone();
+        for input in inputs {
+            values.push(input.clone());
+        }
+        Some(eval(globals, &mut values, default_term))
     }
 
     /// Apply a callback to each of the entries in the record closure.
@@ -181,6 +263,49 @@ impl RecordClosure {
     }
 }
 
+/// Branch closure, capturing the current values in scope.
+#[derive(Clone, Debug)]
+pub struct BranchClosure {
+    values: Env<Arc<Value>>,
+    labels: Arc<[String]>,
+    branches: Arc<[Arc<Term>]>,
+}
+
+impl BranchClosure {
+    pub fn new(
+        values: Env<Arc<Value>>,
+        labels: Arc<[String]>,
+        branches: Arc<[Arc<Term>]>,
+    ) -> BranchClosure {
+        BranchClosure {
+            values,
+            labels,
+            branches,
+        }
+    }
+
+    /// The labels of the branches in the branch closure.
+    pub fn labels(&self) -> &Arc<[String]> {
+        &self.labels
+    }
+
+    /// Apply a payload to the branch at `index`.
+    pub fn apply_branch(&self, globals: &Globals, index: usize, payload: Arc<Value>) -> Arc<Value> {
+        let mut values = self.values.clone();
+        values.push(payload);
+        eval(globals, &mut values, &self.branches[index])
+    }
+
+    /// Apply a payload to the branch associated with `label`.
+    pub fn apply(&self, globals: &Globals, label: &str, payload: Arc<Value>) -> Option<Arc<Value>> {
+        let index = self
+            .labels
+            .iter()
+            .position(|branch_label| branch_label == label)?;
+        Some(self.apply_branch(globals, index, payload))
+    }
+}
+
 /// Initialization operation for lazy values.
 ///
 /// We need to use a [defunctionalized] representation because Rust does not allow
@@ -233,7 +358,15 @@ impl LazyValue {
             Some(LazyInit::EvalTerm(mut values, term)) => eval(globals, &mut values, &term),
             Some(LazyInit::ApplyElim(head, elim)) => match elim {
                 Elim::Record(label) => record_elim(globals, head.force(globals).clone(), &label),
-                Elim::Function(input) => function_elim(globals, head.force(globals).clone(), input),
+                Elim::Function(plicity, input) => {
+                    function_elim(globals, head.force(globals).clone(), plicity, input)
+                }
+                Elim::Variant(closure) => {
+                    variant_elim(globals, head.force(globals).clone(), closure)
+                }
+                Elim::Data(name, motive, methods) => {
+                    data_elim(globals, head.force(globals).clone(), name, motive, methods)
+                }
             },
             None => panic!("Lazy instance has previously been poisoned"),
         })
@@ -286,9 +419,9 @@ pub fn eval(globals: &Globals, values: &mut Env<Arc<Value>>, term: &Term) -> Arc
 
         TermData::TypeType => Arc::new(Value::TypeType),
 
-        TermData::RecordType(labels, types) => Arc::new(Value::RecordType(
+        TermData::RecordType(labels, _, types, defaults) => Arc::new(Value::RecordType(
             labels.clone(),
-            RecordClosure::new(values.clone(), types.clone()),
+            RecordClosure::with_defaults(values.clone(), types.clone(), defaults.clone()),
         )),
         TermData::RecordTerm(labels, terms) => Arc::new(Value::RecordTerm(
             labels.clone(),
@@ -299,21 +432,49 @@ pub fn eval(globals: &Globals, values: &mut Env<Arc<Value>>, term: &Term) -> Arc
             record_elim(globals, head, label)
         }
 
-        TermData::FunctionType(input_name_hint, input_type, output_type) => {
+        TermData::FunctionType(plicity, input_name_hint, input_type, output_type) => {
             Arc::new(Value::FunctionType(
+                *plicity,
                 input_name_hint.clone(),
                 eval(globals, values, input_type),
                 FunctionClosure::new(values.clone(), output_type.clone()),
             ))
         }
-        TermData::FunctionTerm(input_name, output_term) => Arc::new(Value::FunctionTerm(
+        TermData::FunctionTerm(plicity, input_name, output_term) => Arc::new(Value::FunctionTerm(
+            *plicity,
             input_name.clone(),
             FunctionClosure::new(values.clone(), output_term.clone()),
         )),
-        TermData::FunctionElim(head, input) => {
+        TermData::FunctionElim(head, plicity, input) => {
             let head = eval(globals, values, head);
             let input = LazyValue::eval(values.clone(), input.clone());
-            function_elim(globals, head, Arc::new(input))
+            function_elim(globals, head, *plicity, Arc::new(input))
+        }
+
+        TermData::VariantType(labels, types) => {
+            let types = (types.iter())
+                .map(|r#type| eval(globals, values, r#type))
+                .collect();
+
+            Arc::new(Value::VariantType(labels.clone(), types))
+        }
+        TermData::VariantTerm(label, term) => Arc::new(Value::VariantTerm(
+            label.clone(),
+            eval(globals, values, term),
+        )),
+        TermData::VariantElim(head, labels, branches) => {
+            let head = eval(globals, values, head);
+            let closure = BranchClosure::new(values.clone(), labels.clone(), branches.clone());
+            variant_elim(globals, head, closure)
+        }
+
+        TermData::DataElim(head, name, motive, methods) => {
+            let head = eval(globals, values, head);
+            let motive = eval(globals, values, motive);
+            let methods = (methods.iter())
+                .map(|method| eval(globals, values, method))
+                .collect();
+            data_elim(globals, head, name.clone(), motive, methods)
         }
 
         TermData::ArrayTerm(term_entries) => {
@@ -335,10 +496,65 @@ pub fn eval(globals: &Globals, values: &mut Env<Arc<Value>>, term: &Term) -> Arc
 
         TermData::Constant(constant) => Arc::new(Value::Constant(constant.clone())),
 
+        TermData::Meta(meta) => match meta.solution() {
+            Some(solution) => solution.clone(),
+            None => Arc::new(Value::meta(meta.clone(), [])),
+        },
+
         TermData::Error => Arc::new(Value::Error),
     }
 }
 
+/// Force any solved metavariables at the head of a value, and retry primitive
+/// operations that were stuck on metavariables that have since been solved.
+pub fn force_metas(globals: &Globals, value: &Arc<Value>) -> Arc<Value> {
+    let mut value = value.clone();
+    loop {
+        match value.as_ref() {
+            Value::Stuck(Head::Meta(meta), spine) => match meta.solution() {
+                Some(solution) => value = apply_elims(globals, solution.clone(), spine),
+                None => break,
+            },
+            Value::Stuck(Head::Global(name), spine) => {
+                let primitive = match globals.get_primitive(name) {
+                    Some(primitive) => primitive,
+                    None => break,
+                };
+                match apply_primitive(globals, primitive, spine) {
+                    Some(primitive_value) => {
+                        let primitive_value = Arc::new(LazyValue::new(primitive_value));
+                        let head = Head::Global(name.clone());
+                        value = Arc::new(Value::Unstuck(head, spine.clone(), primitive_value));
+                    }
+                    None => break,
+                }
+            }
+            _ => break,
+        }
+    }
+    value
+}
+
+/// Apply a spine of eliminators to a value.
+fn apply_elims(globals: &Globals, head_value: Arc<Value>, spine: &[Elim]) -> Arc<Value> {
+    spine
+        .iter()
+        .fold(head_value, |head_value, elim| match elim {
+            Elim::Function(plicity, input) => {
+                function_elim(globals, head_value, *plicity, input.clone())
+            }
+            Elim::Record(label) => record_elim(globals, head_value, label),
+            Elim::Variant(closure) => variant_elim(globals, head_value, closure.clone()),
+            Elim::Data(name, motive, methods) => data_elim(
+                globals,
+                head_value,
+                name.clone(),
+                motive.clone(),
+                methods.clone(),
+            ),
+        })
+}
+
 /// Return the type of the record elimination.
 pub fn record_elim_type(
     globals: &Globals,
@@ -347,6 +563,7 @@ pub fn record_elim_type(
     head_type: &Arc<Value>,
     label: &str,
 ) -> Option<Arc<Value>> {
+    let head_type = force_metas(globals, head_type);
     match head_type.force(globals) {
         Value::RecordType(labels, closure) => {
             // Evaluate the head of the record elimination now that we know we
@@ -384,7 +601,7 @@ pub fn record_elim_type(
 }
 
 /// Apply a record term elimination.
-fn record_elim(globals: &Globals, mut head_value: Arc<Value>, label: &str) -> Arc<Value> {
+pub fn record_elim(globals: &Globals, mut head_value: Arc<Value>, label: &str) -> Arc<Value> {
     match Arc::make_mut(&mut head_value) {
         Value::Stuck(_, spine) => {
             spine.push(Elim::Record(label.to_owned()));
@@ -418,24 +635,144 @@ fn record_elim(globals: &Globals, mut head_value: Arc<Value>, label: &str) -> Ar
     }
 }
 
+/// Apply a variant term elimination.
+fn variant_elim(
+    globals: &Globals,
+    mut head_value: Arc<Value>,
+    closure: BranchClosure,
+) -> Arc<Value> {
+    match Arc::make_mut(&mut head_value) {
+        Value::Stuck(_, spine) => {
+            spine.push(Elim::Variant(closure));
+            head_value
+        }
+        Value::Unstuck(_, spine, value) => {
+            spine.push(Elim::Variant(closure.clone()));
+            *value = Arc::new(LazyValue::apply_elim(value.clone(), Elim::Variant(closure)));
+            head_value
+        }
+
+        Value::VariantTerm(label, payload) => (closure.apply(globals, label, payload.clone()))
+            .unwrap_or_else(|| Arc::new(Value::Error)),
+
+        _ => Arc::new(Value::Error),
+    }
+}
+
+/// Apply a data term elimination.
+///
+/// This reduces if the head value is a constructor of the data type that has
+/// been applied to all of its parameters and fields. The method for the
+/// constructor is then applied to the fields, followed by the results of
+/// eliminating each of the recursive fields.
+fn data_elim(
+    globals: &Globals,
+    mut head_value: Arc<Value>,
+    name: String,
+    motive: Arc<Value>,
+    methods: Arc<[Arc<Value>]>,
+) -> Arc<Value> {
+    if let Value::Stuck(Head::Global(constructor_name), spine) = head_value.as_ref() {
+        match globals.get_constructor(constructor_name) {
+            Some((decl, index))
+                if decl.name == name
+                    && spine.len() == decl.params.len() + decl.constructors[index].fields.len() =>
+            {
+                let fields = &spine[decl.params.len()..];
+                let mut output_value = methods[index].clone();
+
+                for elim in fields {
+                    match elim {
+                        Elim::Function(plicity, input) => {
+                            output_value =
+                                function_elim(globals, output_value, *plicity, input.clone());
+                        }
+                        _ => return Arc::new(Value::Error),
+                    }
+                }
+
+                let field_types = decl.constructors[index].fields.iter();
+                for (field_index, ((_, field_type), elim)) in field_types.zip(fields).enumerate() {
+                    if let Elim::Function(plicity, input) = elim {
+                        if decl.is_recursive_field(field_index, field_type) {
+                            let elim = Elim::Data(name.clone(), motive.clone(), methods.clone());
+                            let ih = Arc::new(LazyValue::apply_elim(input.clone(), elim));
+                            output_value = function_elim(globals, output_value, *plicity, ih);
+                        }
+                    }
+                }
+
+                return output_value;
+            }
+            Some(_) | None => {}
+        }
+    }
+
+    match Arc::make_mut(&mut head_value) {
+        Value::Stuck(_, spine) => {
+            spine.push(Elim::Data(name, motive, methods));
+            head_value
+        }
+        Value::Unstuck(_, spine, value) => {
+            let elim = Elim::Data(name, motive, methods);
+            spine.push(elim.clone());
+            *value = Arc::new(LazyValue::apply_elim(value.clone(), elim));
+            head_value
+        }
+
+        _ => Arc::new(Value::Error),
+    }
+}
+
+/// Apply a function elimination to a value.
+pub fn apply_function_elim(
+    globals: &Globals,
+    head_value: Arc<Value>,
+    plicity: Plicity,
+    input: Arc<Value>,
+) -> Arc<Value> {
+    function_elim(
+        globals,
+        head_value,
+        plicity,
+        Arc::new(LazyValue::new(input)),
+    )
+}
+
 /// Apply a function term elimination.
 fn function_elim(
     globals: &Globals,
     mut head_value: Arc<Value>,
+    plicity: Plicity,
     input: Arc<LazyValue>,
 ) -> Arc<Value> {
     match Arc::make_mut(&mut head_value) {
-        Value::Stuck(_, spine) => {
-            spine.push(Elim::Function(input));
+        Value::Stuck(head, spine) => {
+            spine.push(Elim::Function(plicity, input));
+
+            // Attempt to apply primitive operations once they are supplied
+            // with enough inputs, otherwise leaving the application stuck.
+            if let Head::Global(name) = head {
+                if let Some(primitive) = globals.get_primitive(name) {
+                    if let Some(value) = apply_primitive(globals, primitive, spine) {
+                        let value = Arc::new(LazyValue::new(value));
+                        return Arc::new(Value::Unstuck(head.clone(), spine.clone(), value));
+                    }
+                }
+            }
+
             head_value
         }
         Value::Unstuck(_, spine, value) => {
-            spine.push(Elim::Function(input.clone()));
-            *value = Arc::new(LazyValue::apply_elim(value.clone(), Elim::Function(input)));
+            spine.push(Elim::Function(plicity, input.clone()));
+            *value = Arc::new(LazyValue::apply_elim(
+                value.clone(),
+                Elim::Function(plicity, input),
+            ));
             head_value
         }
 
-        Value::FunctionTerm(_, output_closure) => {
+        Value::FunctionTerm(_, _, output_closure) => {
             output_closure.apply(globals, input.force(globals).clone())
         }
 
@@ -443,6 +780,22 @@ fn function_elim(
     }
 }
 
+/// Attempt to apply a primitive operation to a spine of eliminators.
+fn apply_primitive(globals: &Globals, primitive: &Primitive, spine: &[Elim]) -> Option<Arc<Value>> {
+    if spine.len() != primitive.arity() {
+        return None;
+    }
+
+    let inputs = (spine.iter())
+        .map(|elim| match elim {
+            Elim::Function(_, input) => Some(force_metas(globals, input.force(globals))),
+            Elim::Record(_) | Elim::Variant(_) | Elim::Data(_, _, _) => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    primitive.apply(globals, &inputs)
+}
+
 /// Describes how definitions should be unfolded to when reading back values.
 #[derive(Copy, Clone, Debug)]
 pub enum Unfold {
@@ -476,20 +829,53 @@ fn read_back_stuck(
             let index = size.level_to_index(*level).unwrap();
             Term::generated(TermData::Var(index)) // TODO: Handle overflow
         }
+        Head::Meta(meta) => Term::generated(TermData::Meta(meta.clone())),
     };
 
     spine.iter().fold(head, |head, elim| match elim {
-        Elim::Function(input) => {
+        Elim::Function(plicity, input) => {
             let input = read_back(globals, size, unfold, input.force(globals));
-            Term::generated(TermData::FunctionElim(Arc::new(head), Arc::new(input)))
+            Term::generated(TermData::FunctionElim(
+                Arc::new(head),
+                *plicity,
+                Arc::new(input),
+            ))
         }
         Elim::Record(label) => Term::generated(TermData::RecordElim(Arc::new(head), label.clone())),
+        Elim::Variant(closure) => {
+            let branches = (0..closure.branches.len())
+                .map(|index| {
+                    let var = Arc::new(Value::var(size.next_level(), []));
+                    let branch = closure.apply_branch(globals, index, var);
+                    Arc::new(read_back(globals, size.next_size(), unfold, &branch))
+                })
+                .collect();
+
+            Term::generated(TermData::VariantElim(
+                Arc::new(head),
+                closure.labels.clone(),
+                branches,
+            ))
+        }
+        Elim::Data(name, motive, methods) => Term::generated(TermData::DataElim(
+            Arc::new(head),
+            name.clone(),
+            Arc::new(read_back(globals, size, unfold, motive)),
+            (methods.iter())
+                .map(|method| Arc::new(read_back(globals, size, unfold, method)))
+                .collect(),
+        )),
     })
 }
 
 /// Read-back a value into the term syntax.
 pub fn read_back(globals: &Globals, size: EnvSize, unfold: Unfold, value: &Value) -> Term {
     match value {
+        Value::Stuck(Head::Meta(meta), spine) if meta.solution().is_some() => {
+            let solution = meta.solution().unwrap().clone();
+            let value = apply_elims(globals, solution, spine);
+            read_back(globals, size, unfold, &value)
+        }
         Value::Stuck(head, spine) => read_back_stuck(globals, size, unfold, head, spine),
         Value::Unstuck(head, spine, value) => match unfold {
             Unfold::Never => read_back_stuck(globals, size, unfold, head, spine),
@@ -498,24 +884,26 @@ pub fn read_back(globals: &Globals, size: EnvSize, unfold: Unfold, value: &Value
 
         Value::TypeType => Term::generated(TermData::TypeType),
 
-        Value::FunctionType(input_name_hint, input_type, output_closure) => {
+        Value::FunctionType(plicity, input_name_hint, input_type, output_closure) => {
             let var = Arc::new(Value::var(size.next_level(), []));
             let input_type = read_back(globals, size, unfold, input_type);
             let output_type = output_closure.apply(globals, var);
             let output_type = read_back(globals, size.next_size(), unfold, &output_type);
 
             Term::generated(TermData::FunctionType(
+                *plicity,
                 input_name_hint.clone(),
                 Arc::new(input_type),
                 Arc::new(output_type),
             ))
         }
-        Value::FunctionTerm(input_name_hint, output_closure) => {
+        Value::FunctionTerm(plicity, input_name_hint, output_closure) => {
             let var = Arc::new(Value::var(size.next_level(), []));
             let output_term = output_closure.apply(globals, var);
             let output_term = read_back(globals, size.next_size(), unfold, &output_term);
 
             Term::generated(TermData::FunctionTerm(
+                *plicity,
                 input_name_hint.clone(),
                 Arc::new(output_term),
             ))
@@ -523,19 +911,35 @@ pub fn read_back(globals: &Globals, size: EnvSize, unfold: Unfold, value: &Value
 
         Value::RecordType(labels, closure) => {
             let mut size = size;
+            let mut index = 0;
+            let mut inputs = Vec::with_capacity(closure.entries.len());
             let mut types = Vec::with_capacity(closure.entries.len());
+            let mut defaults = Vec::with_capacity(closure.entries.len());
 
             closure.for_each_entry(globals, |entry_type| {
                 let entry_type = read_back(globals, size, unfold, &entry_type);
+                let default_term = (closure.apply_default(globals, index, &inputs))
+                    .map(|default| Arc::new(read_back(globals, size, unfold, &default)));
                 types.push(Arc::new(entry_type));
+                defaults.push(default_term);
 
                 let level = size.next_level();
                 size = size.next_size();
+                index += 1;
 
-                Arc::new(Value::var(level, []))
+                let input = Arc::new(Value::var(level, []));
+                inputs.push(input.clone());
+                input
             });
 
-            Term::generated(TermData::RecordType(labels.clone(), types.into()))
+            // Documentation is not preserved in values
+            let docs = vec![String::new(); labels.len()];
+            Term::generated(TermData::RecordType(
+                labels.clone(),
+                docs.into(),
+                types.into(),
+                defaults.into(),
+            ))
         }
         Value::RecordTerm(labels, closure) => {
             let mut size = size;
@@ -554,6 +958,18 @@ pub fn read_back(globals: &Globals, size: EnvSize, unfold: Unfold, value: &Value
             Term::generated(TermData::RecordTerm(labels.clone(), terms.into()))
         }
 
+        Value::VariantType(labels, types) => {
+            let types = (types.iter())
+                .map(|r#type| Arc::new(read_back(globals, size, unfold, r#type)))
+                .collect();
+
+            Term::generated(TermData::VariantType(labels.clone(), types))
+        }
+        Value::VariantTerm(label, value) => Term::generated(TermData::VariantTerm(
+            label.clone(),
+            Arc::new(read_back(globals, size, unfold, value)),
+        )),
+
         Value::ArrayTerm(value_entries) => {
             let term_entries = value_entries
                 .iter()
@@ -590,7 +1006,9 @@ fn is_equal_stuck(
 
     for (elim0, elim1) in Iterator::zip(spine0.iter(), spine1.iter()) {
         match (elim0, elim1) {
-            (Elim::Function(input0), Elim::Function(input1)) => {
+            (Elim::Function(plicity0, input0), Elim::Function(plicity1, input1))
+                if plicity0 == plicity1 =>
+            {
                 let input0 = input0.force(globals);
                 let input1 = input1.force(globals);
 
@@ -599,6 +1017,21 @@ fn is_equal_stuck(
                 }
             }
             (Elim::Record(label0), Elim::Record(label1)) if label0 == label1 => {}
+            (Elim::Variant(closure0), Elim::Variant(closure1)) => {
+                if !is_equal_branch_closure(globals, size, closure0, closure1) {
+                    return false;
+                }
+            }
+            (Elim::Data(name0, motive0, methods0), Elim::Data(name1, motive1, methods1))
+                if name0 == name1 =>
+            {
+                if !is_equal(globals, size, motive0, motive1)
+                    || !Iterator::zip(methods0.iter(), methods1.iter())
+                        .all(|(method0, method1)| is_equal(globals, size, method0, method1))
+                {
+                    return false;
+                }
+            }
             (_, _) => return false,
         }
     }
@@ -650,11 +1083,49 @@ fn is_equal_record_closure(
     true
 }
 
+/// Check that one branch closure is equal to another branch closure.
+fn is_equal_branch_closure(
+    globals: &Globals,
+    size: EnvSize,
+    closure0: &BranchClosure,
+    closure1: &BranchClosure,
+) -> bool {
+    if closure0.labels != closure1.labels {
+        return false;
+    }
+
+    (0..closure0.branches.len()).all(|index| {
+        let var = Arc::new(Value::var(size.next_level(), []));
+        let branch0 = closure0.apply_branch(globals, index, var.clone());
+        let branch1 = closure1.apply_branch(globals, index, var);
+        is_equal(globals, size.next_size(), &branch0, &branch1)
+    })
+}
+
 /// Check that one value is [computationally equal] to another value.
 ///
 /// [computationally equal]: https://ncatlab.org/nlab/show/equality#computational_equality
 pub fn is_equal(globals: &Globals, size: EnvSize, value0: &Value, value1: &Value) -> bool {
     match (value0, value1) {
+        (Value::Stuck(Head::Meta(meta), spine), value1) if meta.solution().is_some() => {
+            let solution = meta.solution().unwrap().clone();
+            is_equal(
+                globals,
+                size,
+                &apply_elims(globals, solution, spine),
+                value1,
+            )
+        }
+        (value0, Value::Stuck(Head::Meta(meta), spine)) if meta.solution().is_some() => {
+            let solution = meta.solution().unwrap().clone();
+            is_equal(
+                globals,
+                size,
+                value0,
+                &apply_elims(globals, solution, spine),
+            )
+        }
+
         (Value::Stuck(head0, spine0), Value::Stuck(head1, spine1)) => {
             is_equal_stuck(globals, size, (head0, spine0), (head1, spine1))
         }
@@ -678,21 +1149,37 @@ pub fn is_equal(globals: &Globals, size: EnvSize, value0: &Value, value1: &Value
         (Value::TypeType, Value::TypeType) => true,
 
         (
-            Value::FunctionType(_, input_type0, output_closure0),
-            Value::FunctionType(_, input_type1, output_closure1),
+            Value::FunctionType(plicity0, _, input_type0, output_closure0),
+            Value::FunctionType(plicity1, _, input_type1, output_closure1),
         ) => {
-            is_equal(globals, size, input_type1, input_type0)
+            plicity0 == plicity1
+                && is_equal(globals, size, input_type1, input_type0)
                 && is_equal_function_closure(globals, size, output_closure0, output_closure1)
         }
-        (Value::FunctionTerm(_, output_closure0), Value::FunctionTerm(_, output_closure1)) => {
-            is_equal_function_closure(globals, size, output_closure0, output_closure1)
+        (
+            Value::FunctionTerm(plicity0, _, output_closure0),
+            Value::FunctionTerm(plicity1, _, output_closure1),
+        ) => {
+            plicity0 == plicity1
+                && is_equal_function_closure(globals, size, output_closure0, output_closure1)
         }
 
+        // The default terms of record types are not compared, because they
+        // only affect how omitted entries are filled in during elaboration.
         (Value::RecordType(labels0, closure0), Value::RecordType(labels1, closure1))
         | (Value::RecordTerm(labels0, closure0), Value::RecordTerm(labels1, closure1)) => {
             labels0 == labels1 && is_equal_record_closure(globals, size, closure0, closure1)
         }
 
+        (Value::VariantType(labels0, types0), Value::VariantType(labels1, types1)) => {
+            labels0 == labels1
+                && Iterator::zip(types0.iter(), types1.iter())
+                    .all(|(type0, type1)| is_equal(globals, size, type0, type1))
+        }
+        (Value::VariantTerm(label0, value0), Value::VariantTerm(label1, value1)) => {
+            label0 == label1 && is_equal(globals, size, value0, value1)
+        }
+
         (Value::ArrayTerm(value_entries0), Value::ArrayTerm(value_entries1))
         | (Value::ListTerm(value_entries0), Value::ListTerm(value_entries1)) => {
             if value_entries0.len() != value_entries1.len() {
@@ -14,7 +14,8 @@ use crossbeam_channel::Sender;
 use std::sync::Arc;
 
 use crate::lang::core::semantics::{self, Elim, Unfold, Value};
-use crate::lang::core::{Constant, Env, EnvSize, Globals, Term, TermData, VarIndex};
+use crate::lang::core::{data, Constant, Env, EnvSize, Globals, Term, TermData, VarIndex};
+use crate::lang::Plicity;
 use crate::reporting::{AmbiguousTerm, CoreTypingMessage, ExpectedType, Message};
 
 /// Type checking context.
@@ -139,15 +140,19 @@ impl<'globals> Context<'globals> {
             (_, Value::Error) => {}
 
             (
-                TermData::FunctionTerm(_, output_term),
-                Value::FunctionType(_, input_type, output_closure),
+                TermData::FunctionTerm(term_plicity, _, output_term),
+                Value::FunctionType(type_plicity, _, input_type, output_closure),
             ) => {
+                if term_plicity != type_plicity {
+                    self.report(CoreTypingMessage::MismatchedPlicity);
+                    return;
+                }
                 let input_term = self.push_variable(input_type.clone());
                 let output_type = output_closure.apply(self.globals, input_term);
                 self.check_type(output_term, &output_type);
                 self.pop_scope();
             }
-            (TermData::FunctionTerm(_, _), _) => {
+            (TermData::FunctionTerm(_, _, _), _) => {
                 self.report(CoreTypingMessage::TooManyInputsInFunctionTerm);
             }
 
@@ -180,14 +185,56 @@ impl<'globals> Context<'globals> {
                 self.truncate_scopes(initial_size);
             }
 
+            (TermData::VariantTerm(label, term), Value::VariantType(labels, types)) => {
+                match labels.iter().position(|type_label| type_label == label) {
+                    Some(index) => self.check_type(term, &types[index]),
+                    None => self.report(CoreTypingMessage::UnexpectedVariantTerm {
+                        label: label.clone(),
+                        expected_type: self.read_back(expected_type),
+                    }),
+                }
+            }
+            (TermData::VariantTerm(label, _), _) => {
+                self.report(CoreTypingMessage::UnexpectedVariantTerm {
+                    label: label.clone(),
+                    expected_type: self.read_back(expected_type),
+                });
+            }
+            (TermData::VariantElim(head_term, branch_labels, branches), _) => {
+                let head_type = self.synth_type(head_term);
+                match head_type.force(self.globals) {
+                    Value::VariantType(labels, types) => {
+                        if branch_labels != labels || branches.len() != types.len() {
+                            self.report(CoreTypingMessage::UnexpectedMatchLabels {
+                                found_labels: branch_labels.clone(),
+                                expected_labels: labels.clone(),
+                            });
+                            return;
+                        }
+                        for (branch, r#type) in Iterator::zip(branches.iter(), types.iter()) {
+                            self.push_variable(r#type.clone());
+                            self.check_type(branch, expected_type);
+                            self.pop_scope();
+                        }
+                    }
+                    Value::Error => {}
+                    _ => self.report(CoreTypingMessage::ExpectedVariant {
+                        head_type: self.read_back(&head_type),
+                    }),
+                }
+            }
+
             (TermData::ArrayTerm(entry_terms), forced_type) => match forced_type.try_global() {
-                Some(("Array", [Elim::Function(len), Elim::Function(entry_type)])) => {
+                Some((
+                    "Array",
+                    [Elim::Function(Plicity::Explicit, len), Elim::Function(Plicity::Explicit, entry_type)],
+                )) => {
                     let forced_entry_type = entry_type.force(self.globals);
                     for entry_term in entry_terms {
                         self.check_type(entry_term, forced_entry_type);
                     }
 
-                    match len.force(self.globals).as_ref() {
+                    match len.force(self.globals).force(self.globals) {
                         Value::Constant(Constant::U32(len))
                             if *len as usize == entry_terms.len() => {}
                         _ => {
@@ -196,8 +243,8 @@ impl<'globals> Context<'globals> {
                                 found_type: self.read_back(&Value::global(
                                     "Array",
                                     [
-                                        Elim::Function(len.clone()),
-                                        Elim::Function(entry_type.clone()),
+                                        Elim::Function(Plicity::Explicit, len.clone()),
+                                        Elim::Function(Plicity::Explicit, entry_type.clone()),
                                     ],
                                 )),
                             });
@@ -209,7 +256,7 @@ impl<'globals> Context<'globals> {
                 }),
             },
             (TermData::ListTerm(entry_terms), forced_type) => match forced_type.try_global() {
-                Some(("List", [Elim::Function(entry_type)])) => {
+                Some(("List", [Elim::Function(Plicity::Explicit, entry_type)])) => {
                     let forced_entry_type = entry_type.force(self.globals);
                     for entry_term in entry_terms {
                         self.check_type(entry_term, forced_entry_type);
@@ -263,7 +310,7 @@ impl<'globals> Context<'globals> {
 
             TermData::TypeType => Arc::new(Value::TypeType),
 
-            TermData::FunctionType(_, input_type, output_type) => {
+            TermData::FunctionType(_, _, input_type, output_type) => {
                 if !self.is_type(input_type) {
                     return Arc::new(Value::Error);
                 }
@@ -277,16 +324,20 @@ impl<'globals> Context<'globals> {
                 self.pop_scope();
                 Arc::new(Value::TypeType)
             }
-            TermData::FunctionTerm(_, _) => {
+            TermData::FunctionTerm(_, _, _) => {
                 self.report(CoreTypingMessage::AmbiguousTerm {
                     term: AmbiguousTerm::FunctionTerm,
                 });
                 Arc::new(Value::Error)
             }
-            TermData::FunctionElim(head_term, input_term) => {
+            TermData::FunctionElim(head_term, input_plicity, input_term) => {
                 let head_type = self.synth_type(head_term);
                 match head_type.force(self.globals) {
-                    Value::FunctionType(_, input_type, output_closure) => {
+                    Value::FunctionType(plicity, _, _, _) if plicity != input_plicity => {
+                        self.report(CoreTypingMessage::MismatchedPlicity);
+                        Arc::new(Value::Error)
+                    }
+                    Value::FunctionType(_, _, input_type, output_closure) => {
                         self.check_type(input_term, &input_type);
                         let input_value = self.eval(input_term);
                         output_closure.apply(self.globals, input_value)
@@ -307,10 +358,13 @@ impl<'globals> Context<'globals> {
                 });
                 Arc::new(Value::Error)
             }
-            TermData::RecordType(labels, types) => {
+            TermData::RecordType(labels, docs, types, defaults) => {
                 use std::collections::BTreeSet;
 
-                if labels.len() != types.len() {
+                if labels.len() != docs.len()
+                    || labels.len() != types.len()
+                    || labels.len() != defaults.len()
+                {
                     self.report(CoreTypingMessage::InvalidRecordTypeLabelCount);
                     return Arc::new(Value::Error);
                 }
@@ -319,7 +373,9 @@ impl<'globals> Context<'globals> {
                 let mut duplicate_labels = Vec::new();
                 let mut seen_labels = BTreeSet::new();
 
-                for (name, r#type) in Iterator::zip(labels.iter(), types.iter()) {
+                for (name, (r#type, default_term)) in
+                    Iterator::zip(labels.iter(), Iterator::zip(types.iter(), defaults.iter()))
+                {
                     if !seen_labels.insert(name) {
                         duplicate_labels.push(name.clone());
                     }
@@ -328,6 +384,9 @@ impl<'globals> Context<'globals> {
                         return Arc::new(Value::Error);
                     }
                     let r#type = self.eval(r#type);
+                    if let Some(default_term) = default_term {
+                        self.check_type(default_term, &r#type);
+                    }
                     self.push_variable(r#type);
                 }
 
@@ -354,6 +413,98 @@ impl<'globals> Context<'globals> {
                 }
             }
 
+            TermData::VariantType(labels, types) => {
+                use std::collections::BTreeSet;
+
+                if labels.len() != types.len() {
+                    self.report(CoreTypingMessage::InvalidVariantTypeLabelCount);
+                    return Arc::new(Value::Error);
+                }
+
+                let mut duplicate_labels = Vec::new();
+                let mut seen_labels = BTreeSet::new();
+
+                for (name, r#type) in Iterator::zip(labels.iter(), types.iter()) {
+                    if !seen_labels.insert(name) {
+                        duplicate_labels.push(name.clone());
+                    }
+                    if !self.is_type(r#type) {
+                        return Arc::new(Value::Error);
+                    }
+                }
+
+                if !duplicate_labels.is_empty() {
+                    self.report(CoreTypingMessage::InvalidVariantType { duplicate_labels });
+                }
+
+                Arc::new(Value::TypeType)
+            }
+            TermData::VariantTerm(_, _) => {
+                self.report(CoreTypingMessage::AmbiguousTerm {
+                    term: AmbiguousTerm::VariantTerm,
+                });
+                Arc::new(Value::Error)
+            }
+            TermData::VariantElim(_, _, _) => {
+                self.report(CoreTypingMessage::AmbiguousTerm {
+                    term: AmbiguousTerm::MatchTerm,
+                });
+                Arc::new(Value::Error)
+            }
+
+            TermData::DataElim(head_term, name, motive, methods) => {
+                let head_type = self.synth_type(head_term);
+                let param_terms = match head_type.force(self.globals).try_global() {
+                    Some((head_name, spine)) if head_name == name => (spine.iter())
+                        .map(|elim| match elim {
+                            Elim::Function(Plicity::Explicit, input) => {
+                                Some(Arc::new(self.read_back(input.force(self.globals))))
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>(),
+                    _ => None,
+                };
+
+                match param_terms {
+                    // Synthesize the type of the data elimination by applying
+                    // the generated eliminator to its inputs.
+                    Some(param_terms) if self.globals.get_data(name).is_some() => {
+                        let elim_term = Term::generated(TermData::Global(data::elim_name(name)));
+                        let elim_term =
+                            (param_terms.into_iter()).fold(elim_term, |elim_term, param_term| {
+                                Term::generated(TermData::FunctionElim(
+                                    Arc::new(elim_term),
+                                    Plicity::Implicit,
+                                    param_term,
+                                ))
+                            });
+                        let input_terms = std::iter::once(motive)
+                            .chain(methods.iter())
+                            .chain(std::iter::once(head_term));
+                        let elim_term = input_terms.fold(elim_term, |elim_term, input_term| {
+                            Term::generated(TermData::FunctionElim(
+                                Arc::new(elim_term),
+                                Plicity::Explicit,
+                                input_term.clone(),
+                            ))
+                        });
+
+                        self.synth_type(&elim_term)
+                    }
+                    _ => match head_type.as_ref() {
+                        Value::Error => Arc::new(Value::Error),
+                        _ => {
+                            self.report(CoreTypingMessage::ExpectedData {
+                                data_name: name.clone(),
+                                head_type: self.read_back(&head_type),
+                            });
+                            Arc::new(Value::Error)
+                        }
+                    },
+                }
+            }
+
             TermData::ArrayTerm(_) => {
                 self.report(CoreTypingMessage::AmbiguousTerm {
                     term: AmbiguousTerm::Sequence,
@@ -371,14 +522,24 @@ impl<'globals> Context<'globals> {
             TermData::Constant(Constant::U16(_)) => Arc::new(Value::global("U16", [])),
             TermData::Constant(Constant::U32(_)) => Arc::new(Value::global("U32", [])),
             TermData::Constant(Constant::U64(_)) => Arc::new(Value::global("U64", [])),
+            TermData::Constant(Constant::U128(_)) => Arc::new(Value::global("U128", [])),
             TermData::Constant(Constant::S8(_)) => Arc::new(Value::global("S8", [])),
             TermData::Constant(Constant::S16(_)) => Arc::new(Value::global("S16", [])),
             TermData::Constant(Constant::S32(_)) => Arc::new(Value::global("S32", [])),
             TermData::Constant(Constant::S64(_)) => Arc::new(Value::global("S64", [])),
+            TermData::Constant(Constant::S128(_)) => Arc::new(Value::global("S128", [])),
+            TermData::Constant(Constant::Nat(_)) => Arc::new(Value::global("Nat", [])),
+            TermData::Constant(Constant::Int(_)) => Arc::new(Value::global("Int", [])),
             TermData::Constant(Constant::F32(_)) => Arc::new(Value::global("F32", [])),
             TermData::Constant(Constant::F64(_)) => Arc::new(Value::global("F64", [])),
             TermData::Constant(Constant::Char(_)) => Arc::new(Value::global("Char", [])),
             TermData::Constant(Constant::String(_)) => Arc::new(Value::global("String", [])),
+            TermData::Constant(Constant::Bytes(_)) => Arc::new(Value::global("Bytes", [])),
+
+            TermData::Meta(_) => {
+                self.report(CoreTypingMessage::UnexpectedMeta);
+                Arc::new(Value::Error)
+            }
 
             TermData::Error => Arc::new(Value::Error),
         }
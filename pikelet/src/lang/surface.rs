@@ -3,9 +3,10 @@
 //! This is a user-friendly concrete syntax for the language.
 
 use crossbeam_channel::Sender;
+use lalrpop_util::ErrorRecovery;
 
-use crate::lang::{FileId, Located, Location};
-use crate::reporting::Message;
+use crate::lang::{Associativity, FileId, Located, Location, Plicity};
+use crate::reporting::{LexerError, Message};
 
 mod lexer;
 
@@ -14,12 +15,114 @@ mod grammar {
     include!(concat!(env!("OUT_DIR"), "/lang/surface/grammar.rs"));
 }
 
-/// Entry in a [record type](Term::RecordType).
-pub type TypeEntry = (Located<String>, Option<Located<String>>, Term);
-/// Entry in a [record term](Term::RecordTerm).
-pub type TermEntry = (Located<String>, Option<Located<String>>, Term);
+/// Lines of a documentation comment, with the leading `|||` removed.
+pub type DocLines = Vec<String>;
+/// Entry in a [record type](Term::RecordType), with an optional default term.
+pub type TypeEntry = (
+    DocLines,
+    Located<String>,
+    Option<Located<String>>,
+    Term,
+    Option<Term>,
+);
+/// Entry in a [record term](Term::RecordTerm), with an optional type annotation.
+pub type TermEntry = (
+    DocLines,
+    Located<String>,
+    Option<Located<String>>,
+    Option<Term>,
+    Term,
+);
+/// Entry in a [variant type](Term::VariantType), with an optional payload type.
+pub type VariantEntry = (Located<String>, Option<Term>);
+/// Branch in a [match term](Term::Match), with an optional name for the payload.
+pub type Branch = (Located<String>, Option<Located<String>>, Term);
 /// A group of function inputs that are elements of the same type.
-pub type InputGroup = (Vec<Located<String>>, Term);
+pub type InputGroup = (DocLines, Plicity, Vec<Located<String>>, Term);
+/// The name of an input in a [function term](Term::FunctionTerm), with an
+/// optional type annotation.
+pub type InputName = (DocLines, Plicity, Located<String>, Option<Term>);
+/// An input in a [function elimination](Term::FunctionElim).
+pub type InputTerm = (Plicity, Term);
+/// A group of parameters or fields in a [data declaration](DataDecl) that are
+/// elements of the same type.
+pub type FieldGroup = (Vec<Located<String>>, Term);
+/// A constructor in a [data declaration](DataDecl).
+pub type Constructor = (Located<String>, Vec<FieldGroup>);
+
+/// Data declarations.
+///
+/// These define a new data type, along with its constructors.
+#[derive(Debug, Clone)]
+pub struct DataDecl {
+    /// The name of the data type.
+    pub name: Located<String>,
+    /// The parameters of the data type.
+    pub params: Vec<FieldGroup>,
+    /// The constructors of the data type.
+    pub constructors: Vec<Constructor>,
+}
+
+/// Operator declarations.
+///
+/// These define a new infix operator, with a precedence and associativity.
+#[derive(Debug, Clone)]
+pub struct OperatorDecl {
+    /// The associativity of the operator.
+    pub associativity: Associativity,
+    /// The precedence of the operator, as a numeric literal.
+    pub precedence: Located<String>,
+    /// The operator being defined.
+    pub operator: Located<String>,
+    /// The term that applications of the operator refer to.
+    pub term: Term,
+}
+
+/// Declarations at the start of a module.
+enum Decl {
+    Data(DataDecl),
+    Operator(OperatorDecl),
+}
+
+/// A source file, made up of some data and operator declarations, followed
+/// by a documented term.
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// The data declarations in the module.
+    pub data_decls: Vec<DataDecl>,
+    /// The operator declarations in the module.
+    pub operator_decls: Vec<OperatorDecl>,
+    /// The documentation of the term that the module evaluates to.
+    pub docs: DocLines,
+    /// The term that the module evaluates to.
+    pub term: Term,
+}
+
+impl Module {
+    /// Parse a module from an input string.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(file_id: FileId, input: &str, messages_tx: &Sender<Message>) -> Module {
+        let tokens = lexer::tokens(file_id, 0, input);
+        let mut errors = Vec::new();
+        let module = grammar::ModuleParser::new().parse(file_id, &mut errors, tokens);
+        report_recovered_errors(file_id, errors, messages_tx);
+
+        module.unwrap_or_else(|error| {
+            messages_tx
+                .send(Message::from_lalrpop(file_id, error))
+                .unwrap();
+            Module {
+                data_decls: Vec::new(),
+                operator_decls: Vec::new(),
+                docs: Vec::new(),
+                term: Term::new(
+                    Location::file_range(file_id, 0..input.len()),
+                    TermData::Error,
+                ),
+            }
+        })
+    }
+}
 
 pub type Term = Located<TermData>;
 
@@ -43,30 +146,60 @@ pub enum TermData {
     /// Function terms.
     ///
     /// Also known as: lambda abstraction, anonymous function.
-    FunctionTerm(Vec<Located<String>>, Box<Term>),
+    FunctionTerm(Vec<InputName>, Box<Term>),
     /// Function eliminations.
     ///
     /// Also known as: function application.
-    FunctionElim(Box<Term>, Vec<Term>),
+    FunctionElim(Box<Term>, Vec<InputTerm>),
+
+    /// Applications of infix operators, like `a + b * c`.
+    ///
+    /// These are grouped according to the precedence and associativity of
+    /// the operators during elaboration.
+    Operators(Box<Term>, Vec<(Located<String>, Term)>),
 
     /// Record types.
     RecordType(Vec<TypeEntry>),
     /// Record terms.
     RecordTerm(Vec<TermEntry>),
+    /// Record updates, replacing some of the entries of a record.
+    RecordUpdate(Box<Term>, Vec<TermEntry>),
+    /// Right-biased record merges, combining the entries of two records.
+    RecordMerge(Box<Term>, Box<Term>),
     /// Record eliminations.
     ///
     /// Also known as: record projections, field lookup.
     RecordElim(Box<Term>, Located<String>),
 
+    /// Variant types.
+    ///
+    /// Also known as: tagged unions, sum types.
+    VariantType(Vec<VariantEntry>),
+    /// Variant terms, with an optional payload.
+    ///
+    /// Also known as: injections.
+    VariantTerm(Located<String>, Option<Box<Term>>),
+    /// Match terms.
+    ///
+    /// Also known as: case analysis, variant eliminations.
+    Match(Box<Term>, Vec<Branch>),
+
     /// Ordered sequences.
     SequenceTerm(Vec<Term>),
     /// Character literals.
     CharTerm(String),
     /// String literals.
     StringTerm(String),
+    /// Byte string literals.
+    BytesTerm(String),
     /// Numeric literals.
     NumberTerm(String),
 
+    /// Holes, with an optional name.
+    ///
+    /// These are placeholders for terms that have yet to be written.
+    Hole(Option<String>),
+
     /// Error sentinel.
     Error,
 }
@@ -75,17 +208,42 @@ impl<'input> Term {
     /// Parse a term from an input string.
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(file_id: FileId, input: &str, messages_tx: &Sender<Message>) -> Term {
-        let tokens = lexer::tokens(file_id, input);
-        grammar::TermParser::new()
-            .parse(file_id, tokens)
-            .unwrap_or_else(|error| {
-                messages_tx
-                    .send(Message::from_lalrpop(file_id, error))
-                    .unwrap();
-                Term::new(
-                    Location::file_range(file_id, 0..input.len()),
-                    TermData::Error,
-                )
-            })
+        Term::from_str_at(file_id, 0, input, messages_tx)
+    }
+
+    /// Parse a term from an input string that starts at `offset` in the file,
+    /// for example the source of a term interpolated in a string literal.
+    pub fn from_str_at(
+        file_id: FileId,
+        offset: usize,
+        input: &str,
+        messages_tx: &Sender<Message>,
+    ) -> Term {
+        let tokens = lexer::tokens(file_id, offset, input);
+        let mut errors = Vec::new();
+        let term = grammar::TermParser::new().parse(file_id, &mut errors, tokens);
+        report_recovered_errors(file_id, errors, messages_tx);
+
+        term.unwrap_or_else(|error| {
+            messages_tx
+                .send(Message::from_lalrpop(file_id, error))
+                .unwrap();
+            Term::new(
+                Location::file_range(file_id, offset..(offset + input.len())),
+                TermData::Error,
+            )
+        })
+    }
+}
+
+/// Report the syntax errors that the parser recovered from.
+fn report_recovered_errors(
+    file_id: FileId,
+    errors: Vec<ErrorRecovery<usize, lexer::Token<'_>, LexerError>>,
+    messages_tx: &Sender<Message>,
+) {
+    for error in errors {
+        let message = Message::from_lalrpop(file_id, error.error);
+        messages_tx.send(message).unwrap();
     }
 }
@@ -7,19 +7,36 @@ use crate::reporting::LexerError;
 /// Tokens in the surface language.
 #[derive(Debug, Clone, Logos)]
 pub enum Token<'a> {
-    #[regex(r"\|\|\|(.*)\n")]
+    #[regex(r"\|\|\|[^\n]*")]
     DocComment(&'a str),
     #[regex(r#"'([^'\\]|\\.)*'"#)]
     CharLiteral(&'a str),
     #[regex(r#""([^"\\]|\\.)*""#)]
+    #[token(r#"""""#, lex_multiline_string)]
+    #[regex(r##"r#*""##, lex_raw_string)]
     StringLiteral(&'a str),
-    #[regex(r"[-+]?[0-9][a-zA-Z0-9_\.]*")]
+    #[regex(r#"b"([^"\\]|\\.)*""#)]
+    #[regex(r#"x"[^"]*""#)]
+    BytesLiteral(&'a str),
+    #[regex(r"[-+]?[0-9]([a-zA-Z0-9_\.]|[eEpP][-+])*")]
     NumericLiteral(&'a str),
-    #[regex(r"[a-zA-Z][a-zA-Z0-9\-]*")]
+    #[regex(r"\p{XID_Start}[\p{XID_Continue}\-]*")]
     Name(&'a str),
+    #[regex(r"\?(\p{XID_Start}[\p{XID_Continue}\-]*)?")]
+    Hole(&'a str),
+    #[regex(r"[+\-*/<>=!&|^%~]+")]
+    Operator(&'a str),
 
     #[token("as")]
     As,
+    #[token("data")]
+    Data,
+    #[token("infix")]
+    Infix,
+    #[token("infixl")]
+    InfixLeft,
+    #[token("infixr")]
+    InfixRight,
     #[token("fun")]
     FunTerm,
     #[token("Fun")]
@@ -28,6 +45,14 @@ pub enum Token<'a> {
     RecordTerm,
     #[token("Record")]
     RecordType,
+    #[token("with")]
+    With,
+    #[token("variant")]
+    VariantTerm,
+    #[token("Variant")]
+    VariantType,
+    #[token("match")]
+    Match,
 
     #[token(":")]
     Colon,
@@ -41,6 +66,8 @@ pub enum Token<'a> {
     Dot,
     #[token("=")]
     Equal,
+    #[token("//")]
+    DSlash,
 
     #[token("(")]
     LParen,
@@ -50,7 +77,9 @@ pub enum Token<'a> {
     LBrack,
     #[token("]")]
     RBrack,
+    // A brace that is followed by a line comment, rather than a block comment
     #[token("{")]
+    #[regex(r"\{--[^\n]*")]
     LBrace,
     #[token("}")]
     RBrace,
@@ -58,23 +87,89 @@ pub enum Token<'a> {
     #[error]
     #[regex(r"\p{Whitespace}", logos::skip)]
     #[regex(r"--(.*)\n", logos::skip)]
+    #[token("{-", lex_block_comment)]
     Error,
 }
 
+/// Skip the remainder of a block comment, up to the matching `-}`. Block
+/// comments can be nested, and are reported as errors if left unterminated.
+fn lex_block_comment<'a>(lexer: &mut logos::Lexer<'a, Token<'a>>) -> logos::FilterResult<()> {
+    let remainder = lexer.remainder();
+    let mut depth = 1;
+    let mut index = 0;
+    while index < remainder.len() {
+        if remainder[index..].starts_with("{-") {
+            depth += 1;
+            index += 2;
+        } else if remainder[index..].starts_with("-}") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                lexer.bump(index);
+                return logos::FilterResult::Skip;
+            }
+        } else {
+            index += remainder[index..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    lexer.bump(remainder.len());
+    logos::FilterResult::Error
+}
+
+/// Lex the remainder of a multi-line string literal, up to the closing `"""`.
+fn lex_multiline_string<'a>(lexer: &mut logos::Lexer<'a, Token<'a>>) -> Option<&'a str> {
+    let remainder = lexer.remainder();
+    let mut chars = remainder.char_indices();
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            '"' if remainder[index..].starts_with(r#"""""#) => {
+                lexer.bump(index + 3);
+                return Some(lexer.slice());
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Lex the remainder of a raw string literal, up to a closing quote that is
+/// followed by the same number of hashes as the opening quote.
+fn lex_raw_string<'a>(lexer: &mut logos::Lexer<'a, Token<'a>>) -> Option<&'a str> {
+    let num_hashes = lexer.slice().len() - 2;
+    let end_quote = format!("\"{}", "#".repeat(num_hashes));
+    let len = lexer.remainder().find(&end_quote)?;
+    lexer.bump(len + end_quote.len());
+    Some(lexer.slice())
+}
+
 impl<'a> fmt::Display for Token<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Token::DocComment(s) => write!(f, "{}", s),
             Token::CharLiteral(s) => write!(f, "{}", s),
             Token::StringLiteral(s) => write!(f, "{}", s),
+            Token::BytesLiteral(s) => write!(f, "{}", s),
             Token::NumericLiteral(s) => write!(f, "{}", s),
             Token::Name(s) => write!(f, "{}", s),
+            Token::Hole(s) => write!(f, "{}", s),
+            Token::Operator(s) => write!(f, "{}", s),
 
             Token::As => write!(f, "as"),
+            Token::Data => write!(f, "data"),
+            Token::Infix => write!(f, "infix"),
+            Token::InfixLeft => write!(f, "infixl"),
+            Token::InfixRight => write!(f, "infixr"),
             Token::FunTerm => write!(f, "fun"),
             Token::FunType => write!(f, "Fun"),
             Token::RecordTerm => write!(f, "record"),
             Token::RecordType => write!(f, "Record"),
+            Token::With => write!(f, "with"),
+            Token::VariantTerm => write!(f, "variant"),
+            Token::VariantType => write!(f, "Variant"),
+            Token::Match => write!(f, "match"),
 
             Token::Colon => write!(f, ":"),
             Token::Comma => write!(f, ","),
@@ -82,6 +177,7 @@ impl<'a> fmt::Display for Token<'a> {
             Token::Arrow => write!(f, "->"),
             Token::Equal => write!(f, "="),
             Token::Dot => write!(f, "."),
+            Token::DSlash => write!(f, "//"),
 
             Token::LParen => write!(f, "("),
             Token::RParen => write!(f, ")"),
@@ -97,25 +193,172 @@ impl<'a> fmt::Display for Token<'a> {
 
 pub type Spanned<Tok, Loc, Error> = Result<(Loc, Tok, Loc), Error>;
 
+impl<'a> Token<'a> {
+    /// Returns `true` if the token can appear at the end of an operand.
+    fn ends_operand(&self) -> bool {
+        matches!(
+            self,
+            Token::CharLiteral(_)
+                | Token::StringLiteral(_)
+                | Token::BytesLiteral(_)
+                | Token::NumericLiteral(_)
+                | Token::Name(_)
+                | Token::Hole(_)
+                | Token::RParen
+                | Token::RBrack
+                | Token::RBrace
+        )
+    }
+}
+
+/// Split a sign off the end of an operator that starts with a reserved symbol,
+/// so that `x=-1` is lexed in the same way as `x = -1`.
+fn split_sign(operator: &str) -> Option<(Token<'_>, &str)> {
+    let (symbol, sign) = operator.split_at(operator.len().checked_sub(1)?);
+    if sign != "-" && sign != "+" {
+        return None;
+    }
+    match symbol {
+        "=" => Some((Token::Equal, sign)),
+        "=>" => Some((Token::DArrow, sign)),
+        "->" => Some((Token::Arrow, sign)),
+        _ => None,
+    }
+}
+
+/// Lex the tokens in some source code that starts at `offset` in the file.
+///
+/// A sign that directly follows an operand is lexed as an operator, rather
+/// than as the start of a numeric literal, so that `a+1` is lexed in the same
+/// way as `a+b`.
 pub fn tokens(
     file_id: FileId,
+    offset: usize,
     source: &str,
 ) -> impl Iterator<Item = Spanned<Token<'_>, usize, LexerError>> {
-    Token::lexer(source)
-        .spanned()
-        .map(move |(token, range)| match token {
-            Token::Error => Err(LexerError::InvalidToken {
-                location: Location::file_range(file_id, range),
-            }),
-            token => Ok((range.start, token, range.end)),
-        })
+    let mut lexer = Token::lexer(source).spanned().peekable();
+    let mut operand_end = None;
+    let mut pending_token = None;
+
+    std::iter::from_fn(move || {
+        if let Some(token) = pending_token.take() {
+            return Some(token);
+        }
+
+        let (token, range) = lexer.next()?;
+        let follows_operand = operand_end == Some(range.start);
+        operand_end = token.ends_operand().then_some(range.end);
+
+        let (start, end) = (offset + range.start, offset + range.end);
+        match token {
+            Token::Error => Some(Err(LexerError::InvalidToken {
+                location: Location::file_range(file_id, start..end),
+            })),
+            Token::LBrace => Some(Ok((start, Token::LBrace, start + 1))),
+            Token::NumericLiteral(literal)
+                if follows_operand && literal.starts_with(&['-', '+'][..]) =>
+            {
+                let (sign, literal) = literal.split_at(1);
+                pending_token = Some(Ok((start + 1, Token::NumericLiteral(literal), end)));
+                Some(Ok((start, Token::Operator(sign), start + 1)))
+            }
+            Token::Operator(operator) => match split_sign(operator) {
+                Some((symbol, sign)) => {
+                    let sign_start = range.end - sign.len();
+                    pending_token = match lexer.peek() {
+                        Some((Token::NumericLiteral(literal), literal_range))
+                            if literal_range.start == range.end
+                                && !literal.starts_with(&['-', '+'][..]) =>
+                        {
+                            let literal_end = literal_range.end;
+                            lexer.next();
+                            operand_end = Some(literal_end);
+                            let literal = Token::NumericLiteral(&source[sign_start..literal_end]);
+                            Some(Ok((offset + sign_start, literal, offset + literal_end)))
+                        }
+                        _ => Some(Ok((offset + sign_start, Token::Operator(sign), end))),
+                    };
+                    Some(Ok((start, symbol, offset + sign_start)))
+                }
+                None => Some(Ok((start, Token::Operator(operator), end))),
+            },
+            token => Some(Ok((start, token, end))),
+        }
+    })
 }
 
 #[test]
 fn behavior_after_error() {
     let starts_with_invalid = "@.";
     // [Err(...), Some(Token::DOT)]
-    let from_lex: Vec<_> = tokens(0, starts_with_invalid).collect();
+    let from_lex: Vec<_> = tokens(0, 0, starts_with_invalid).collect();
     let result: Vec<_> = from_lex.iter().map(Result::is_ok).collect();
     assert_eq!(result, vec![false, true]);
 }
+
+#[test]
+fn names_and_operators() {
+    let source = "größe-1 + λ-term -> x ++ a-b - -1";
+    let tokens: Vec<_> = tokens(0, 0, source)
+        .map(|token| token.unwrap().1.to_string())
+        .collect();
+    assert_eq!(
+        tokens,
+        vec!["größe-1", "+", "λ-term", "->", "x", "++", "a-b", "-", "-1"],
+    );
+}
+
+#[test]
+fn signs_after_operands() {
+    let source = "a+1 1+2 (a)-1 a -1 f(-1)";
+    let tokens: Vec<_> = tokens(0, 0, source)
+        .map(|token| token.unwrap().1.to_string())
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            "a", "+", "1", "1", "+", "2", "(", "a", ")", "-", "1", "a", "-1", "f", "(", "-1", ")",
+        ],
+    );
+}
+
+#[test]
+fn nested_block_comments() {
+    let source = "a {- b {- c -} d -} e {- f";
+    let tokens: Vec<_> = tokens(0, 0, source)
+        .map(|token| token.map(|(_, token, _)| token.to_string()))
+        .collect();
+    assert!(matches!(&tokens[..], [Ok(a), Ok(e), Err(_)] if a == "a" && e == "e"));
+}
+
+#[test]
+fn signs_after_reserved_symbols() {
+    let source = "x=-1 fun x=>+2 A ->-3";
+    let tokens: Vec<_> = tokens(0, 0, source)
+        .map(|token| token.unwrap().1.to_string())
+        .collect();
+    assert_eq!(
+        tokens,
+        vec!["x", "=", "-1", "fun", "x", "=>", "+2", "A", "->", "-3"],
+    );
+}
+
+#[test]
+fn line_comments_after_braces() {
+    let source = "record {-- comment\n x = 1 }";
+    let tokens: Vec<_> = tokens(0, 0, source)
+        .map(|token| token.map(|(start, token, end)| (start, token.to_string(), end)))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            (0, "record".to_owned(), 6),
+            (7, "{".to_owned(), 8),
+            (20, "x".to_owned(), 21),
+            (22, "=".to_owned(), 23),
+            (24, "1".to_owned(), 25),
+            (26, "}".to_owned(), 27),
+        ],
+    );
+}
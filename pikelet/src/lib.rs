@@ -5,8 +5,13 @@
 #![allow(clippy::new_without_default)]
 #![allow(clippy::while_let_on_iterator)]
 
+mod config;
 pub mod lang;
 pub mod pass;
 
 mod literal;
 pub mod reporting;
+
+pub use crate::config::{
+    from_path, from_path_with_globals, from_str, from_str_with_globals, Error,
+};
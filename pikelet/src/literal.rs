@@ -4,7 +4,10 @@
 
 use crossbeam_channel::Sender;
 use logos::Logos;
-use num_traits::{Float, PrimInt, Signed, Unsigned};
+use num_traits::{
+    CheckedAdd, CheckedMul, CheckedSub, Float, FromPrimitive, Signed, Unsigned, Zero,
+};
+use std::str::FromStr;
 
 use crate::lang::Location;
 use crate::reporting::LiteralParseMessage::*;
@@ -14,6 +17,8 @@ use crate::reporting::Message;
 pub const MAX_UNICODE: u32 = 0x10FFFF;
 /// The maximum character code permitted in ASCII escape sequences.
 pub const MAX_ASCII: u32 = 0x7F;
+/// The maximum byte permitted in the escape sequences of byte string literals.
+pub const MAX_BYTE: u32 = 0xFF;
 
 /// The sign of a numeric literal.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -86,23 +91,105 @@ enum Digit36 {
     Error,
 }
 
-/// Digits up to base 10.
+/// Digits in the mantissa of a float literal.
+///
+/// Exponent markers are lexed as digits, and are distinguished based on the
+/// base of the literal.
 #[derive(Debug, Clone, Logos)]
-enum Digit10 {
-    #[regex(b"[0-9]", ascii_digit)]
+enum FloatDigit {
+    #[regex(b"[0-9a-zA-Z]", ascii_digit)]
     Digit(u8),
     #[regex(b"_+")]
     Separator,
     #[token(b".")]
     StartFractional,
-    #[token(b"e")]
-    #[token(b"E")]
-    StartExponent,
 
     #[error]
     Error,
 }
 
+/// Digits in the exponent of a float literal.
+#[derive(Debug, Clone, Logos)]
+enum ExponentDigit {
+    #[token(b"+", |_| Sign::Positive)]
+    #[token(b"-", |_| Sign::Negative)]
+    Sign(Sign),
+    #[regex(b"[0-9]", ascii_digit)]
+    Digit(u8),
+    #[regex(b"_+")]
+    Separator,
+
+    #[error]
+    Error,
+}
+
+/// Integer types that numeric literals can be parsed into, including
+/// arbitrary-precision integers.
+pub trait IntegerLiteral: Zero + CheckedAdd + CheckedSub + CheckedMul + FromPrimitive {}
+
+impl<T> IntegerLiteral for T where T: Zero + CheckedAdd + CheckedSub + CheckedMul + FromPrimitive {}
+
+/// Floating point types that numeric literals can be parsed into.
+pub trait FloatLiteral: Float + FromStr {
+    /// The number of significant binary digits, including the implicit bit.
+    const MANTISSA_DIGITS: u32;
+    /// One more than the binary exponent of the smallest normal number.
+    const MIN_EXP: i32;
+}
+
+impl FloatLiteral for f32 {
+    const MANTISSA_DIGITS: u32 = f32::MANTISSA_DIGITS;
+    const MIN_EXP: i32 = f32::MIN_EXP;
+}
+
+impl FloatLiteral for f64 {
+    const MANTISSA_DIGITS: u32 = f64::MANTISSA_DIGITS;
+    const MIN_EXP: i32 = f64::MIN_EXP;
+}
+
+/// Compute `mantissa * 2^exponent`, correctly rounded to the nearest float,
+/// with ties rounded to even.
+fn binary_to_float<T: FloatLiteral>(mantissa: u64, exponent: i64) -> T {
+    if mantissa == 0 {
+        return T::zero();
+    }
+
+    // Find the number of bits to keep, taking subnormals into account
+    let mut mantissa = u128::from(mantissa);
+    let mut exponent = exponent;
+    let num_bits = i64::from(128 - mantissa.leading_zeros());
+    let min_normal_exp = i64::from(T::MIN_EXP) - 1;
+    let top_exp = exponent + num_bits - 1;
+    let num_kept_bits = match top_exp >= min_normal_exp {
+        true => i64::from(T::MANTISSA_DIGITS),
+        false => i64::from(T::MANTISSA_DIGITS) - (min_normal_exp - top_exp),
+    };
+
+    let shift = num_bits - num_kept_bits;
+    if shift > num_bits {
+        return T::zero(); // Less than half of the smallest subnormal
+    } else if shift > 0 {
+        let dropped_bits = mantissa & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        mantissa >>= shift;
+        exponent += shift;
+        if dropped_bits > half || (dropped_bits == half && mantissa & 1 == 1) {
+            mantissa += 1;
+        }
+    }
+
+    // The rounded mantissa is exactly representable, and scaling by powers of
+    // two is exact until the result is reached.
+    let two = T::one() + T::one();
+    let mut float = T::from(mantissa).unwrap();
+    while exponent != 0 && float.is_finite() && !float.is_zero() {
+        let step = exponent.clamp(-64, 64);
+        float = float * two.powi(step as i32);
+        exponent -= step;
+    }
+    float
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum Quote {
     Single,
@@ -130,10 +217,13 @@ enum QuotedLiteral {
 
 #[derive(Debug, Clone, Logos)]
 enum QuotedText<'source> {
-    #[regex(r#"[^\\"']+"#)]
+    #[regex(r#"[^\\"'$]+"#)]
+    #[token("$")]
     Utf8Text(&'source str),
     #[token("\\")]
     StartEscape,
+    #[token("${")]
+    StartInterpolation,
     #[token("\'", |_| Quote::Single)]
     #[token("\"", |_| Quote::Double)]
     End(Quote),
@@ -151,6 +241,7 @@ enum EscapeSequence {
     #[token("0", |_| '\0')]
     #[token("\'", |_| '\'')]
     #[token("\"", |_| '\"')]
+    #[token("$", |_| '$')]
     Single(char),
     #[token("u")]
     StartUnicodeEscape,
@@ -191,6 +282,97 @@ enum AsciiEscape<'source> {
     Error,
 }
 
+/// A part of a string literal.
+#[derive(Debug, Clone)]
+pub enum StringPart<'source> {
+    /// Text, with its escape sequences decoded.
+    Text(String),
+    /// The source of an interpolated term, along with its location.
+    Interpolation(Location, &'source str),
+}
+
+/// The quotes that delimit multi-line string literals.
+const MULTILINE_QUOTE: &str = r#"""""#;
+
+/// The number of whitespace characters at the start of a line.
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(&[' ', '\t'][..]).len()
+}
+
+/// Find the length of an interpolated term, up to its closing brace.
+fn interpolation_len(source: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, ch) in source.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(index),
+            '}' => depth -= 1,
+            '"' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Encode a string as the most readable form of string literal.
+///
+/// - Strings made up of lines are encoded as multi-line literals.
+/// - Strings that contain quotes or backslashes are encoded as raw literals.
+/// - Otherwise strings are encoded as double quoted literals.
+pub fn utf8_string_to_quoted(value: &str) -> String {
+    let is_control = |ch: char| ch.is_control() && ch != '\n' && ch != '\t';
+
+    if value.ends_with('\n') && value.matches('\n').count() > 1 && !value.chars().any(is_control) {
+        let mut literal = format!("{}\n", MULTILINE_QUOTE);
+        let mut chars = value.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match (ch, chars.peek()) {
+                ('\\', _) => literal.push_str("\\\\"),
+                // Avoid closing the literal early
+                ('"', Some('"')) => literal.push_str("\\\""),
+                // Avoid starting an interpolation
+                ('$', Some('{')) => literal.push_str("\\$"),
+                (ch, _) => literal.push(ch),
+            }
+        }
+        literal.push_str(MULTILINE_QUOTE);
+        literal
+    } else if value.contains(&['"', '\\'][..]) && !value.chars().any(|ch| ch.is_control()) {
+        let mut num_hashes = 0;
+        while value.contains(&format!("\"{}", "#".repeat(num_hashes))) {
+            num_hashes += 1;
+        }
+        let hashes = "#".repeat(num_hashes);
+        format!("r{}\"{}\"{}", hashes, value, hashes)
+    } else {
+        format!("{:?}", value).replace("${", "\\${")
+    }
+}
+
+/// Encode a sequence of bytes as the most readable form of byte string literal.
+///
+/// Bytes made up of printable ASCII text are encoded as byte string literals,
+/// and other bytes are encoded as hexadecimal literals.
+pub fn bytes_to_quoted(value: &[u8]) -> String {
+    if value
+        .iter()
+        .all(|byte| matches!(byte, b' '..=b'~' | b'\n' | b'\t'))
+    {
+        let text = value
+            .iter()
+            .map(|byte| char::from(*byte))
+            .collect::<String>();
+        format!("b{:?}", text).replace("${", "\\${")
+    } else {
+        let mut literal = String::from("x\"");
+        for byte in value {
+            literal.push_str(&format!("{:02x}", byte));
+        }
+        literal.push('"');
+        literal
+    }
+}
+
 /// Literal parser state.
 pub struct State<'source, 'messages> {
     location: Location,
@@ -222,22 +404,50 @@ impl<'source, 'messages> State<'source, 'messages> {
     where
         Token: Logos<'source>,
     {
+        self.range_location(lexer.span())
+    }
+
+    /// Get the file-relative location of a range in the literal.
+    fn range_location(&self, literal_range: std::ops::Range<usize>) -> Location {
         match self.location {
             Location::Generated => Location::Generated,
             Location::FileRange(file_id, range) => Location::file_range(
                 file_id,
-                (range.start + lexer.span().start)..(range.start + lexer.span().end),
+                (range.start + literal_range.start)..(range.start + literal_range.end),
             ),
         }
     }
 
+    /// Returns `true` if a numeric literal has a fractional part or an exponent,
+    /// and should therefore default to a floating point type.
+    pub fn number_is_float(&self) -> bool {
+        let mut lexer = NumericLiteral::lexer(self.source.as_bytes());
+
+        loop {
+            match lexer.next() {
+                Some(NumericLiteral::Sign(_)) => {}
+                Some(NumericLiteral::Digit(_)) => {
+                    let rest = &self.source[lexer.span().end..];
+                    return rest.contains(&['.', 'e', 'E'][..]);
+                }
+                Some(NumericLiteral::Base(Base::Hexadecimal)) => {
+                    let rest = &self.source[lexer.span().end..];
+                    return rest.contains(&['.', 'p', 'P'][..]);
+                }
+                Some(NumericLiteral::Base(_)) | Some(NumericLiteral::Error) | None => {
+                    return false;
+                }
+            }
+        }
+    }
+
     /// Parse a numeric literal into an unsigned integer.
     ///
     /// # Returns
     ///
     /// - `Some(_)`: If the literal was parsed correctly.
     /// - `None`: If a fatal error when parsing the literal.
-    pub fn number_to_unsigned_int<T: PrimInt + Unsigned>(self) -> Option<T> {
+    pub fn number_to_unsigned_int<T: IntegerLiteral + Unsigned>(self) -> Option<T> {
         let mut lexer = NumericLiteral::lexer(self.source.as_bytes());
 
         let (base, start_digit) = match self.expect_numeric_literal_start(&mut lexer)? {
@@ -283,7 +493,7 @@ impl<'source, 'messages> State<'source, 'messages> {
     ///
     /// - `Some(_)`: If the literal was parsed correctly.
     /// - `None`: If a fatal error when parsing the literal.
-    pub fn number_to_signed_int<T: PrimInt + Signed>(self) -> Option<T> {
+    pub fn number_to_signed_int<T: IntegerLiteral + Signed>(self) -> Option<T> {
         let mut lexer = NumericLiteral::lexer(self.source.as_bytes());
 
         let (sign, base, start_digit) = self.expect_numeric_literal_start(&mut lexer)?;
@@ -320,111 +530,180 @@ impl<'source, 'messages> State<'source, 'messages> {
         Some(integer)
     }
 
-    /// Parse a numeric literal into a float.
+    /// Parse a numeric literal into a float, correctly rounding the result.
     ///
     /// # Returns
     ///
     /// - `Some(_)`: If the literal was parsed correctly.
     /// - `None`: If a fatal error when parsing the literal.
-    pub fn number_to_float<T: Float + From<u8>>(self) -> Option<T> {
-        // NOTE: This could probably be improved a great deal.
-        // It might be worth looking at `lexical-core` crate as an alternative
-        // to implementing our own parser: https://github.com/Alexhuszagh/rust-lexical/
-
+    pub fn number_to_float<T: FloatLiteral>(self) -> Option<T> {
         let mut lexer = NumericLiteral::lexer(self.source.as_bytes());
+        let (sign, base, start_digit) = self.expect_numeric_literal_start(&mut lexer)?;
 
-        let add_digit = |sign, base: Base, float: T, digit: u8| match sign {
-            Sign::Positive => float * base.to_u8().into() + digit.into(),
-            Sign::Negative => float * base.to_u8().into() - digit.into(),
+        let exponent_marker = match base {
+            Base::Decimal => 14,     // `e` or `E`
+            Base::Hexadecimal => 25, // `p` or `P`
+            Base::Binary | Base::Octal => {
+                return self.report(UnsupportedFloatLiteralBase(self.location, base))
+            }
         };
 
-        let (sign, base, start_digit) = self.expect_numeric_literal_start(&mut lexer)?;
+        let mut lexer = lexer.morph();
+        let mut integer_digits = Vec::new();
+        let mut fractional_digits = Vec::new();
+        let mut has_fractional = false;
+        let mut has_exponent = false;
 
-        let mut float = T::zero();
-        let mut num_integer_digits = 0;
+        integer_digits.extend(start_digit);
 
-        if let Some(digit) = start_digit {
-            float = add_digit(sign, base, float, digit);
-            num_integer_digits += 1;
+        while let Some(token) = lexer.next() {
+            let location = self.token_location(&lexer);
+            match token {
+                FloatDigit::Digit(digit) if digit < base.to_u8() => integer_digits.push(digit),
+                FloatDigit::Digit(digit) if digit == exponent_marker => {
+                    has_exponent = true;
+                    break;
+                }
+                FloatDigit::Separator if !integer_digits.is_empty() => {}
+                FloatDigit::Separator => return self.report(ExpectedDigit(location, base)),
+                FloatDigit::StartFractional => {
+                    has_fractional = true;
+                    break;
+                }
+                FloatDigit::Digit(_) | FloatDigit::Error => match integer_digits.len() {
+                    0 => return self.report(ExpectedDigit(location, base)),
+                    _ => return self.report(ExpectedDigitSeparatorFracOrExp(location, base)),
+                },
+            }
         }
 
-        if base == Base::Decimal {
-            let mut lexer = lexer.morph();
-            let mut has_fractional = false;
-            let mut has_exponent = false;
+        if integer_digits.is_empty() {
+            return self.report(ExpectedDigit(self.token_location(&lexer), base));
+        }
 
+        if has_fractional {
             while let Some(token) = lexer.next() {
                 let location = self.token_location(&lexer);
                 match token {
-                    Digit10::Digit(digit) if digit < base.to_u8() => {
-                        float = add_digit(sign, base, float, digit);
-                        num_integer_digits += 1;
-                    }
-                    Digit10::Separator if num_integer_digits != 0 => {}
-                    Digit10::Separator => return self.report(ExpectedDigit(location, base)),
-                    Digit10::StartFractional => {
-                        has_fractional = true;
-                        break;
+                    FloatDigit::Digit(digit) if digit < base.to_u8() => {
+                        fractional_digits.push(digit)
                     }
-                    Digit10::StartExponent => {
+                    FloatDigit::Digit(digit) if digit == exponent_marker => {
                         has_exponent = true;
                         break;
                     }
-                    Digit10::Digit(_) | Digit10::Error => match num_integer_digits {
-                        0 => return self.report(ExpectedDigit(location, base)),
-                        _ => return self.report(ExpectedDigitSeparatorFracOrExp(location, base)),
-                    },
+                    FloatDigit::Separator if !fractional_digits.is_empty() => {}
+                    FloatDigit::Separator => return self.report(ExpectedDigit(location, base)),
+                    FloatDigit::Digit(_) | FloatDigit::StartFractional | FloatDigit::Error => {
+                        match fractional_digits.len() {
+                            0 => return self.report(ExpectedDigit(location, base)),
+                            _ => return self.report(ExpectedDigitSeparatorOrExp(location, base)),
+                        }
+                    }
                 }
             }
 
-            if num_integer_digits == 0 {
+            if fractional_digits.is_empty() {
                 return self.report(ExpectedDigit(self.token_location(&lexer), base));
             }
+        }
 
-            if has_fractional {
-                let mut frac = T::zero();
-                let mut num_frac_digits = 0;
+        let (exponent_sign, exponent_digits) = match has_exponent {
+            true => self.expect_exponent(lexer.morph())?,
+            false => (Sign::Positive, Vec::new()),
+        };
 
-                while let Some(token) = lexer.next() {
-                    let location = self.token_location(&lexer);
-                    match token {
-                        Digit10::Digit(digit) if digit < base.to_u8() => {
-                            frac = add_digit(sign, base, frac, digit);
-                            num_frac_digits += 1;
-                        }
-                        Digit10::Separator if num_frac_digits != 0 => {}
-                        Digit10::Separator => return self.report(ExpectedDigit(location, base)),
-                        Digit10::StartExponent => {
-                            has_exponent = true;
-                            break;
-                        }
-                        Digit10::Digit(_) | Digit10::StartFractional | Digit10::Error => {
-                            match num_frac_digits {
-                                0 => return self.report(ExpectedDigit(location, base)),
-                                _ => {
-                                    return self.report(ExpectedDigitSeparatorOrExp(location, base))
-                                }
-                            }
-                        }
+        let float = match base {
+            Base::Hexadecimal => {
+                // Saturate the exponent, which will round to zero or infinity
+                let exponent = (exponent_digits.iter()).fold(0_i64, |exponent, digit| {
+                    (exponent * 10 + i64::from(*digit)).min(1 << 20)
+                });
+                let exponent = match exponent_sign {
+                    Sign::Positive => exponent,
+                    Sign::Negative => -exponent,
+                };
+
+                // Accumulate the digits of the mantissa, keeping track of any
+                // non-zero digits that do not fit in a sticky bit.
+                let mut mantissa = 0_u64;
+                let mut exponent = exponent - 1;
+                let mut is_inexact = false;
+                for (index, digit) in integer_digits.iter().chain(&fractional_digits).enumerate() {
+                    let is_fractional = index >= integer_digits.len();
+                    if mantissa < 1 << 58 {
+                        mantissa = mantissa * 16 + u64::from(*digit);
+                        exponent -= if is_fractional { 4 } else { 0 };
+                    } else {
+                        is_inexact |= *digit != 0;
+                        exponent += if is_fractional { 0 } else { 4 };
                     }
                 }
+                let mantissa = (mantissa << 1) | u64::from(is_inexact);
 
-                if num_frac_digits == 0 {
-                    return self.report(ExpectedDigit(self.token_location(&lexer), base));
+                binary_to_float::<T>(mantissa, exponent)
+            }
+            _ => {
+                // Decimal literals are normalised and then parsed using the
+                // standard library, which ensures that they are correctly rounded.
+                let to_char = |digit: &u8| char::from(b'0' + digit);
+                let mut source = String::new();
+                source.extend(integer_digits.iter().map(to_char));
+                source.push('.');
+                source.extend(fractional_digits.iter().map(to_char));
+                source.push_str("0e");
+                if exponent_sign == Sign::Negative {
+                    source.push('-');
+                }
+                source.extend(exponent_digits.iter().map(to_char));
+                if exponent_digits.is_empty() {
+                    source.push('0');
                 }
 
-                float = float + frac / T::powi(base.to_u8().into(), num_frac_digits);
+                T::from_str(&source).ok()?
             }
+        };
+
+        // Non-zero literals that overflow to infinity or underflow to zero
+        // are out of range.
+        let is_non_zero =
+            (integer_digits.iter().chain(&fractional_digits)).any(|digit| *digit != 0);
+        match float.is_finite() && (float != T::zero() || !is_non_zero) {
+            true if sign == Sign::Negative => Some(-float),
+            true => Some(float),
+            false => self.report(LiteralOutOfRange(self.location)),
+        }
+    }
+
+    /// Expect the digits of an exponent, after the exponent marker.
+    fn expect_exponent(
+        &self,
+        mut lexer: logos::Lexer<'source, ExponentDigit>,
+    ) -> Option<(Sign, Vec<u8>)> {
+        let base = Base::Decimal;
+        let mut sign = None;
+        let mut digits = Vec::new();
 
-            if has_exponent {
-                return self.report(FloatLiteralExponentNotSupported(
-                    self.token_location(&lexer),
-                ));
+        while let Some(token) = lexer.next() {
+            let location = self.token_location(&lexer);
+            match token {
+                ExponentDigit::Sign(exponent_sign) if sign.is_none() && digits.is_empty() => {
+                    sign = Some(exponent_sign)
+                }
+                ExponentDigit::Digit(digit) => digits.push(digit),
+                ExponentDigit::Separator if !digits.is_empty() => {}
+                ExponentDigit::Separator | ExponentDigit::Sign(_) | ExponentDigit::Error => {
+                    match digits.len() {
+                        0 => return self.report(ExpectedDigit(location, base)),
+                        _ => return self.report(ExpectedDigitOrSeparator(location, base)),
+                    }
+                }
             }
+        }
 
-            Some(float)
-        } else {
-            self.report(UnsupportedFloatLiteralBase(self.location, base))
+        match digits.is_empty() {
+            true => self.report(ExpectedDigit(self.token_location(&lexer), base)),
+            false => Some((sign.unwrap_or(Sign::Positive), digits)),
         }
     }
 
@@ -451,12 +730,12 @@ impl<'source, 'messages> State<'source, 'messages> {
     /// Add a new place to the given integer, handling overflow and underflow.
     fn add_integer_digit<T>(&self, sign: Sign, base: Base, integer: T, digit: u8) -> Option<T>
     where
-        T: PrimInt,
+        T: IntegerLiteral,
     {
-        T::checked_mul(&integer, &T::from(base.to_u8()).unwrap())
+        T::checked_mul(&integer, &T::from_u8(base.to_u8()).unwrap())
             .and_then(|place_shifted| match sign {
-                Sign::Positive => T::checked_add(&place_shifted, &T::from(digit).unwrap()),
-                Sign::Negative => T::checked_sub(&place_shifted, &T::from(digit).unwrap()),
+                Sign::Positive => T::checked_add(&place_shifted, &T::from_u8(digit).unwrap()),
+                Sign::Negative => T::checked_sub(&place_shifted, &T::from_u8(digit).unwrap()),
             })
             .or_else(|| self.report(LiteralOutOfRange(self.location)))
     }
@@ -487,14 +766,19 @@ impl<'source, 'messages> State<'source, 'messages> {
                         }
                     }
                 }
+                QuotedText::StartInterpolation => {
+                    return self.report(OverlongCharLiteral(self.location))
+                }
                 QuotedText::StartEscape => match character {
-                    None => match self.expect_escape_sequence(lexer.morph(), end_quote)? {
-                        (_, None) => return None,
-                        (escape, Some(ch)) => {
-                            character = Some(ch);
-                            lexer = escape.morph();
+                    None => {
+                        match self.expect_escape_sequence(lexer.morph(), end_quote, MAX_ASCII)? {
+                            (_, None) => return None,
+                            (escape, Some(ch)) => {
+                                character = Some(ch);
+                                lexer = escape.morph();
+                            }
                         }
-                    },
+                    }
                     Some(_) => {
                         return self.report(OverlongCharLiteral(self.token_location(&lexer)))
                     }
@@ -520,55 +804,265 @@ impl<'source, 'messages> State<'source, 'messages> {
         }
     }
 
-    /// Parse a double quoted literal into a UTF-8 encoded string.
-    pub fn quoted_to_utf8_string(self) -> Option<String> {
+    /// Parse a string literal into the parts of a UTF-8 encoded string,
+    /// splitting out the source of any interpolated terms.
+    ///
+    /// This supports double quoted literals, multi-line literals, and raw literals.
+    pub fn quoted_to_utf8_string_parts(self) -> Option<Vec<StringPart<'source>>> {
+        if self.source.starts_with('r') {
+            return Some(vec![StringPart::Text(self.raw_to_utf8_string())]);
+        } else if self.source.starts_with(MULTILINE_QUOTE) {
+            return self.multiline_to_utf8_string_parts();
+        }
+
         let mut lexer = QuotedLiteral::lexer(self.source);
+        match self.expect_token(&mut lexer)? {
+            QuotedLiteral::Start(quote) => {
+                let start = lexer.span().end;
+                self.text_to_utf8_string_parts(start..self.source.len(), Some(quote), 0)
+            }
+            QuotedLiteral::Error => self.report(InvalidToken(self.token_location(&lexer))),
+        }
+    }
 
-        let (mut lexer, end_quote) = match self.expect_token(&mut lexer)? {
-            QuotedLiteral::Start(quote) => (lexer.morph(), quote),
-            QuotedLiteral::Error => return self.report(InvalidToken(self.token_location(&lexer))),
+    /// Parse the contents of a raw string literal, for example `r#"hello"#`.
+    fn raw_to_utf8_string(&self) -> String {
+        let num_hashes = self.source[1..].find('"').unwrap_or(0);
+        let start = num_hashes + 2;
+        let end = self.source.len() - (num_hashes + 1);
+        self.source.get(start..end).unwrap_or("").to_owned()
+    }
+
+    /// Parse a multi-line string literal, which starts with `"""` and a line break.
+    ///
+    /// The line break after the opening quote is omitted, along with the
+    /// indentation before the closing quote. Any indentation that is common to
+    /// the lines of the string and the closing quote is removed.
+    fn multiline_to_utf8_string_parts(self) -> Option<Vec<StringPart<'source>>> {
+        let quote_len = MULTILINE_QUOTE.len();
+        let content_end = self.source.len() - quote_len;
+        let content = &self.source[quote_len..content_end];
+
+        let start = match content.find('\n') {
+            Some(index) if content[..index].trim().is_empty() => quote_len + index + 1,
+            _ => {
+                let location = self.range_location(0..quote_len);
+                return self.report(ExpectedLineBreakAfterMultilineQuote(location));
+            }
+        };
+
+        let lines = &self.source[start..content_end];
+        let (end, closing_indent) = match lines.rfind('\n') {
+            Some(index) if lines[(index + 1)..].trim().is_empty() => {
+                (start + index + 1, Some(indentation(&lines[(index + 1)..])))
+            }
+            None if lines.trim().is_empty() => (start, Some(indentation(lines))),
+            Some(_) | None => (content_end, None),
         };
 
-        let mut string = Some(String::new());
+        let indent = (self.source[start..end].lines())
+            .filter(|line| !line.trim().is_empty())
+            .map(indentation)
+            .chain(closing_indent)
+            .min()
+            .unwrap_or(0);
+
+        self.text_to_utf8_string_parts(start..end, None, indent)
+    }
+
+    /// Parse the text in a string literal, with escape sequences and
+    /// interpolated terms, removing `indent` whitespace characters from the
+    /// start of each line.
+    ///
+    /// If `end_quote` is not supplied the text ends at the end of the range.
+    fn text_to_utf8_string_parts(
+        &self,
+        range: std::ops::Range<usize>,
+        end_quote: Option<Quote>,
+        indent: usize,
+    ) -> Option<Vec<StringPart<'source>>> {
+        let mut lexer = QuotedText::lexer(&self.source[..range.end]);
+        lexer.bump(range.start);
+
+        let mut parts = Some(Vec::new());
+        let mut text = String::new();
+        let mut line_indent = Some(0);
 
         'quoted_text: loop {
-            match self.expect_token(&mut lexer)? {
-                QuotedText::Utf8Text(text) => {
-                    if let Some(string) = &mut string {
-                        string.push_str(text);
+            let token = match lexer.next() {
+                Some(token) => token,
+                None if end_quote.is_none() => break 'quoted_text,
+                None => return self.report(UnexpectedEndOfLiteral(self.token_location(&lexer))),
+            };
+
+            match token {
+                QuotedText::Utf8Text(utf8_text) => {
+                    for ch in utf8_text.chars() {
+                        match line_indent {
+                            Some(skipped) if skipped < indent && (ch == ' ' || ch == '\t') => {
+                                line_indent = Some(skipped + 1);
+                            }
+                            _ => {
+                                line_indent = if ch == '\n' { Some(0) } else { None };
+                                text.push(ch);
+                            }
+                        }
                     }
                 }
                 QuotedText::StartEscape => {
+                    let escape_quote = end_quote.unwrap_or(Quote::Double);
                     let (escape_lexer, ch) =
-                        self.expect_escape_sequence(lexer.morph(), end_quote)?;
+                        self.expect_escape_sequence(lexer.morph(), escape_quote, MAX_ASCII)?;
                     lexer = escape_lexer.morph();
+                    line_indent = None;
 
                     match ch {
-                        None => string = None,
-                        Some(ch) => {
-                            if let Some(string) = &mut string {
-                                string.push(ch);
-                            }
+                        None => parts = None,
+                        Some(ch) => text.push(ch),
+                    }
+                }
+                QuotedText::StartInterpolation => {
+                    let start = lexer.span().end;
+                    let len = match interpolation_len(&self.source[start..]) {
+                        Some(len) => len,
+                        None => {
+                            return self
+                                .report(UnterminatedInterpolation(self.token_location(&lexer)))
+                        }
+                    };
+                    let location = self.range_location(start..(start + len));
+                    lexer.bump(len + 1);
+                    line_indent = None;
+
+                    if let Some(parts) = &mut parts {
+                        if !text.is_empty() {
+                            parts.push(StringPart::Text(std::mem::take(&mut text)));
                         }
+                        let source = &self.source[start..(start + len)];
+                        parts.push(StringPart::Interpolation(location, source));
                     }
                 }
-                QuotedText::End(quote) if quote == end_quote => match lexer.next() {
+                QuotedText::End(quote) if Some(quote) == end_quote => match lexer.next() {
                     None => break 'quoted_text,
                     Some(_) => {
                         return self.report(ExpectedEndOfLiteral(self.token_location(&lexer)))
                     }
                 },
                 QuotedText::End(quote) => {
-                    if let Some(string) = &mut string {
-                        string.push(quote.to_char());
-                    }
+                    line_indent = None;
+                    text.push(quote.to_char());
                 }
 
                 QuotedText::Error => return self.report(InvalidToken(self.token_location(&lexer))),
             }
         }
 
-        string
+        let mut parts = parts?;
+        if !text.is_empty() || parts.is_empty() {
+            parts.push(StringPart::Text(text));
+        }
+        Some(parts)
+    }
+
+    /// Parse a byte string literal into a sequence of bytes.
+    ///
+    /// This supports byte string literals, like `b"PIKC"`, where text is
+    /// encoded as UTF-8 and `\x` escapes can be used for arbitrary bytes, and
+    /// hexadecimal literals, like `x"dead beef"`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(_)`: If the literal was parsed correctly.
+    /// - `None`: If a fatal error when parsing the literal.
+    pub fn quoted_to_bytes(self) -> Option<Vec<u8>> {
+        match self.source.get(..2) {
+            Some("x\"") => self.hex_to_bytes(),
+            Some("b\"") => self.text_to_bytes(),
+            _ => self.report(InvalidToken(self.range_location(0..1))),
+        }
+    }
+
+    /// Parse a hexadecimal byte string literal, ignoring whitespace and digit
+    /// separators between the digits.
+    fn hex_to_bytes(self) -> Option<Vec<u8>> {
+        let end = self.source.len() - 1;
+        let mut bytes = Some(Vec::new());
+        let mut high_digit = None;
+
+        for (index, ch) in self.source[2..end].char_indices() {
+            let index = index + 2;
+            match (ch.to_digit(16), high_digit) {
+                (Some(digit), None) => high_digit = Some((index, digit as u8)),
+                (Some(digit), Some((_, high))) => {
+                    high_digit = None;
+                    if let Some(bytes) = &mut bytes {
+                        bytes.push(high << 4 | digit as u8);
+                    }
+                }
+                (None, _) if ch.is_whitespace() || ch == '_' => {}
+                (None, _) => {
+                    let location = self.range_location(index..(index + ch.len_utf8()));
+                    bytes = self.report(ExpectedHexDigit(location));
+                }
+            }
+        }
+
+        match high_digit {
+            None => bytes,
+            Some((index, _)) => self.report(IncompleteHexByte(self.range_location(index..end))),
+        }
+    }
+
+    /// Parse a byte string literal with escape sequences.
+    fn text_to_bytes(self) -> Option<Vec<u8>> {
+        let mut lexer = QuotedText::lexer(self.source);
+        lexer.bump(2);
+
+        let mut bytes = Some(Vec::new());
+
+        loop {
+            match self.expect_token(&mut lexer)? {
+                QuotedText::Utf8Text(text) => {
+                    if let Some(bytes) = &mut bytes {
+                        bytes.extend_from_slice(text.as_bytes());
+                    }
+                }
+                // Byte strings do not support interpolation
+                QuotedText::StartInterpolation => {
+                    if let Some(bytes) = &mut bytes {
+                        bytes.extend_from_slice(b"${");
+                    }
+                }
+                QuotedText::StartEscape => {
+                    let is_byte_escape = self.source[lexer.span().end..].starts_with('x');
+                    let (escape_lexer, ch) =
+                        self.expect_escape_sequence(lexer.morph(), Quote::Double, MAX_BYTE)?;
+                    lexer = escape_lexer.morph();
+
+                    match (ch, &mut bytes) {
+                        (None, _) => bytes = None,
+                        (Some(ch), Some(bytes)) if is_byte_escape => bytes.push(ch as u8),
+                        (Some(ch), Some(bytes)) => {
+                            bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes())
+                        }
+                        (Some(_), None) => {}
+                    }
+                }
+                QuotedText::End(Quote::Double) => match lexer.next() {
+                    None => return bytes,
+                    Some(_) => {
+                        return self.report(ExpectedEndOfLiteral(self.token_location(&lexer)))
+                    }
+                },
+                QuotedText::End(Quote::Single) => {
+                    if let Some(bytes) = &mut bytes {
+                        bytes.push(b'\'');
+                    }
+                }
+
+                QuotedText::Error => return self.report(InvalidToken(self.token_location(&lexer))),
+            }
+        }
     }
 
     /// Expect another token to be present in the lexer, reporting an error if not.
@@ -598,6 +1092,7 @@ impl<'source, 'messages> State<'source, 'messages> {
         &self,
         mut lexer: logos::Lexer<'source, EscapeSequence>,
         end_quote: Quote,
+        max_ascii: u32,
     ) -> Option<(logos::Lexer<'source, EscapeSequence>, Option<char>)> {
         match self.expect_token(&mut lexer)? {
             EscapeSequence::Single(ch) => Some((lexer, Some(ch))),
@@ -638,7 +1133,7 @@ impl<'source, 'messages> State<'source, 'messages> {
                 let ch = match next {
                     AsciiEscape::CharCode(code) if code.len() == 2 => {
                         match u32::from_str_radix(code, 16).unwrap() {
-                            code @ 0..=MAX_ASCII => Some(std::char::from_u32(code).unwrap()),
+                            code if code <= max_ascii => Some(std::char::from_u32(code).unwrap()),
                             _ => self.report(OversizedAsciiEscapeCode(location)),
                         }
                     }
@@ -660,3 +1155,169 @@ impl<'source, 'messages> State<'source, 'messages> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_float<T: FloatLiteral>(source: &str) -> Option<T> {
+        let (message_tx, _message_rx) = crossbeam_channel::unbounded();
+        State::new(Location::generated(), source, &message_tx).number_to_float()
+    }
+
+    fn parse_string(source: &str) -> Option<String> {
+        let (message_tx, _message_rx) = crossbeam_channel::unbounded();
+        let parts =
+            State::new(Location::generated(), source, &message_tx).quoted_to_utf8_string_parts()?;
+        match parts.as_slice() {
+            [StringPart::Text(text)] => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn raw_strings() {
+        assert_eq!(parse_string(r#"r"C:\path""#), Some(r"C:\path".to_owned()));
+        assert_eq!(
+            parse_string(r##"r#"say "${hi}""#"##),
+            Some(r#"say "${hi}""#.to_owned())
+        );
+    }
+
+    #[test]
+    fn multiline_strings() {
+        let source = "\"\"\"\n    SELECT *\n      FROM t\n\n    \"\"\"";
+        assert_eq!(
+            parse_string(source),
+            Some("SELECT *\n  FROM t\n\n".to_owned())
+        );
+        let source = "\"\"\"  \n    first\n  second\"\"\"";
+        assert_eq!(parse_string(source), Some("  first\nsecond".to_owned()));
+        let source = "\"\"\"\n    indented\n\"\"\"";
+        assert_eq!(parse_string(source), Some("    indented\n".to_owned()));
+        let source = "\"\"\"\n  \\t\"\"\\\"\"\"\n  \"\"\"";
+        assert_eq!(parse_string(source), Some("\t\"\"\"\"\"\n".to_owned()));
+        assert_eq!(parse_string("\"\"\"\n\"\"\""), Some("".to_owned()));
+        assert_eq!(parse_string("\"\"\"text\n\"\"\""), None);
+    }
+
+    #[test]
+    fn string_round_trips() {
+        let values = [
+            "hello",
+            "${not-interpolated}",
+            r#"C:\path\"quoted""#,
+            "\"#",
+            "line\r\n",
+            "single line\n",
+            "  indented\n    lines\n\n  with \"\"\" and ${braces} \\\n",
+            "\ttabs\n\tand trailing space \n",
+        ];
+        for value in &values {
+            let literal = utf8_string_to_quoted(value);
+            assert_eq!(
+                parse_string(&literal).as_deref(),
+                Some(*value),
+                "{}",
+                literal
+            );
+        }
+        assert!(utf8_string_to_quoted("a\nb\n").starts_with(MULTILINE_QUOTE));
+        assert!(utf8_string_to_quoted(r"a\b").starts_with('r'));
+    }
+
+    fn parse_bytes(source: &str) -> Option<Vec<u8>> {
+        let (message_tx, _message_rx) = crossbeam_channel::unbounded();
+        State::new(Location::generated(), source, &message_tx).quoted_to_bytes()
+    }
+
+    #[test]
+    fn byte_strings() {
+        assert_eq!(parse_bytes(r#"b"PIKC""#), Some(b"PIKC".to_vec()));
+        assert_eq!(
+            parse_bytes(r#"b"\x00\xff\n${x}λ""#),
+            Some(b"\x00\xff\n${x}\xce\xbb".to_vec()),
+        );
+        assert_eq!(
+            parse_bytes(r#"x"dead_beef 01""#),
+            Some(vec![0xde, 0xad, 0xbe, 0xef, 0x01]),
+        );
+        assert_eq!(parse_bytes(r#"x"""#), Some(vec![]));
+        assert_eq!(parse_bytes(r#"x"abc""#), None);
+        assert_eq!(parse_bytes(r#"x"zz""#), None);
+    }
+
+    #[test]
+    fn bytes_round_trips() {
+        let values: [&[u8]; 5] = [
+            b"",
+            b"PIKC",
+            b"say \"${hi}\"\n",
+            b"\x00\xff",
+            "λ".as_bytes(),
+        ];
+        for value in &values {
+            let literal = bytes_to_quoted(value);
+            assert_eq!(
+                parse_bytes(&literal).as_deref(),
+                Some(*value),
+                "{}",
+                literal
+            );
+        }
+        assert_eq!(bytes_to_quoted(b"\xde\xad"), r#"x"dead""#);
+    }
+
+    #[test]
+    fn decimal_floats() {
+        assert_eq!(parse_float::<f64>("0.1"), Some(0.1));
+        assert_eq!(parse_float::<f64>("-1_000.5"), Some(-1000.5));
+        assert_eq!(parse_float::<f64>("6.022e23"), Some(6.022e23));
+        assert_eq!(parse_float::<f64>("1E-7"), Some(1e-7));
+        assert_eq!(parse_float::<f32>("16777217"), Some(16777216.0));
+        assert_eq!(parse_float::<f64>("5e-324"), Some(5e-324));
+        assert_eq!(parse_float::<f64>("1e309"), None);
+        assert_eq!(parse_float::<f64>("1e-400"), None);
+        assert_eq!(parse_float::<f32>("1e-46"), None);
+        assert_eq!(parse_float::<f64>("0.0e-400"), Some(0.0));
+        assert_eq!(parse_float::<f64>("1e"), None);
+    }
+
+    #[test]
+    fn hexadecimal_floats() {
+        assert_eq!(parse_float::<f64>("0x1.8p3"), Some(12.0));
+        assert_eq!(parse_float::<f64>("0xA"), Some(10.0));
+        assert_eq!(parse_float::<f64>("-0x1p-2"), Some(-0.25));
+        assert_eq!(parse_float::<f64>("0x1.fffffffffffffp1023"), Some(f64::MAX));
+        assert_eq!(parse_float::<f64>("0x1p-1074"), Some(5e-324));
+        assert_eq!(parse_float::<f64>("0x1p-1075"), None);
+        assert_eq!(parse_float::<f64>("0x1p-1080"), None);
+        assert_eq!(parse_float::<f64>("0x0p-1080"), Some(0.0));
+        assert_eq!(parse_float::<f64>("0x1.8p-1074"), Some(1e-323));
+        assert_eq!(parse_float::<f32>("0x1.000001p0"), Some(1.0));
+        assert_eq!(parse_float::<f32>("0x1.000003p0"), Some(1.0000002));
+        assert_eq!(
+            parse_float::<f32>("0x1.0000010000000000000001p0"),
+            Some(1.0000001)
+        );
+        assert_eq!(parse_float::<f64>("0x1p1024"), None);
+    }
+
+    #[test]
+    fn float_round_trips() {
+        for value in &[
+            0.1,
+            1.0,
+            1e300,
+            1.5e-300,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+        ] {
+            assert_eq!(parse_float::<f64>(&format!("{:?}", value)), Some(*value));
+        }
+        for value in &[0.1_f32, 3.4028235e38, 1e-45] {
+            assert_eq!(parse_float::<f32>(&format!("{:?}", value)), Some(*value));
+        }
+    }
+}
@@ -4,6 +4,10 @@
 //! which handles elaboration of the surface language into the core language,
 //! and is the source of most user-facing typing diagnostics.
 
+pub mod binary_to_core;
+pub mod core_to_binary;
+pub mod core_to_doc;
+pub mod core_to_json;
 pub mod core_to_pretty;
 pub mod core_to_surface;
 pub mod surface_to_core;
@@ -0,0 +1,518 @@
+//! Deserializes the [core language] from the binary format produced by the
+//! [`core_to_binary`] pass.
+//!
+//! Decoded terms are not trusted: they should be re-validated with
+//! [`core::typing`] before they are used. Terms that are nested more deeply
+//! than [`MAX_DEPTH`] are rejected, to avoid overflowing the stack.
+//!
+//! [core language]: crate::lang::core
+//! [`core_to_binary`]: crate::pass::core_to_binary
+//! [`core::typing`]: crate::lang::core::typing
+
+use num_bigint::{BigInt, BigUint};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::lang::core::{Constant, Term, TermData, VarIndex};
+use crate::lang::{Located, Location, Plicity};
+use crate::pass::core_to_binary::{checksum, tags, FLAG_LOCATIONS, MAGIC, VERSION};
+
+/// The maximum depth that terms can be nested to when encoding or decoding.
+pub const MAX_DEPTH: usize = 128;
+
+/// Errors produced when decoding a term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The input did not start with the expected magic bytes.
+    InvalidMagic,
+    /// The input was encoded with an unsupported version of the format.
+    UnsupportedVersion(u16),
+    /// The input contained unknown flags.
+    UnsupportedFlags(u8),
+    /// The checksum at the end of the input did not match its contents.
+    InvalidChecksum { expected: u32, found: u32 },
+    /// The input ended before the term was fully decoded.
+    UnexpectedEndOfInput,
+    /// There were bytes remaining after the term was decoded.
+    TrailingBytes,
+    /// An unknown tag was encountered.
+    InvalidTag { kind: &'static str, tag: u8 },
+    /// A variable-length integer was too large to be decoded.
+    IntegerOverflow,
+    /// A string contained invalid UTF-8.
+    InvalidUtf8,
+    /// A character was not a valid Unicode scalar value.
+    InvalidChar(u32),
+    /// Terms were nested more deeply than [`MAX_DEPTH`].
+    NestingTooDeep,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidMagic => write!(f, "invalid magic bytes"),
+            Error::UnsupportedVersion(version) => write!(f, "unsupported version `{}`", version),
+            Error::UnsupportedFlags(flags) => write!(f, "unsupported flags `{:#010b}`", flags),
+            Error::InvalidChecksum { expected, found } => write!(
+                f,
+                "invalid checksum: expected `{:#010x}`, found `{:#010x}`",
+                expected, found,
+            ),
+            Error::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+            Error::TrailingBytes => write!(f, "unexpected bytes after term"),
+            Error::InvalidTag { kind, tag } => write!(f, "invalid {} tag `{}`", kind, tag),
+            Error::IntegerOverflow => write!(f, "integer overflow"),
+            Error::InvalidUtf8 => write!(f, "invalid UTF-8 in string"),
+            Error::InvalidChar(value) => write!(f, "invalid character `{:#x}`", value),
+            Error::NestingTooDeep => {
+                write!(f, "terms nested more deeply than {} levels", MAX_DEPTH)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Decode a term from some bytes.
+pub fn from_bytes(bytes: &[u8]) -> Result<Term, Error> {
+    const HEADER_LEN: usize = MAGIC.len() + 2 + 1;
+    const CHECKSUM_LEN: usize = 4;
+
+    if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::InvalidMagic);
+    }
+    if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+        return Err(Error::UnexpectedEndOfInput);
+    }
+
+    let version = u16::from_le_bytes([bytes[MAGIC.len()], bytes[MAGIC.len() + 1]]);
+    if version != VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    let (contents, checksum_bytes) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    let expected = checksum(contents);
+    let found = u32::from_le_bytes([
+        checksum_bytes[0],
+        checksum_bytes[1],
+        checksum_bytes[2],
+        checksum_bytes[3],
+    ]);
+    if expected != found {
+        return Err(Error::InvalidChecksum { expected, found });
+    }
+
+    let flags = contents[HEADER_LEN - 1];
+    if flags & !FLAG_LOCATIONS != 0 {
+        return Err(Error::UnsupportedFlags(flags));
+    }
+
+    let mut decoder = Decoder {
+        bytes: &contents[HEADER_LEN..],
+        include_locations: flags & FLAG_LOCATIONS != 0,
+        depth: 0,
+    };
+    let term = decoder.term()?;
+
+    match decoder.bytes.is_empty() {
+        true => Ok(term),
+        false => Err(Error::TrailingBytes),
+    }
+}
+
+struct Decoder<'bytes> {
+    bytes: &'bytes [u8],
+    include_locations: bool,
+    depth: usize,
+}
+
+impl<'bytes> Decoder<'bytes> {
+    fn take(&mut self, len: usize) -> Result<&'bytes [u8], Error> {
+        if self.bytes.len() < len {
+            return Err(Error::UnexpectedEndOfInput);
+        }
+        let (taken, bytes) = self.bytes.split_at(len);
+        self.bytes = bytes;
+        Ok(taken)
+    }
+
+    fn take_array<const LEN: usize>(&mut self) -> Result<[u8; LEN], Error> {
+        let mut array = [0; LEN];
+        array.copy_from_slice(self.take(LEN)?);
+        Ok(array)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn unsigned(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = u64::from(byte & 0x7f);
+            if shift == 63 && bits > 1 {
+                return Err(Error::IntegerOverflow);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::IntegerOverflow)
+    }
+
+    fn unsigned_as<T: std::convert::TryFrom<u64>>(&mut self) -> Result<T, Error> {
+        T::try_from(self.unsigned()?).map_err(|_| Error::IntegerOverflow)
+    }
+
+    fn signed(&mut self) -> Result<i64, Error> {
+        let value = self.unsigned()?;
+        Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+    }
+
+    fn signed_as<T: std::convert::TryFrom<i64>>(&mut self) -> Result<T, Error> {
+        T::try_from(self.signed()?).map_err(|_| Error::IntegerOverflow)
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let bytes = self.byte_string()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| Error::InvalidUtf8)
+    }
+
+    fn byte_string(&mut self) -> Result<&'bytes [u8], Error> {
+        let len = self.unsigned_as::<usize>()?;
+        self.take(len)
+    }
+
+    fn location(&mut self) -> Result<Location, Error> {
+        match self.byte()? {
+            tags::LOCATION_GENERATED => Ok(Location::Generated),
+            tags::LOCATION_FILE_RANGE => {
+                let file_id = self.unsigned_as()?;
+                let start = self.unsigned_as()?;
+                let end = self.unsigned_as()?;
+                Ok(Location::file_range(file_id, start..end))
+            }
+            tag => Err(Error::InvalidTag {
+                kind: "location",
+                tag,
+            }),
+        }
+    }
+
+    fn constant(&mut self) -> Result<Constant, Error> {
+        match self.byte()? {
+            tags::CONSTANT_U8 => Ok(Constant::U8(self.byte()?)),
+            tags::CONSTANT_U16 => Ok(Constant::U16(self.unsigned_as()?)),
+            tags::CONSTANT_U32 => Ok(Constant::U32(self.unsigned_as()?)),
+            tags::CONSTANT_U64 => Ok(Constant::U64(self.unsigned()?)),
+            tags::CONSTANT_U128 => Ok(Constant::U128(u128::from_le_bytes(self.take_array()?))),
+            tags::CONSTANT_S8 => Ok(Constant::S8(self.byte()? as i8)),
+            tags::CONSTANT_S16 => Ok(Constant::S16(self.signed_as()?)),
+            tags::CONSTANT_S32 => Ok(Constant::S32(self.signed_as()?)),
+            tags::CONSTANT_S64 => Ok(Constant::S64(self.signed()?)),
+            tags::CONSTANT_S128 => Ok(Constant::S128(i128::from_le_bytes(self.take_array()?))),
+            tags::CONSTANT_NAT => Ok(Constant::Nat(BigUint::from_bytes_le(self.byte_string()?))),
+            tags::CONSTANT_INT => Ok(Constant::Int(BigInt::from_signed_bytes_le(
+                self.byte_string()?,
+            ))),
+            tags::CONSTANT_F32 => {
+                let bytes = self.take(4)?;
+                let bits = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                Ok(Constant::F32(f32::from_bits(bits)))
+            }
+            tags::CONSTANT_F64 => {
+                let bytes = self.take(8)?;
+                let mut bits = [0; 8];
+                bits.copy_from_slice(bytes);
+                Ok(Constant::F64(f64::from_bits(u64::from_le_bytes(bits))))
+            }
+            tags::CONSTANT_CHAR => {
+                let value = self.unsigned_as::<u32>()?;
+                let value = std::char::from_u32(value).ok_or(Error::InvalidChar(value))?;
+                Ok(Constant::Char(value))
+            }
+            tags::CONSTANT_STRING => Ok(Constant::String(self.string()?)),
+            tags::CONSTANT_BYTES => Ok(Constant::Bytes(self.byte_string()?.to_vec())),
+            tag => Err(Error::InvalidTag {
+                kind: "constant",
+                tag,
+            }),
+        }
+    }
+
+    fn plicity(&mut self) -> Result<Plicity, Error> {
+        match self.byte()? {
+            tags::PLICITY_EXPLICIT => Ok(Plicity::Explicit),
+            tags::PLICITY_IMPLICIT => Ok(Plicity::Implicit),
+            tag => Err(Error::InvalidTag {
+                kind: "plicity",
+                tag,
+            }),
+        }
+    }
+
+    fn terms(&mut self) -> Result<Vec<Arc<Term>>, Error> {
+        let len = self.unsigned_as::<usize>()?;
+        // Avoid trusting the length when pre-allocating
+        let mut terms = Vec::with_capacity(std::cmp::min(len, self.bytes.len()));
+        for _ in 0..len {
+            terms.push(Arc::new(self.term()?));
+        }
+        Ok(terms)
+    }
+
+    fn optional_terms(&mut self) -> Result<Vec<Option<Arc<Term>>>, Error> {
+        let len = self.unsigned_as::<usize>()?;
+        let mut terms = Vec::with_capacity(std::cmp::min(len, self.bytes.len()));
+        for _ in 0..len {
+            terms.push(match self.byte()? {
+                tags::OPTION_NONE => None,
+                tags::OPTION_SOME => Some(Arc::new(self.term()?)),
+                tag => {
+                    return Err(Error::InvalidTag {
+                        kind: "option",
+                        tag,
+                    })
+                }
+            });
+        }
+        Ok(terms)
+    }
+
+    fn strings(&mut self) -> Result<Arc<[String]>, Error> {
+        let len = self.unsigned_as::<usize>()?;
+        let mut values = Vec::with_capacity(std::cmp::min(len, self.bytes.len()));
+        for _ in 0..len {
+            values.push(self.string()?);
+        }
+        Ok(values.into())
+    }
+
+    fn term(&mut self) -> Result<Term, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+
+        self.depth += 1;
+        let term = self.term_impl();
+        self.depth -= 1;
+        term
+    }
+
+    fn term_impl(&mut self) -> Result<Term, Error> {
+        let location = match self.include_locations {
+            true => self.location()?,
+            false => Location::Generated,
+        };
+
+        let data = match self.byte()? {
+            tags::TERM_GLOBAL => TermData::Global(self.string()?),
+            tags::TERM_VAR => TermData::Var(VarIndex::new(self.unsigned_as()?)),
+            tags::TERM_ANN => TermData::Ann(Arc::new(self.term()?), Arc::new(self.term()?)),
+            tags::TERM_TYPE_TYPE => TermData::TypeType,
+            tags::TERM_FUNCTION_TYPE => {
+                let plicity = self.plicity()?;
+                let input_name_hint = match self.byte()? {
+                    tags::OPTION_NONE => None,
+                    tags::OPTION_SOME => Some(self.string()?),
+                    tag => {
+                        return Err(Error::InvalidTag {
+                            kind: "option",
+                            tag,
+                        })
+                    }
+                };
+                let input_type = self.term()?;
+                let output_type = self.term()?;
+                TermData::FunctionType(
+                    plicity,
+                    input_name_hint,
+                    Arc::new(input_type),
+                    Arc::new(output_type),
+                )
+            }
+            tags::TERM_FUNCTION_TERM => {
+                TermData::FunctionTerm(self.plicity()?, self.string()?, Arc::new(self.term()?))
+            }
+            tags::TERM_FUNCTION_ELIM => {
+                let head_term = self.term()?;
+                let plicity = self.plicity()?;
+                TermData::FunctionElim(Arc::new(head_term), plicity, Arc::new(self.term()?))
+            }
+            tags::TERM_RECORD_TYPE => TermData::RecordType(
+                self.strings()?,
+                self.strings()?,
+                self.terms()?.into(),
+                self.optional_terms()?.into(),
+            ),
+            tags::TERM_RECORD_TERM => TermData::RecordTerm(self.strings()?, self.terms()?.into()),
+            tags::TERM_RECORD_ELIM => TermData::RecordElim(Arc::new(self.term()?), self.string()?),
+            tags::TERM_VARIANT_TYPE => TermData::VariantType(self.strings()?, self.terms()?.into()),
+            tags::TERM_VARIANT_TERM => {
+                TermData::VariantTerm(self.string()?, Arc::new(self.term()?))
+            }
+            tags::TERM_VARIANT_ELIM => TermData::VariantElim(
+                Arc::new(self.term()?),
+                self.strings()?,
+                self.terms()?.into(),
+            ),
+            tags::TERM_DATA_ELIM => TermData::DataElim(
+                Arc::new(self.term()?),
+                self.string()?,
+                Arc::new(self.term()?),
+                self.terms()?.into(),
+            ),
+            tags::TERM_ARRAY_TERM => TermData::ArrayTerm(self.terms()?),
+            tags::TERM_LIST_TERM => TermData::ListTerm(self.terms()?),
+            tags::TERM_CONSTANT => TermData::Constant(self.constant()?),
+            tags::TERM_ERROR => TermData::Error,
+            tag => return Err(Error::InvalidTag { kind: "term", tag }),
+        };
+
+        Ok(Located::new(location, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::core::{typing, Globals, MetaVar};
+    use crate::lang::surface;
+    use crate::pass::{core_to_binary, surface_to_core};
+
+    fn elaborate(globals: &Globals, source: &str) -> Term {
+        let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
+        let surface_term = surface::Term::from_str(0, source, &messages_tx);
+        let mut context = surface_to_core::Context::new(globals, messages_tx);
+        let (core_term, _) = context.synth_type(&surface_term);
+        assert!(messages_rx.try_iter().next().is_none());
+        core_term
+    }
+
+    const SOURCE: &str = r#"
+        record {
+            id = fun A a => a,
+            point = record { x = 1, y = -2.5 },
+            names = ["hello", "world"],
+            flags = [true, false],
+            letter = 'λ',
+            population = 123456789012345678901234567890,
+            balance = -98765432109876543210,
+            serial = 0xFFFF_FFFF_FFFF_FFFF_FFFF,
+            offset = -170141183460469231731687303715884105728,
+            magic = x"dead beef",
+        } : Record {
+            id : Fun (A : Type) -> A -> A,
+            point : Record { x : U64, y : F32 },
+            names : List String,
+            flags : Array 2 Bool,
+            letter : Char,
+            population : Nat,
+            balance : Int,
+            serial : U128,
+            offset : S128,
+            magic : Bytes,
+        }
+    "#;
+
+    #[test]
+    fn round_trip_validates() {
+        let globals = Globals::default();
+        let core_term = elaborate(&globals, SOURCE);
+
+        for include_locations in [false, true].iter() {
+            let bytes = core_to_binary::from_term(&core_term, *include_locations).unwrap();
+            let decoded_term = from_bytes(&bytes).unwrap();
+            assert_eq!(
+                core_to_binary::from_term(&decoded_term, *include_locations),
+                Ok(bytes),
+            );
+
+            let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
+            let mut context = typing::Context::new(&globals, messages_tx);
+            context.synth_type(&decoded_term);
+            assert!(messages_rx.try_iter().next().is_none());
+        }
+    }
+
+    #[test]
+    fn corrupted_bytes() {
+        let globals = Globals::default();
+        let mut bytes = core_to_binary::from_term(&elaborate(&globals, SOURCE), false).unwrap();
+
+        bytes[10] ^= 0xff;
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(Error::InvalidChecksum { .. }),
+        ));
+        assert_eq!(from_bytes(&bytes[..3]).err(), Some(Error::InvalidMagic));
+        assert_eq!(
+            from_bytes(b"PIKC\xff\xff").err(),
+            Some(Error::UnexpectedEndOfInput),
+        );
+    }
+
+    /// Encode a term that is made from nested functions, wrapping the type of
+    /// types.
+    fn nested_function_terms(depth: usize) -> Vec<u8> {
+        let mut contents = Vec::new();
+        contents.extend_from_slice(&MAGIC);
+        contents.extend_from_slice(&VERSION.to_le_bytes());
+        contents.push(0);
+        for _ in 0..depth {
+            contents.extend_from_slice(&[tags::TERM_FUNCTION_TERM, tags::PLICITY_EXPLICIT, 0]);
+        }
+        contents.push(tags::TERM_TYPE_TYPE);
+        contents.extend_from_slice(&checksum(&contents).to_le_bytes());
+        contents
+    }
+
+    #[test]
+    fn deeply_nested_terms() {
+        assert!(from_bytes(&nested_function_terms(MAX_DEPTH - 1)).is_ok());
+        assert_eq!(
+            from_bytes(&nested_function_terms(MAX_DEPTH)).err(),
+            Some(Error::NestingTooDeep),
+        );
+        assert_eq!(
+            from_bytes(&nested_function_terms(1_000_000)).err(),
+            Some(Error::NestingTooDeep),
+        );
+    }
+
+    /// A term that is made from nested annotations of the type of types.
+    fn nested_ann_terms(depth: usize) -> Term {
+        (1..depth).fold(Term::generated(TermData::TypeType), |term, _| {
+            Term::generated(TermData::Ann(
+                Arc::new(term),
+                Arc::new(Term::generated(TermData::TypeType)),
+            ))
+        })
+    }
+
+    #[test]
+    fn round_trip_at_max_depth() {
+        let bytes = core_to_binary::from_term(&nested_ann_terms(MAX_DEPTH), false).unwrap();
+        let decoded_term = from_bytes(&bytes).unwrap();
+        assert_eq!(core_to_binary::from_term(&decoded_term, false), Ok(bytes));
+        assert_eq!(
+            core_to_binary::from_term(&nested_ann_terms(MAX_DEPTH + 1), false),
+            Err(core_to_binary::Error::NestingTooDeep),
+        );
+    }
+
+    #[test]
+    fn unsolved_metavariables() {
+        let meta = Arc::new(MetaVar::new(0));
+        let core_term = Term::generated(TermData::ListTerm(vec![Arc::new(Term::generated(
+            TermData::Meta(meta),
+        ))]));
+
+        assert_eq!(
+            core_to_binary::from_term(&core_term, false),
+            Err(core_to_binary::Error::UnsolvedMetavariable),
+        );
+    }
+}
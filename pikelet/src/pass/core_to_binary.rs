@@ -0,0 +1,435 @@
+//! Serializes the [core language] to a compact binary format.
+//!
+//! This is useful for caching elaborated terms, or for sending them between
+//! processes without needing to parse and elaborate them again. Terms can be
+//! read back in using the [`binary_to_core`] pass.
+//!
+//! # Format
+//!
+//! All multi-byte integers are stored using unsigned [LEB128] variable-length
+//! encodings (signed integers are first [zig-zag encoded]), with the exception
+//! of 128-bit integers, which are stored as little-endian bytes, and floating
+//! point numbers, which are stored as little-endian IEEE-754 bits.
+//!
+//! | Field      | Encoding                                         |
+//! | ---------- | ------------------------------------------------ |
+//! | magic      | the bytes `b"PIKC"`                              |
+//! | version    | 2 bytes, little-endian ([`VERSION`])             |
+//! | flags      | 1 byte (see [`FLAG_LOCATIONS`])                  |
+//! | term       | the encoded term                                 |
+//! | checksum   | 4 bytes, little-endian [CRC-32] of the above     |
+//!
+//! Terms are encoded as an optional [`Location`] (only present if the
+//! [`FLAG_LOCATIONS`] flag is set), followed by a one byte tag identifying the
+//! term, followed by the fields of the term. Strings and sequences are prefixed
+//! with their length. Arbitrary-precision integers are stored as length-prefixed
+//! little-endian bytes, using two's complement for signed integers.
+//!
+//! Metavariables should be replaced by their solutions before terms are
+//! encoded. Terms containing unsolved metavariables, or that are nested more
+//! deeply than the [`MAX_DEPTH`] accepted by the decoder, result in an
+//! [`Error`].
+//!
+//! [core language]: crate::lang::core
+//! [`binary_to_core`]: crate::pass::binary_to_core
+//! [`MAX_DEPTH`]: crate::pass::binary_to_core::MAX_DEPTH
+//! [LEB128]: https://en.wikipedia.org/wiki/LEB128
+//! [zig-zag encoded]: https://developers.google.com/protocol-buffers/docs/encoding#signed_integers
+//! [CRC-32]: https://en.wikipedia.org/wiki/Cyclic_redundancy_check
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::lang::core::{Constant, Term, TermData};
+use crate::lang::{Location, Plicity};
+use crate::pass::binary_to_core::MAX_DEPTH;
+
+/// The magic bytes at the start of every encoded term.
+pub const MAGIC: [u8; 4] = *b"PIKC";
+/// The current version of the binary format.
+pub const VERSION: u16 = 1;
+/// Set if the encoded terms include their source locations.
+pub const FLAG_LOCATIONS: u8 = 0b0000_0001;
+
+pub(crate) mod tags {
+    pub const LOCATION_GENERATED: u8 = 0;
+    pub const LOCATION_FILE_RANGE: u8 = 1;
+
+    pub const TERM_GLOBAL: u8 = 0;
+    pub const TERM_VAR: u8 = 1;
+    pub const TERM_ANN: u8 = 2;
+    pub const TERM_TYPE_TYPE: u8 = 3;
+    pub const TERM_FUNCTION_TYPE: u8 = 4;
+    pub const TERM_FUNCTION_TERM: u8 = 5;
+    pub const TERM_FUNCTION_ELIM: u8 = 6;
+    pub const TERM_RECORD_TYPE: u8 = 7;
+    pub const TERM_RECORD_TERM: u8 = 8;
+    pub const TERM_RECORD_ELIM: u8 = 9;
+    pub const TERM_ARRAY_TERM: u8 = 10;
+    pub const TERM_LIST_TERM: u8 = 11;
+    pub const TERM_CONSTANT: u8 = 12;
+    pub const TERM_ERROR: u8 = 13;
+    pub const TERM_VARIANT_TYPE: u8 = 14;
+    pub const TERM_VARIANT_TERM: u8 = 15;
+    pub const TERM_VARIANT_ELIM: u8 = 16;
+    pub const TERM_DATA_ELIM: u8 = 17;
+
+    pub const CONSTANT_U8: u8 = 0;
+    pub const CONSTANT_U16: u8 = 1;
+    pub const CONSTANT_U32: u8 = 2;
+    pub const CONSTANT_U64: u8 = 3;
+    pub const CONSTANT_S8: u8 = 4;
+    pub const CONSTANT_S16: u8 = 5;
+    pub const CONSTANT_S32: u8 = 6;
+    pub const CONSTANT_S64: u8 = 7;
+    pub const CONSTANT_F32: u8 = 8;
+    pub const CONSTANT_F64: u8 = 9;
+    pub const CONSTANT_CHAR: u8 = 10;
+    pub const CONSTANT_STRING: u8 = 11;
+    pub const CONSTANT_NAT: u8 = 12;
+    pub const CONSTANT_INT: u8 = 13;
+    pub const CONSTANT_U128: u8 = 14;
+    pub const CONSTANT_S128: u8 = 15;
+    pub const CONSTANT_BYTES: u8 = 16;
+
+    pub const OPTION_NONE: u8 = 0;
+    pub const OPTION_SOME: u8 = 1;
+
+    pub const PLICITY_EXPLICIT: u8 = 0;
+    pub const PLICITY_IMPLICIT: u8 = 1;
+}
+
+/// Compute the [CRC-32] checksum of some bytes.
+///
+/// [CRC-32]: https://en.wikipedia.org/wiki/Cyclic_redundancy_check
+pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+/// Errors produced when encoding a term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The term contained a metavariable that was not solved.
+    UnsolvedMetavariable,
+    /// Terms were nested more deeply than [`MAX_DEPTH`].
+    ///
+    /// [`MAX_DEPTH`]: crate::pass::binary_to_core::MAX_DEPTH
+    NestingTooDeep,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsolvedMetavariable => write!(f, "cannot encode an unsolved metavariable"),
+            Error::NestingTooDeep => {
+                write!(f, "terms nested more deeply than {} levels", MAX_DEPTH)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Encode a term, optionally including its source locations.
+///
+/// Source locations refer to file identifiers that are only meaningful in the
+/// current process, so they should usually be excluded when sending terms
+/// elsewhere.
+pub fn from_term(term: &Term, include_locations: bool) -> Result<Vec<u8>, Error> {
+    let mut encoder = Encoder {
+        bytes: Vec::new(),
+        include_locations,
+        depth: 0,
+    };
+
+    encoder.bytes.extend_from_slice(&MAGIC);
+    encoder.bytes.extend_from_slice(&VERSION.to_le_bytes());
+    encoder.bytes.push(match include_locations {
+        true => FLAG_LOCATIONS,
+        false => 0,
+    });
+    encoder.term(term)?;
+
+    let checksum = checksum(&encoder.bytes);
+    encoder.bytes.extend_from_slice(&checksum.to_le_bytes());
+    Ok(encoder.bytes)
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+    include_locations: bool,
+    depth: usize,
+}
+
+impl Encoder {
+    fn tag(&mut self, tag: u8) {
+        self.bytes.push(tag);
+    }
+
+    fn unsigned(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            match value {
+                0 => return self.bytes.push(byte),
+                _ => self.bytes.push(byte | 0x80),
+            }
+        }
+    }
+
+    fn signed(&mut self, value: i64) {
+        self.unsigned(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.byte_string(value.as_bytes());
+    }
+
+    fn byte_string(&mut self, value: &[u8]) {
+        self.unsigned(value.len() as u64);
+        self.bytes.extend_from_slice(value);
+    }
+
+    fn plicity(&mut self, plicity: Plicity) {
+        match plicity {
+            Plicity::Explicit => self.tag(tags::PLICITY_EXPLICIT),
+            Plicity::Implicit => self.tag(tags::PLICITY_IMPLICIT),
+        }
+    }
+
+    fn location(&mut self, location: &Location) {
+        match location {
+            Location::Generated => self.tag(tags::LOCATION_GENERATED),
+            Location::FileRange(file_id, range) => {
+                self.tag(tags::LOCATION_FILE_RANGE);
+                self.unsigned(*file_id as u64);
+                self.unsigned(range.start as u64);
+                self.unsigned(range.end as u64);
+            }
+        }
+    }
+
+    fn constant(&mut self, constant: &Constant) {
+        match constant {
+            Constant::U8(value) => {
+                self.tag(tags::CONSTANT_U8);
+                self.bytes.push(*value);
+            }
+            Constant::U16(value) => {
+                self.tag(tags::CONSTANT_U16);
+                self.unsigned(u64::from(*value));
+            }
+            Constant::U32(value) => {
+                self.tag(tags::CONSTANT_U32);
+                self.unsigned(u64::from(*value));
+            }
+            Constant::U64(value) => {
+                self.tag(tags::CONSTANT_U64);
+                self.unsigned(*value);
+            }
+            Constant::U128(value) => {
+                self.tag(tags::CONSTANT_U128);
+                self.bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Constant::S8(value) => {
+                self.tag(tags::CONSTANT_S8);
+                self.bytes.push(*value as u8);
+            }
+            Constant::S16(value) => {
+                self.tag(tags::CONSTANT_S16);
+                self.signed(i64::from(*value));
+            }
+            Constant::S32(value) => {
+                self.tag(tags::CONSTANT_S32);
+                self.signed(i64::from(*value));
+            }
+            Constant::S64(value) => {
+                self.tag(tags::CONSTANT_S64);
+                self.signed(*value);
+            }
+            Constant::S128(value) => {
+                self.tag(tags::CONSTANT_S128);
+                self.bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Constant::Nat(value) => {
+                self.tag(tags::CONSTANT_NAT);
+                self.byte_string(&value.to_bytes_le());
+            }
+            Constant::Int(value) => {
+                self.tag(tags::CONSTANT_INT);
+                self.byte_string(&value.to_signed_bytes_le());
+            }
+            Constant::F32(value) => {
+                self.tag(tags::CONSTANT_F32);
+                self.bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            Constant::F64(value) => {
+                self.tag(tags::CONSTANT_F64);
+                self.bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            Constant::Char(value) => {
+                self.tag(tags::CONSTANT_CHAR);
+                self.unsigned(u64::from(*value));
+            }
+            Constant::String(value) => {
+                self.tag(tags::CONSTANT_STRING);
+                self.string(value);
+            }
+            Constant::Bytes(value) => {
+                self.tag(tags::CONSTANT_BYTES);
+                self.byte_string(value);
+            }
+        }
+    }
+
+    fn terms<'a>(&mut self, terms: impl ExactSizeIterator<Item = &'a Term>) -> Result<(), Error> {
+        self.unsigned(terms.len() as u64);
+        for term in terms {
+            self.term(term)?;
+        }
+        Ok(())
+    }
+
+    fn optional_terms(&mut self, terms: &[Option<Arc<Term>>]) -> Result<(), Error> {
+        self.unsigned(terms.len() as u64);
+        for term in terms {
+            match term {
+                None => self.tag(tags::OPTION_NONE),
+                Some(term) => {
+                    self.tag(tags::OPTION_SOME);
+                    self.term(term)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn strings(&mut self, values: &[String]) {
+        self.unsigned(values.len() as u64);
+        for value in values {
+            self.string(value);
+        }
+    }
+
+    fn term(&mut self, term: &Term) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+
+        self.depth += 1;
+        let result = self.term_impl(term);
+        self.depth -= 1;
+        result
+    }
+
+    fn term_impl(&mut self, term: &Term) -> Result<(), Error> {
+        if self.include_locations {
+            self.location(&term.location);
+        }
+
+        match &term.data {
+            TermData::Global(name) => {
+                self.tag(tags::TERM_GLOBAL);
+                self.string(name);
+            }
+            TermData::Var(index) => {
+                self.tag(tags::TERM_VAR);
+                self.unsigned(index.to_usize() as u64);
+            }
+            TermData::Ann(term, r#type) => {
+                self.tag(tags::TERM_ANN);
+                self.term(term)?;
+                self.term(r#type)?;
+            }
+            TermData::TypeType => self.tag(tags::TERM_TYPE_TYPE),
+            TermData::FunctionType(plicity, input_name_hint, input_type, output_type) => {
+                self.tag(tags::TERM_FUNCTION_TYPE);
+                self.plicity(*plicity);
+                match input_name_hint {
+                    None => self.tag(tags::OPTION_NONE),
+                    Some(input_name_hint) => {
+                        self.tag(tags::OPTION_SOME);
+                        self.string(input_name_hint);
+                    }
+                }
+                self.term(input_type)?;
+                self.term(output_type)?;
+            }
+            TermData::FunctionTerm(plicity, input_name_hint, output_term) => {
+                self.tag(tags::TERM_FUNCTION_TERM);
+                self.plicity(*plicity);
+                self.string(input_name_hint);
+                self.term(output_term)?;
+            }
+            TermData::FunctionElim(head_term, plicity, input_term) => {
+                self.tag(tags::TERM_FUNCTION_ELIM);
+                self.term(head_term)?;
+                self.plicity(*plicity);
+                self.term(input_term)?;
+            }
+            TermData::RecordType(labels, docs, types, defaults) => {
+                self.tag(tags::TERM_RECORD_TYPE);
+                self.strings(labels);
+                self.strings(docs);
+                self.terms(types.iter().map(|r#type| r#type.as_ref()))?;
+                self.optional_terms(defaults)?;
+            }
+            TermData::RecordTerm(labels, terms) => {
+                self.tag(tags::TERM_RECORD_TERM);
+                self.strings(labels);
+                self.terms(terms.iter().map(|term| term.as_ref()))?;
+            }
+            TermData::RecordElim(head_term, label) => {
+                self.tag(tags::TERM_RECORD_ELIM);
+                self.term(head_term)?;
+                self.string(label);
+            }
+            TermData::VariantType(labels, types) => {
+                self.tag(tags::TERM_VARIANT_TYPE);
+                self.strings(labels);
+                self.terms(types.iter().map(|r#type| r#type.as_ref()))?;
+            }
+            TermData::VariantTerm(label, term) => {
+                self.tag(tags::TERM_VARIANT_TERM);
+                self.string(label);
+                self.term(term)?;
+            }
+            TermData::VariantElim(head_term, labels, branches) => {
+                self.tag(tags::TERM_VARIANT_ELIM);
+                self.term(head_term)?;
+                self.strings(labels);
+                self.terms(branches.iter().map(|branch| branch.as_ref()))?;
+            }
+            TermData::DataElim(head_term, name, motive, methods) => {
+                self.tag(tags::TERM_DATA_ELIM);
+                self.term(head_term)?;
+                self.string(name);
+                self.term(motive)?;
+                self.terms(methods.iter().map(|method| method.as_ref()))?;
+            }
+            TermData::ArrayTerm(entry_terms) => {
+                self.tag(tags::TERM_ARRAY_TERM);
+                self.terms(entry_terms.iter().map(|term| term.as_ref()))?;
+            }
+            TermData::ListTerm(entry_terms) => {
+                self.tag(tags::TERM_LIST_TERM);
+                self.terms(entry_terms.iter().map(|term| term.as_ref()))?;
+            }
+            TermData::Constant(constant) => {
+                self.tag(tags::TERM_CONSTANT);
+                self.constant(constant);
+            }
+            TermData::Meta(_) => return Err(Error::UnsolvedMetavariable),
+            TermData::Error => self.tag(tags::TERM_ERROR),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+//! Generates documentation pages from the [core language].
+//!
+//! Pages list the entries of the record types found in a term, along with
+//! their types and the documentation supplied in `|||` doc comments. The
+//! documentation of the module is shown beneath the title of the page. Record
+//! types are collected from the type annotation of the term, and from any
+//! record types that are defined as entries in record terms. For example the
+//! `Semigroup` and `Category` types in `examples/prelude.pi`.
+//!
+//! [core language]: crate::lang::core
+
+use std::fmt::Write;
+
+use crate::lang::core::{Globals, Term, TermData};
+use crate::pass::{core_to_surface, surface_to_pretty};
+
+/// The output format of the documentation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+/// A record type to be documented.
+struct Section {
+    /// The path of labels leading to the record type.
+    path: Vec<String>,
+    entries: Vec<Entry>,
+}
+
+/// An entry in a record type.
+struct Entry {
+    label: String,
+    r#type: String,
+    default: Option<String>,
+    docs: String,
+}
+
+/// Generate a documentation page for a term, with the documentation of the
+/// module that it was defined in.
+pub fn from_term(
+    globals: &Globals,
+    title: &str,
+    docs: &str,
+    term: &Term,
+    format: Format,
+) -> String {
+    let mut context = Context {
+        core_to_surface: core_to_surface::Context::new(globals),
+        path: Vec::new(),
+        sections: Vec::new(),
+    };
+    context.collect_term(term);
+
+    match format {
+        Format::Markdown => to_markdown(title, &strip_docs(docs), &context.sections),
+        Format::Html => to_html(title, &strip_docs(docs), &context.sections),
+    }
+}
+
+struct Context<'globals> {
+    core_to_surface: core_to_surface::Context<'globals>,
+    path: Vec<String>,
+    sections: Vec<Section>,
+}
+
+impl<'globals> Context<'globals> {
+    fn collect_term(&mut self, term: &Term) {
+        match &term.data {
+            TermData::Ann(term, r#type) => {
+                self.collect_record_type(r#type);
+                self.collect_term(term);
+            }
+            TermData::RecordType(_, _, _, _) => self.collect_record_type(term),
+            TermData::RecordTerm(labels, terms) => {
+                for (label, entry_term) in Iterator::zip(labels.iter(), terms.iter()) {
+                    self.path.push(label.clone());
+                    self.collect_term(entry_term);
+                    self.path.pop();
+                    self.core_to_surface.push_scope(Some(label));
+                }
+                self.core_to_surface.pop_scopes(labels.len());
+            }
+            TermData::FunctionTerm(_, input_name_hint, output_term) => {
+                self.core_to_surface.push_scope(Some(input_name_hint));
+                self.collect_term(output_term);
+                self.core_to_surface.pop_scope();
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_record_type(&mut self, r#type: &Term) {
+        let pretty_alloc = pretty::BoxAllocator;
+
+        if let TermData::RecordType(labels, docs, types, defaults) = &r#type.data {
+            if labels.is_empty() {
+                return;
+            }
+
+            let mut entries = Vec::with_capacity(labels.len());
+            for (label, (docs, (entry_type, default_term))) in Iterator::zip(
+                labels.iter(),
+                Iterator::zip(docs.iter(), Iterator::zip(types.iter(), defaults.iter())),
+            ) {
+                let entry_type = self.core_to_surface.from_term(entry_type);
+                let entry_type = surface_to_pretty::from_term(&pretty_alloc, &entry_type);
+                let default_term = default_term.as_ref().map(|default_term| {
+                    let default_term = self.core_to_surface.from_term(default_term);
+                    let default_term = surface_to_pretty::from_term(&pretty_alloc, &default_term);
+                    default_term.1.pretty(80).to_string()
+                });
+                self.core_to_surface.push_scope(Some(label));
+
+                entries.push(Entry {
+                    label: label.clone(),
+                    r#type: entry_type.1.pretty(80).to_string(),
+                    default: default_term,
+                    docs: strip_docs(docs),
+                });
+            }
+            self.core_to_surface.pop_scopes(labels.len());
+
+            self.sections.push(Section {
+                path: self.path.clone(),
+                entries,
+            });
+        }
+    }
+}
+
+/// Remove the leading space from each line of the documentation.
+fn strip_docs(docs: &str) -> String {
+    let lines = docs.split('\n');
+    let lines = lines.map(|line| line.strip_prefix(' ').unwrap_or(line));
+    lines.collect::<Vec<_>>().join("\n").trim().to_owned()
+}
+
+fn section_name<'a>(title: &'a str, section: &'a Section) -> std::borrow::Cow<'a, str> {
+    match section.path.is_empty() {
+        true => title.into(),
+        false => section.path.join(".").into(),
+    }
+}
+
+/// Write documentation in Markdown, nesting its headings beneath a heading of
+/// the given level.
+fn write_markdown_docs(output: &mut String, heading_level: usize, docs: &str) {
+    if !docs.is_empty() {
+        writeln!(output).unwrap();
+        for line in docs.lines() {
+            match line.starts_with('#') {
+                true => writeln!(output, "{}{}", "#".repeat(heading_level), line).unwrap(),
+                false => writeln!(output, "{}", line).unwrap(),
+            }
+        }
+    }
+}
+
+/// Write documentation in HTML, as a sequence of paragraphs.
+fn write_html_docs(output: &mut String, docs: &str) {
+    for paragraph in docs.split("\n\n").filter(|p| !p.is_empty()) {
+        writeln!(output, "<p>{}</p>", escape_html(paragraph)).unwrap();
+    }
+}
+
+fn to_markdown(title: &str, docs: &str, sections: &[Section]) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "# {}", title).unwrap();
+    write_markdown_docs(&mut output, 1, docs);
+    for section in sections {
+        writeln!(output).unwrap();
+        writeln!(output, "## {}", section_name(title, section)).unwrap();
+
+        for entry in &section.entries {
+            writeln!(output).unwrap();
+            writeln!(output, "### `{}`", entry.label).unwrap();
+            writeln!(output).unwrap();
+            writeln!(output, "```pikelet").unwrap();
+            match &entry.default {
+                None => writeln!(output, "{} : {}", entry.label, entry.r#type).unwrap(),
+                Some(default) => {
+                    writeln!(output, "{} : {} = {}", entry.label, entry.r#type, default).unwrap()
+                }
+            }
+            writeln!(output, "```").unwrap();
+            write_markdown_docs(&mut output, 3, &entry.docs);
+        }
+    }
+
+    output
+}
+
+fn to_html(title: &str, docs: &str, sections: &[Section]) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "<!DOCTYPE html>").unwrap();
+    writeln!(output, "<html>").unwrap();
+    writeln!(output, "<head>").unwrap();
+    writeln!(output, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(output, "<title>{}</title>", escape_html(title)).unwrap();
+    writeln!(output, "</head>").unwrap();
+    writeln!(output, "<body>").unwrap();
+    writeln!(output, "<h1>{}</h1>", escape_html(title)).unwrap();
+    write_html_docs(&mut output, docs);
+
+    for section in sections {
+        let name = section_name(title, section);
+        writeln!(output, "<section id=\"{}\">", escape_html(&name)).unwrap();
+        writeln!(output, "<h2>{}</h2>", escape_html(&name)).unwrap();
+
+        for entry in &section.entries {
+            let id = match section.path.is_empty() {
+                true => entry.label.clone(),
+                false => format!("{}.{}", name, entry.label),
+            };
+            writeln!(output, "<h3 id=\"{}\">", escape_html(&id)).unwrap();
+            writeln!(output, "<code>{}</code>", escape_html(&entry.label)).unwrap();
+            writeln!(output, "</h3>").unwrap();
+            let default = match &entry.default {
+                None => String::new(),
+                Some(default) => format!(" = {}", escape_html(default)),
+            };
+            writeln!(
+                output,
+                "<pre><code>{} : {}{}</code></pre>",
+                escape_html(&entry.label),
+                escape_html(&entry.r#type),
+                default,
+            )
+            .unwrap();
+            write_html_docs(&mut output, &entry.docs);
+        }
+
+        writeln!(output, "</section>").unwrap();
+    }
+
+    writeln!(output, "</body>").unwrap();
+    writeln!(output, "</html>").unwrap();
+
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            ch => output.push(ch),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::surface;
+    use crate::pass::surface_to_core;
+
+    #[test]
+    fn markdown_entries() {
+        let globals = Globals::default();
+        let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
+        let source = r#"
+            record {
+                Point = Record {
+                    ||| The horizontal position.
+                    x : F32,
+                    y : F32,
+                },
+                always = fun a b => a,
+            } : Record {
+                ||| A point in space.
+                Point : Type,
+                ||| Ignore the second input.
+                always : Fun {A B : Type} -> A -> B -> A,
+            }
+        "#;
+        let surface_term = surface::Term::from_str(0, source, &messages_tx);
+        let mut context = surface_to_core::Context::new(&globals, messages_tx);
+        let (core_term, _) = context.synth_type(&surface_term);
+        assert!(messages_rx.try_iter().next().is_none());
+
+        let docs = " Example definitions.\n\n # Usage\n\n Use them.";
+        let output = from_term(&globals, "example", docs, &core_term, Format::Markdown);
+        let expected = "\
+# example
+
+Example definitions.
+
+## Usage
+
+Use them.
+
+## example
+
+### `Point`
+
+```pikelet
+Point : Type
+```
+
+A point in space.
+
+### `always`
+
+```pikelet
+always : Fun {A : Type} -> Fun {B : Type} -> A -> B -> A
+```
+
+Ignore the second input.
+
+## Point
+
+### `x`
+
+```pikelet
+x : F32
+```
+
+The horizontal position.
+
+### `y`
+
+```pikelet
+y : F32
+```
+";
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,305 @@
+//! Exports terms in the [core language] as [JSON] data.
+//!
+//! Terms are evaluated before they are exported, and must reduce to one of
+//! the following values:
+//!
+//! | Value                   | JSON                                      |
+//! | ----------------------- | ----------------------------------------- |
+//! | `true`, `false`         | booleans                                  |
+//! | numeric constants       | numbers                                   |
+//! | `Char`, `String`        | strings                                   |
+//! | `Bytes`                 | [base64] encoded strings                  |
+//! | record terms            | objects, with the entries in order        |
+//! | array and list terms    | arrays                                    |
+//! | variant terms           | objects, mapping the label to the payload |
+//!
+//! Other values, like types and functions, have no representation in JSON,
+//! and will result in an [`Error`].
+//!
+//! [core language]: crate::lang::core
+//! [JSON]: https://www.json.org/
+//! [base64]: https://datatracker.ietf.org/doc/html/rfc4648#section-4
+
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use crate::lang::core::semantics::{self, Head, Value};
+use crate::lang::core::{Constant, Env, Globals, Term};
+
+/// Errors produced when exporting a term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The term evaluated to a value that can not be represented in JSON.
+    UnsupportedValue { kind: &'static str },
+    /// A floating point number was infinite or not a number.
+    NonFiniteNumber,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedValue { kind } => write!(f, "cannot export {} as JSON", kind),
+            Error::NonFiniteNumber => write!(f, "cannot export a non-finite number as JSON"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Evaluate a term and export the resulting value as a JSON document.
+pub fn from_term(globals: &Globals, term: &Term) -> Result<String, Error> {
+    let value = semantics::eval(globals, &mut Env::new(), term);
+    let mut output = String::new();
+    write_value(globals, &mut output, 0, &value)?;
+    output.push('\n');
+    Ok(output)
+}
+
+fn write_value(
+    globals: &Globals,
+    output: &mut String,
+    depth: usize,
+    value: &Value,
+) -> Result<(), Error> {
+    match value.force(globals) {
+        Value::Stuck(Head::Global(name), spine) if spine.is_empty() => match name.as_str() {
+            "true" | "false" => output.push_str(name),
+            _ => return Err(Error::UnsupportedValue { kind: "a global" }),
+        },
+        Value::Stuck(_, _) | Value::Unstuck(_, _, _) => {
+            return Err(Error::UnsupportedValue {
+                kind: "an unevaluated term",
+            });
+        }
+
+        Value::TypeType
+        | Value::FunctionType(_, _, _, _)
+        | Value::RecordType(_, _)
+        | Value::VariantType(_, _) => return Err(Error::UnsupportedValue { kind: "a type" }),
+        Value::FunctionTerm(_, _, _) => {
+            return Err(Error::UnsupportedValue { kind: "a function" });
+        }
+
+        Value::RecordTerm(labels, closure) => {
+            let mut entries = Vec::with_capacity(labels.len());
+            closure.for_each_entry(globals, |entry| {
+                entries.push(entry.clone());
+                entry
+            });
+
+            write_object(
+                globals,
+                output,
+                depth,
+                Iterator::zip(labels.iter().map(String::as_str), entries.iter()),
+            )?;
+        }
+        Value::VariantTerm(label, payload) => {
+            write_object(
+                globals,
+                output,
+                depth,
+                std::iter::once((label.as_str(), payload)),
+            )?;
+        }
+        Value::ArrayTerm(entries) | Value::ListTerm(entries) => {
+            if entries.is_empty() {
+                output.push_str("[]");
+                return Ok(());
+            }
+
+            output.push('[');
+            for (index, entry) in entries.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_newline(output, depth + 1);
+                write_value(globals, output, depth + 1, entry)?;
+            }
+            write_newline(output, depth);
+            output.push(']');
+        }
+
+        Value::Constant(constant) => write_constant(output, constant)?,
+
+        Value::Error => return Err(Error::UnsupportedValue { kind: "an error" }),
+    }
+
+    Ok(())
+}
+
+fn write_object<'a>(
+    globals: &Globals,
+    output: &mut String,
+    depth: usize,
+    entries: impl ExactSizeIterator<Item = (&'a str, &'a Arc<Value>)>,
+) -> Result<(), Error> {
+    if entries.len() == 0 {
+        output.push_str("{}");
+        return Ok(());
+    }
+
+    output.push('{');
+    for (index, (label, entry)) in entries.enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+        write_newline(output, depth + 1);
+        write_string(output, label);
+        output.push_str(": ");
+        write_value(globals, output, depth + 1, entry)?;
+    }
+    write_newline(output, depth);
+    output.push('}');
+
+    Ok(())
+}
+
+fn write_constant(output: &mut String, constant: &Constant) -> Result<(), Error> {
+    match constant {
+        Constant::U8(value) => write!(output, "{}", value).unwrap(),
+        Constant::U16(value) => write!(output, "{}", value).unwrap(),
+        Constant::U32(value) => write!(output, "{}", value).unwrap(),
+        Constant::U64(value) => write!(output, "{}", value).unwrap(),
+        Constant::U128(value) => write!(output, "{}", value).unwrap(),
+        Constant::S8(value) => write!(output, "{}", value).unwrap(),
+        Constant::S16(value) => write!(output, "{}", value).unwrap(),
+        Constant::S32(value) => write!(output, "{}", value).unwrap(),
+        Constant::S64(value) => write!(output, "{}", value).unwrap(),
+        Constant::S128(value) => write!(output, "{}", value).unwrap(),
+        Constant::Nat(value) => write!(output, "{}", value).unwrap(),
+        Constant::Int(value) => write!(output, "{}", value).unwrap(),
+        Constant::F32(value) if value.is_finite() => write!(output, "{}", value).unwrap(),
+        Constant::F64(value) if value.is_finite() => write!(output, "{}", value).unwrap(),
+        Constant::F32(_) | Constant::F64(_) => return Err(Error::NonFiniteNumber),
+        Constant::Char(value) => write_string(output, value.encode_utf8(&mut [0; 4])),
+        Constant::String(value) => write_string(output, value),
+        Constant::Bytes(value) => write_base64(output, value),
+    }
+
+    Ok(())
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            ch if ch.is_control() => write!(output, "\\u{:04x}", ch as u32).unwrap(),
+            ch => output.push(ch),
+        }
+    }
+    output.push('"');
+}
+
+fn write_base64(output: &mut String, value: &[u8]) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    output.push('"');
+    for chunk in value.chunks(3) {
+        let bits = (chunk.iter().enumerate()).fold(0u32, |bits, (index, byte)| {
+            bits | u32::from(*byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => output.push(char::from(
+                    ALPHABET[(bits >> (18 - 6 * index) & 0x3f) as usize],
+                )),
+                false => output.push('='),
+            }
+        }
+    }
+    output.push('"');
+}
+
+fn write_newline(output: &mut String, depth: usize) {
+    output.push('\n');
+    (0..depth).for_each(|_| output.push_str("  "));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::surface;
+    use crate::pass::surface_to_core;
+
+    fn export(source: &str) -> Result<String, Error> {
+        let globals = Globals::default();
+        let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
+        let surface_term = surface::Term::from_str(0, source, &messages_tx);
+        let mut context = surface_to_core::Context::new(&globals, messages_tx);
+        let (core_term, _) = context.synth_type(&surface_term);
+        assert!(messages_rx.try_iter().next().is_none());
+
+        from_term(&globals, &core_term)
+    }
+
+    #[test]
+    fn records_and_variants() {
+        let source = r#"
+            record {
+                name = "assets\n",
+                enabled = true,
+                ports = [80, 443] : List U16,
+                backends = [
+                    variant s3 (record { bucket = "assets" }),
+                    variant memory,
+                ] : Array 2 (Variant { s3 : Record { bucket : String }, memory }),
+            }
+        "#;
+        let expected = r#"{
+  "name": "assets\n",
+  "enabled": true,
+  "ports": [
+    80,
+    443
+  ],
+  "backends": [
+    {
+      "s3": {
+        "bucket": "assets"
+      }
+    },
+    {
+      "memory": {}
+    }
+  ]
+}
+"#;
+        assert_eq!(export(source).unwrap(), expected);
+    }
+
+    #[test]
+    fn bytes_as_base64() {
+        let source = r#"[b"", b"f", b"fo", b"foo", b"foobar", x"fb ff"]"#;
+        let expected = r#"[
+  "",
+  "Zg==",
+  "Zm8=",
+  "Zm9v",
+  "Zm9vYmFy",
+  "+/8="
+]
+"#;
+        assert_eq!(
+            export(&format!("{} : List Bytes", source)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn unsupported_values() {
+        assert_eq!(
+            export(r#"fun (x : String) => x"#),
+            Err(Error::UnsupportedValue { kind: "a function" }),
+        );
+        assert_eq!(
+            export(r#"Record { x : String }"#),
+            Err(Error::UnsupportedValue { kind: "a type" }),
+        );
+    }
+}
@@ -5,6 +5,8 @@
 use pretty::{DocAllocator, DocBuilder};
 
 use crate::lang::core::{Constant, Term, TermData};
+use crate::lang::Plicity;
+use crate::literal;
 
 /// The precedence of a term.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -56,41 +58,54 @@ where
 
         TermData::TypeType => alloc.text("Type"),
 
-        TermData::FunctionType(_, input_type, output_type) => paren(
+        TermData::FunctionType(plicity, _, input_type, output_type) => paren(
             alloc,
             prec > Prec::Arrow,
             (alloc.nil())
-                .append(from_term_prec(alloc, input_type, Prec::App))
+                .append(match plicity {
+                    Plicity::Explicit => from_term_prec(alloc, input_type, Prec::App),
+                    Plicity::Implicit => {
+                        braces(alloc, from_term_prec(alloc, input_type, Prec::Term))
+                    }
+                })
                 .append(alloc.space())
                 .append("->")
                 .append(alloc.space())
                 .append(from_term_prec(alloc, output_type, Prec::Arrow)),
         ),
-        TermData::FunctionTerm(_, output_term) => paren(
+        TermData::FunctionTerm(plicity, _, output_term) => paren(
             alloc,
             prec > Prec::Expr,
             (alloc.nil())
                 .append("fun")
                 .append(alloc.space())
-                .append("_")
+                .append(match plicity {
+                    Plicity::Explicit => alloc.text("_"),
+                    Plicity::Implicit => braces(alloc, alloc.text("_")),
+                })
                 .append(alloc.space())
                 .append("=>")
                 .group()
                 .append(alloc.space())
                 .append(from_term_prec(alloc, output_term, Prec::Expr).nest(4)),
         ),
-        TermData::FunctionElim(head_term, input_term) => paren(
+        TermData::FunctionElim(head_term, plicity, input_term) => paren(
             alloc,
             prec > Prec::App,
             from_term_prec(alloc, head_term, Prec::App).append(
                 (alloc.space())
-                    .append(from_term_prec(alloc, input_term, Prec::Arrow))
+                    .append(match plicity {
+                        Plicity::Explicit => from_term_prec(alloc, input_term, Prec::Atomic),
+                        Plicity::Implicit => {
+                            braces(alloc, from_term_prec(alloc, input_term, Prec::Term))
+                        }
+                    })
                     .group()
                     .nest(4),
             ),
         ),
 
-        TermData::RecordType(labels, types) => (alloc.nil())
+        TermData::RecordType(labels, _, types, defaults) => (alloc.nil())
             .append("Record")
             .append(alloc.space())
             .append("{")
@@ -107,7 +122,21 @@ where
                 (types.iter()).map(|ty| from_term_prec(alloc, ty, Prec::Term).group().nest(4)),
                 alloc.text(",").append(alloc.space()),
             )))
-            .append("}"),
+            .append("}")
+            .append(match defaults.iter().any(Option::is_some) {
+                true => (alloc.space())
+                    .append("{")
+                    .group()
+                    .append(alloc.space().append(alloc.intersperse(
+                        (defaults.iter()).map(|default| match default {
+                            None => alloc.text("_"),
+                            Some(term) => from_term_prec(alloc, term, Prec::Term).group().nest(4),
+                        }),
+                        alloc.text(",").append(alloc.space()),
+                    )))
+                    .append("}"),
+                false => alloc.nil(),
+            }),
         TermData::RecordTerm(labels, terms) => (alloc.nil())
             .append("record")
             .append(alloc.space())
@@ -131,6 +160,94 @@ where
             .append(".")
             .append(alloc.text(label)),
 
+        TermData::VariantType(labels, types) => (alloc.nil())
+            .append("Variant")
+            .append(alloc.space())
+            .append("{")
+            .group()
+            .append(alloc.space().append(alloc.intersperse(
+                labels.iter().map(|label| alloc.text(label).group().nest(4)),
+                alloc.text(",").append(alloc.space()),
+            )))
+            .append("}")
+            .append(alloc.space())
+            .append("{")
+            .group()
+            .append(alloc.space().append(alloc.intersperse(
+                (types.iter()).map(|ty| from_term_prec(alloc, ty, Prec::Term).group().nest(4)),
+                alloc.text(",").append(alloc.space()),
+            )))
+            .append("}"),
+        TermData::VariantTerm(label, term) => paren(
+            alloc,
+            prec > Prec::App,
+            (alloc.nil())
+                .append("variant")
+                .append(alloc.space())
+                .append(alloc.text(label))
+                .append(
+                    (alloc.space())
+                        .append(from_term_prec(alloc, term, Prec::Atomic))
+                        .group()
+                        .nest(4),
+                ),
+        ),
+        TermData::VariantElim(head_term, labels, branches) => (alloc.nil())
+            .append("match")
+            .append(alloc.space())
+            .append(from_term_prec(alloc, head_term, Prec::Atomic))
+            .append(alloc.space())
+            .append("{")
+            .group()
+            .append(alloc.space().append(alloc.intersperse(
+                labels.iter().map(|label| alloc.text(label).group().nest(4)),
+                alloc.text(",").append(alloc.space()),
+            )))
+            .append("}")
+            .append(alloc.space())
+            .append("{")
+            .group()
+            .append(alloc.space().append(alloc.intersperse(
+                (branches.iter()).map(|branch| {
+                    (alloc.nil())
+                        .append("_")
+                        .append(alloc.space())
+                        .append("=>")
+                        .append(alloc.space())
+                        .append(from_term_prec(alloc, branch, Prec::Term))
+                        .group()
+                        .nest(4)
+                }),
+                alloc.text(",").append(alloc.space()),
+            )))
+            .append("}"),
+
+        TermData::DataElim(head_term, name, motive, methods) => paren(
+            alloc,
+            prec > Prec::App,
+            (alloc.nil())
+                .append("elim")
+                .append(alloc.space())
+                .append(alloc.text(name))
+                .append(
+                    (alloc.space())
+                        .append(from_term_prec(alloc, head_term, Prec::Atomic))
+                        .append(alloc.space())
+                        .append(from_term_prec(alloc, motive, Prec::Atomic))
+                        .append(alloc.space())
+                        .append("{")
+                        .append(alloc.space().append(alloc.intersperse(
+                            (methods.iter()).map(|method| {
+                                from_term_prec(alloc, method, Prec::Term).group().nest(4)
+                            }),
+                            alloc.text(",").append(alloc.space()),
+                        )))
+                        .append("}")
+                        .group()
+                        .nest(4),
+                ),
+        ),
+
         TermData::ArrayTerm(term_entries) | TermData::ListTerm(term_entries) => (alloc.nil())
             .append("[")
             .group()
@@ -146,6 +263,8 @@ where
 
         TermData::Constant(constant) => from_constant(alloc, constant),
 
+        TermData::Meta(meta) => alloc.text(format!("?{}", meta.id())),
+
         TermData::Error => alloc.text("!"),
     }
 }
@@ -160,17 +279,39 @@ where
         Constant::U16(value) => alloc.text(format!("{}", value)),
         Constant::U32(value) => alloc.text(format!("{}", value)),
         Constant::U64(value) => alloc.text(format!("{}", value)),
+        Constant::U128(value) => alloc.text(format!("{}", value)),
         Constant::S8(value) => alloc.text(format!("{}", value)),
         Constant::S16(value) => alloc.text(format!("{}", value)),
         Constant::S32(value) => alloc.text(format!("{}", value)),
         Constant::S64(value) => alloc.text(format!("{}", value)),
-        Constant::F32(value) => alloc.text(format!("{}", value)),
-        Constant::F64(value) => alloc.text(format!("{}", value)),
+        Constant::S128(value) => alloc.text(format!("{}", value)),
+        Constant::Nat(value) => alloc.text(format!("{}", value)),
+        Constant::Int(value) => alloc.text(format!("{}", value)),
+        // Debug formatting uses the shortest representation that round-trips
+        Constant::F32(value) => alloc.text(format!("{:?}", value)),
+        Constant::F64(value) => alloc.text(format!("{:?}", value)),
         Constant::Char(value) => alloc.text(format!("{:?}", value)),
-        Constant::String(value) => alloc.text(format!("{:?}", value)),
+        Constant::String(value) => {
+            let literal = literal::utf8_string_to_quoted(value);
+            // Multi-line literals are split on hard line breaks, so that they
+            // remain valid when indented.
+            alloc.intersperse(
+                literal.split('\n').map(|line| alloc.text(line.to_owned())),
+                alloc.hardline(),
+            )
+        }
+        Constant::Bytes(value) => alloc.text(literal::bytes_to_quoted(value)),
     }
 }
 
+fn braces<'a, D>(alloc: &'a D, doc: DocBuilder<'a, D>) -> DocBuilder<'a, D>
+where
+    D: DocAllocator<'a>,
+    D::Doc: Clone,
+{
+    alloc.text("{").append(doc).append("}")
+}
+
 fn paren<'a, D>(alloc: &'a D, b: bool, doc: DocBuilder<'a, D>) -> DocBuilder<'a, D>
 where
     D: DocAllocator<'a>,
@@ -10,9 +10,13 @@
 use contracts::debug_ensures;
 use fxhash::FxHashMap;
 
-use crate::lang::core::{Constant, EnvSize, Globals, Term, TermData, VarIndex};
+use std::sync::Arc;
+
+use crate::lang::core::semantics::{self, Value};
+use crate::lang::core::{data, Constant, Env, EnvSize, Globals, Term, TermData, VarIndex};
 use crate::lang::surface;
-use crate::lang::Located;
+use crate::lang::{Located, Plicity};
+use crate::literal;
 
 /// Distillation context.
 pub struct Context<'globals> {
@@ -145,36 +149,73 @@ impl<'globals> Context<'globals> {
                 None => surface::TermData::Error, // TODO: Log error?
             },
 
-            TermData::Ann(term, r#type) => surface::TermData::Ann(
-                Box::new(self.from_term(term)),
-                Box::new(self.from_term(r#type)),
-            ),
+            TermData::Ann(term, r#type) => match (&term.data, &r#type.data) {
+                // Omit the entries of annotated record terms that are the
+                // same as the defaults in the record type.
+                (
+                    TermData::RecordTerm(labels, terms),
+                    TermData::RecordType(type_labels, _, _, defaults),
+                ) if labels == type_labels => surface::TermData::Ann(
+                    Box::new(surface::Term::generated(
+                        self.distill_record_term(labels, terms, defaults),
+                    )),
+                    Box::new(self.from_term(r#type)),
+                ),
+                (_, _) => surface::TermData::Ann(
+                    Box::new(self.from_term(term)),
+                    Box::new(self.from_term(r#type)),
+                ),
+            },
 
             TermData::TypeType => surface::TermData::Name("Type".to_owned()),
 
-            TermData::FunctionType(input_name_hint, input_type, output_type) => {
+            TermData::FunctionType(Plicity::Explicit, input_name_hint, input_type, output_type)
+                if !output_type.has_var(0) =>
+            {
+                let input_type = self.from_term(input_type);
+                self.push_scope(input_name_hint.as_ref().map(String::as_str));
+                let output_type = self.from_term(output_type);
+                self.pop_scopes(1);
+
+                surface::TermData::FunctionArrowType(Box::new(input_type), Box::new(output_type))
+            }
+            TermData::FunctionType(plicity, input_name_hint, input_type, output_type) => {
                 // FIXME: properly group inputs!
                 let input_type = self.from_term(input_type);
                 let fresh_input_name =
                     self.push_scope(input_name_hint.as_ref().map(String::as_str));
-                let input_type_groups =
-                    vec![(vec![Located::generated(fresh_input_name)], input_type)];
+                let input_type_groups = vec![(
+                    Vec::new(),
+                    *plicity,
+                    vec![Located::generated(fresh_input_name)],
+                    input_type,
+                )];
                 let output_type = self.from_term(output_type);
-                self.pop_scopes(input_type_groups.iter().map(|(ns, _)| ns.len()).sum());
+                self.pop_scopes(input_type_groups.iter().map(|(_, _, ns, _)| ns.len()).sum());
 
                 surface::TermData::FunctionType(input_type_groups, Box::new(output_type))
             }
-            TermData::FunctionTerm(input_name_hint, output_term) => {
+            TermData::FunctionTerm(plicity, input_name_hint, output_term) => {
                 let mut current_output_term = output_term;
 
                 let fresh_input_name = self.push_scope(Some(input_name_hint));
-                let mut input_names = vec![Located::generated(fresh_input_name)];
-
-                while let TermData::FunctionTerm(input_name_hint, output_term) =
+                let mut input_names = vec![(
+                    Vec::new(),
+                    *plicity,
+                    Located::generated(fresh_input_name),
+                    None,
+                )];
+
+                while let TermData::FunctionTerm(plicity, input_name_hint, output_term) =
                     &current_output_term.data
                 {
                     let fresh_input_name = self.push_scope(Some(input_name_hint));
-                    input_names.push(Located::generated(fresh_input_name));
+                    input_names.push((
+                        Vec::new(),
+                        *plicity,
+                        Located::generated(fresh_input_name),
+                        None,
+                    ));
                     current_output_term = output_term;
                 }
 
@@ -183,63 +224,133 @@ impl<'globals> Context<'globals> {
 
                 surface::TermData::FunctionTerm(input_names, Box::new(output_term))
             }
-            TermData::FunctionElim(head_term, input_term) => {
+            TermData::FunctionElim(head_term, plicity, input_term) => {
                 let mut current_head_term = head_term;
 
-                let mut input_terms = vec![self.from_term(input_term)];
-                while let TermData::FunctionElim(head_term, input_term) = &current_head_term.data {
-                    input_terms.push(self.from_term(input_term));
+                let mut input_terms = vec![(*plicity, self.from_term(input_term))];
+                while let TermData::FunctionElim(head_term, plicity, input_term) =
+                    &current_head_term.data
+                {
+                    input_terms.push((*plicity, self.from_term(input_term)));
                     current_head_term = head_term;
                 }
                 input_terms.reverse();
 
-                let head_term = self.from_term(current_head_term);
-                surface::TermData::FunctionElim(Box::new(head_term), input_terms)
+                match &current_head_term.data {
+                    // Distill applications of infix operators to operator syntax
+                    TermData::Global(name)
+                        if self.globals.get_operator(name).is_some()
+                            && matches!(
+                                &input_terms[..],
+                                [(Plicity::Explicit, _), (Plicity::Explicit, _)],
+                            ) =>
+                    {
+                        let mut input_terms = input_terms.into_iter().map(|(_, term)| term);
+                        let input_term0 = input_terms.next().unwrap();
+                        let input_term1 = input_terms.next().unwrap();
+                        surface::TermData::Operators(
+                            Box::new(input_term0),
+                            vec![(Located::generated(name.clone()), input_term1)],
+                        )
+                    }
+                    _ => {
+                        let head_term = self.from_term(current_head_term);
+                        surface::TermData::FunctionElim(Box::new(head_term), input_terms)
+                    }
+                }
             }
 
-            TermData::RecordType(labels, types) => {
-                let type_entries = Iterator::zip(labels.iter(), types.iter())
-                    .map(|(label, entry_type)| {
-                        let entry_type = self.from_term(entry_type);
-                        let label = label.clone();
-                        match self.push_scope(Some(&label)) {
-                            name if name == label => (Located::generated(label), None, entry_type),
-                            name => (
-                                Located::generated(label),
-                                Some(Located::generated(name)),
-                                entry_type,
-                            ),
-                        }
-                    })
-                    .collect::<Vec<_>>();
+            TermData::RecordType(labels, docs, types, defaults) => {
+                let type_entries = Iterator::zip(
+                    labels.iter(),
+                    Iterator::zip(docs.iter(), Iterator::zip(types.iter(), defaults.iter())),
+                )
+                .map(|(label, (docs, (entry_type, default_term)))| {
+                    let docs = from_docs(docs);
+                    let entry_type = self.from_term(entry_type);
+                    let default_term = (default_term.as_ref()).map(|term| self.from_term(term));
+                    let label = label.clone();
+                    match self.push_scope(Some(&label)) {
+                        name if name == label => (
+                            docs,
+                            Located::generated(label),
+                            None,
+                            entry_type,
+                            default_term,
+                        ),
+                        name => (
+                            docs,
+                            Located::generated(label),
+                            Some(Located::generated(name)),
+                            entry_type,
+                            default_term,
+                        ),
+                    }
+                })
+                .collect::<Vec<_>>();
                 self.pop_scopes(type_entries.len());
 
                 surface::TermData::RecordType(type_entries)
             }
-            TermData::RecordTerm(labels, terms) => {
-                let term_entries = Iterator::zip(labels.iter(), terms.iter())
-                    .map(|(label, entry_type)| {
-                        let entry_type = self.from_term(entry_type);
-                        let label = label.clone();
-                        match self.push_scope(Some(&label)) {
-                            name if name == label => (Located::generated(label), None, entry_type),
-                            name => (
-                                Located::generated(label),
-                                Some(Located::generated(name)),
-                                entry_type,
-                            ),
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                self.pop_scopes(term_entries.len());
-
-                surface::TermData::RecordTerm(term_entries)
-            }
+            TermData::RecordTerm(labels, terms) => self.distill_record_term(labels, terms, &[]),
             TermData::RecordElim(head_term, label) => surface::TermData::RecordElim(
                 Box::new(self.from_term(head_term)),
                 Located::generated(label.clone()),
             ),
 
+            TermData::VariantType(labels, types) => surface::TermData::VariantType(
+                Iterator::zip(labels.iter(), types.iter())
+                    .map(|(label, payload_type)| {
+                        let payload_type = match &payload_type.data {
+                            // Omit empty payload types
+                            TermData::RecordType(labels, _, _, _) if labels.is_empty() => None,
+                            _ => Some(self.from_term(payload_type)),
+                        };
+                        (Located::generated(label.clone()), payload_type)
+                    })
+                    .collect(),
+            ),
+            TermData::VariantTerm(label, payload_term) => surface::TermData::VariantTerm(
+                Located::generated(label.clone()),
+                match &payload_term.data {
+                    // Omit empty payloads
+                    TermData::RecordTerm(labels, _) if labels.is_empty() => None,
+                    _ => Some(Box::new(self.from_term(payload_term))),
+                },
+            ),
+            TermData::VariantElim(head_term, labels, branches) => {
+                let head_term = self.from_term(head_term);
+                let branches = Iterator::zip(labels.iter(), branches.iter())
+                    .map(|(label, branch_term)| {
+                        let name = self.push_scope(Some(label));
+                        let branch_term = self.from_term(branch_term);
+                        self.pop_scope();
+                        (
+                            Located::generated(label.clone()),
+                            Some(Located::generated(name)),
+                            branch_term,
+                        )
+                    })
+                    .collect();
+
+                surface::TermData::Match(Box::new(head_term), branches)
+            }
+            // Data eliminations are distilled as applications of the
+            // generated eliminator, leaving the parameters to be inferred.
+            TermData::DataElim(head_term, name, motive, methods) => {
+                let elim_name = data::elim_name(name);
+                let input_terms = std::iter::once(motive)
+                    .chain(methods.iter())
+                    .chain(std::iter::once(head_term))
+                    .map(|input_term| (Plicity::Explicit, self.from_term(input_term)))
+                    .collect();
+
+                surface::TermData::FunctionElim(
+                    Box::new(surface::Term::generated(surface::TermData::Name(elim_name))),
+                    input_terms,
+                )
+            }
+
             TermData::ArrayTerm(entry_terms) | TermData::ListTerm(entry_terms) => {
                 let core_entry_terms = entry_terms
                     .iter()
@@ -254,21 +365,88 @@ impl<'globals> Context<'globals> {
                 Constant::U16(value) => surface::TermData::NumberTerm(value.to_string()),
                 Constant::U32(value) => surface::TermData::NumberTerm(value.to_string()),
                 Constant::U64(value) => surface::TermData::NumberTerm(value.to_string()),
+                Constant::U128(value) => surface::TermData::NumberTerm(value.to_string()),
                 Constant::S8(value) => surface::TermData::NumberTerm(value.to_string()),
                 Constant::S16(value) => surface::TermData::NumberTerm(value.to_string()),
                 Constant::S32(value) => surface::TermData::NumberTerm(value.to_string()),
                 Constant::S64(value) => surface::TermData::NumberTerm(value.to_string()),
-                Constant::F32(value) => surface::TermData::NumberTerm(value.to_string()),
-                Constant::F64(value) => surface::TermData::NumberTerm(value.to_string()),
+                Constant::S128(value) => surface::TermData::NumberTerm(value.to_string()),
+                Constant::Nat(value) => surface::TermData::NumberTerm(value.to_string()),
+                Constant::Int(value) => surface::TermData::NumberTerm(value.to_string()),
+                // Debug formatting uses the shortest representation that round-trips
+                Constant::F32(value) => surface::TermData::NumberTerm(format!("{:?}", value)),
+                Constant::F64(value) => surface::TermData::NumberTerm(format!("{:?}", value)),
                 Constant::Char(value) => surface::TermData::CharTerm(format!("{:?}", value)),
-                Constant::String(value) => surface::TermData::StringTerm(format!("{:?}", value)),
+                Constant::String(value) => {
+                    surface::TermData::StringTerm(literal::utf8_string_to_quoted(value))
+                }
+                Constant::Bytes(value) => {
+                    surface::TermData::BytesTerm(literal::bytes_to_quoted(value))
+                }
             },
 
+            TermData::Meta(meta) => surface::TermData::Hole(Some(meta.id().to_string())),
+
             TermData::Error => surface::TermData::Error,
         };
 
         surface::Term::generated(term_data)
     }
+
+    /// Distill a record term, omitting any entries that are equal to the
+    /// corresponding `defaults` from its record type.
+    fn distill_record_term(
+        &mut self,
+        labels: &[String],
+        terms: &[Arc<Term>],
+        defaults: &[Option<Arc<Term>>],
+    ) -> surface::TermData {
+        let mut term_entries = Vec::with_capacity(terms.len());
+
+        for (index, (label, entry_term)) in Iterator::zip(labels.iter(), terms.iter()).enumerate() {
+            let is_default = match defaults.get(index) {
+                Some(Some(default_term)) => self.is_equal_terms(entry_term, default_term),
+                Some(None) | None => false,
+            };
+            let entry_term = self.from_term(entry_term);
+            let label = label.clone();
+            let name = match self.push_scope(Some(&label)) {
+                name if name == label => None,
+                name => Some(Located::generated(name)),
+            };
+            if !is_default {
+                term_entries.push((
+                    Vec::new(),
+                    Located::generated(label),
+                    name,
+                    None,
+                    entry_term,
+                ));
+            }
+        }
+        self.pop_scopes(terms.len());
+
+        surface::TermData::RecordTerm(term_entries)
+    }
+
+    /// Check if two terms in the current scope evaluate to the same value.
+    fn is_equal_terms(&self, term0: &Term, term1: &Term) -> bool {
+        let mut values = Env::new();
+        for _ in 0..self.names.len() {
+            values.push(Arc::new(Value::var(values.size().next_level(), [])));
+        }
+        let value0 = semantics::eval(self.globals, &mut values, term0);
+        let value1 = semantics::eval(self.globals, &mut values, term1);
+        semantics::is_equal(self.globals, values.size(), &value0, &value1)
+    }
+}
+
+/// Split documentation into the lines of a doc comment.
+fn from_docs(docs: &str) -> surface::DocLines {
+    match docs {
+        "" => Vec::new(),
+        docs => docs.split('\n').map(str::to_owned).collect(),
+    }
 }
 
 #[cfg(test)]
@@ -359,4 +537,37 @@ mod tests {
         assert_eq!(state.push_scope(Some("Type")), "Type-1");
         assert_eq!(state.push_scope(Some("Type")), "Type-2");
     }
+
+    #[test]
+    fn omit_default_entries() {
+        let globals = Globals::default();
+        let mut state = Context::new(&globals);
+
+        let labels = Arc::<[String]>::from(vec!["x".to_owned(), "y".to_owned()]);
+        let s32 = |value| Arc::new(Term::generated(TermData::from(Constant::S32(value))));
+        let s32_type = Arc::new(Term::generated(TermData::Global("S32".to_owned())));
+        let term = Term::generated(TermData::Ann(
+            Arc::new(Term::generated(TermData::RecordTerm(
+                labels.clone(),
+                vec![s32(1), s32(2)].into(),
+            ))),
+            Arc::new(Term::generated(TermData::RecordType(
+                labels,
+                vec![String::new(), String::new()].into(),
+                vec![s32_type.clone(), s32_type].into(),
+                vec![Some(s32(1)), Some(s32(3))].into(),
+            ))),
+        ));
+
+        match state.from_term(&term).data {
+            surface::TermData::Ann(term, _) => match term.data {
+                surface::TermData::RecordTerm(entries) => {
+                    let labels = entries.iter().map(|(_, label, _, _, _)| &label.data);
+                    assert_eq!(labels.collect::<Vec<_>>(), ["y"]);
+                }
+                _ => panic!("expected a record term"),
+            },
+            _ => panic!("expected an annotated term"),
+        }
+    }
 }
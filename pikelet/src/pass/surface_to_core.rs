@@ -7,16 +7,76 @@
 
 use contracts::debug_ensures;
 use crossbeam_channel::Sender;
-use num_traits::{Float, PrimInt, Signed, Unsigned};
+use num_traits::{Signed, Unsigned};
 use std::sync::Arc;
 
-use crate::lang::core::semantics::{self, Elim, RecordClosure, Unfold, Value};
-use crate::lang::surface::{Term, TermData};
-use crate::lang::{core, Location};
-use crate::literal;
+use crate::lang::core::builtins;
+use crate::lang::core::semantics::{self, Elim, Head, RecordClosure, Unfold, Value};
+use crate::lang::surface::{Branch, DataDecl, Module, OperatorDecl, Term, TermData};
+use crate::lang::{core, Associativity, Fixity, Located, Location, Plicity};
+use crate::literal::{self, StringPart};
 use crate::pass::core_to_surface;
 use crate::reporting::{AmbiguousTerm, ExpectedType, Message, SurfaceToCoreMessage};
 
+mod suggestions;
+mod unification;
+
+/// Elaborate the declarations at the start of a module, defining them in the
+/// globals.
+pub fn define_module_decls(
+    globals: &mut core::Globals,
+    message_tx: &Sender<Message>,
+    module: &Module,
+) {
+    define_data_decls(globals, message_tx, &module.data_decls);
+    define_operator_decls(globals, message_tx, &module.operator_decls);
+}
+
+/// Elaborate some data declarations, defining them in the globals.
+///
+/// Each declaration can refer to the data types defined by the declarations
+/// that precede it.
+pub fn define_data_decls(
+    globals: &mut core::Globals,
+    message_tx: &Sender<Message>,
+    data_decls: &[DataDecl],
+) {
+    for data_decl in data_decls {
+        let data_decl = Context::new(globals, message_tx.clone()).check_data_decl(data_decl);
+        if let Some(data_decl) = data_decl {
+            globals.define_data(data_decl);
+        }
+    }
+}
+
+/// Elaborate some operator declarations, defining them in the globals.
+///
+/// Each declaration can refer to the operators defined by the declarations
+/// that precede it.
+pub fn define_operator_decls(
+    globals: &mut core::Globals,
+    message_tx: &Sender<Message>,
+    operator_decls: &[OperatorDecl],
+) {
+    for operator_decl in operator_decls {
+        let operator_decl =
+            Context::new(globals, message_tx.clone()).check_operator_decl(operator_decl);
+        if let Some((name, fixity, r#type, term)) = operator_decl {
+            globals.define_operator(name, fixity, Arc::new(r#type), Arc::new(term));
+        }
+    }
+}
+
+/// The term that a metavariable was created to stand in for.
+enum MetaSource {
+    /// An implicit input, with the name of the input if it is known.
+    ImplicitInput(Option<String>),
+    /// A hole, with an optional name.
+    Hole(Option<String>),
+    /// The type of a hole whose type could not be inferred.
+    HoleType,
+}
+
 /// Elaboration context.
 pub struct Context<'globals> {
     /// Global definition environment.
@@ -27,6 +87,13 @@ pub struct Context<'globals> {
     values: core::Env<Arc<Value>>,
     /// Distillation context (used for pretty printing).
     core_to_surface: core_to_surface::Context<'globals>,
+    /// Metavariables created during elaboration, along with the location of
+    /// the term that created them and what they stand in for.
+    metas: Vec<(Arc<core::MetaVar>, Location, MetaSource)>,
+    /// Number literals that were checked against unsolved metavariables,
+    /// along with the metavariable standing in for the elaborated literal,
+    /// the expected type, and the size of the context they were checked in.
+    literals: Vec<(Arc<core::MetaVar>, Term, Arc<Value>, core::EnvSize)>,
     /// The diagnostic messages accumulated during elaboration.
     message_tx: Sender<Message>,
 }
@@ -39,6 +106,8 @@ impl<'globals> Context<'globals> {
             types: Vec::new(),
             values: core::Env::new(),
             core_to_surface: core_to_surface::Context::new(globals),
+            metas: Vec::new(),
+            literals: Vec::new(),
             message_tx,
         }
     }
@@ -138,6 +207,136 @@ impl<'globals> Context<'globals> {
         semantics::is_equal(self.globals, self.size(), value0, value1)
     }
 
+    /// Force any solved metavariables at the head of a value.
+    fn force_metas(&self, value: &Arc<Value>) -> Arc<Value> {
+        semantics::force_metas(self.globals, value)
+    }
+
+    /// Unify two values, solving any metavariables along the way.
+    fn unify(&self, value0: &Arc<Value>, value1: &Arc<Value>) -> bool {
+        unification::unify(self.globals, self.size(), value0, value1)
+    }
+
+    /// Create a fresh metavariable, applied to the variables that are
+    /// currently bound in the context.
+    fn push_meta(&mut self, location: Location, source: MetaSource) -> core::Term {
+        let meta = Arc::new(core::MetaVar::new(self.metas.len()));
+        self.metas.push((meta.clone(), location, source));
+
+        let size = self.size();
+        let mut bound_indices = (core::var_indices().take(size.to_usize()))
+            .filter(
+                |index| match self.values.get(*index).map(|value| value.as_ref()) {
+                    // Definitions are not included, because they would not be
+                    // valid pattern unification problems.
+                    Some(Value::Stuck(semantics::Head::Var(level), spine)) => {
+                        spine.is_empty() && size.index_to_level(*index) == Some(*level)
+                    }
+                    _ => false,
+                },
+            )
+            .collect::<Vec<_>>();
+        bound_indices.reverse();
+
+        let meta_term = core::Term::new(location, core::TermData::Meta(meta));
+        bound_indices
+            .into_iter()
+            .fold(meta_term, |head_term, index| {
+                core::Term::new(
+                    location,
+                    core::TermData::FunctionElim(
+                        Arc::new(head_term),
+                        Plicity::Explicit,
+                        Arc::new(core::Term::new(location, core::TermData::Var(index))),
+                    ),
+                )
+            })
+    }
+
+    /// Postpone checking a number literal against an unsolved metavariable,
+    /// returning a metavariable that will be solved with the literal once the
+    /// expected type is known.
+    fn postpone_literal(&mut self, term: &Term, expected_type: &Arc<Value>) -> core::Term {
+        let meta = Arc::new(core::MetaVar::new(self.metas.len()));
+        (self.metas).push((meta.clone(), term.location, MetaSource::ImplicitInput(None)));
+        let size = self.size();
+        (self.literals).push((meta.clone(), term.clone(), expected_type.clone(), size));
+
+        core::Term::new(term.location, core::TermData::Meta(meta))
+    }
+
+    /// Check the number literals postponed since `initial_metas`, defaulting
+    /// the types of the ones that are still unconstrained. This is done at
+    /// the end of each definition and record entry.
+    fn check_postponed_literals(&mut self, initial_metas: usize) {
+        let start = (self.literals.iter())
+            .position(|(meta, _, _, _)| meta.id() >= initial_metas)
+            .unwrap_or(self.literals.len());
+
+        for (meta, term, expected_type, size) in self.literals.split_off(start) {
+            let forced_type = self.force_metas(&expected_type);
+            if let (TermData::NumberTerm(data), Value::Stuck(Head::Meta(_), _)) =
+                (&term.data, forced_type.as_ref())
+            {
+                let literal_type = self.default_literal_type(term.location, data);
+                let default_type = Arc::new(Value::global(literal_type.global_name(), []));
+                unification::unify(self.globals, size, &expected_type, &default_type);
+
+                let default_type = self.read_back_to_surface(&default_type);
+                self.report(SurfaceToCoreMessage::DefaultedNumberLiteral {
+                    location: term.location,
+                    default_type,
+                });
+            }
+
+            let forced_type = self.force_metas(&expected_type);
+            let core_term = match self.convert_literal(&term, forced_type.force(self.globals)) {
+                Some(core_term) => core_term,
+                None => {
+                    let expected_type =
+                        semantics::read_back(self.globals, size, Unfold::Never, &expected_type);
+                    let expected_type = self.core_to_surface(&expected_type);
+                    self.report(SurfaceToCoreMessage::NoLiteralConversion {
+                        location: term.location,
+                        expected_type,
+                    });
+                    core::Term::new(term.location, core::TermData::Error)
+                }
+            };
+            let value = semantics::eval(self.globals, &mut core::Env::new(), &core_term);
+            meta.solve(value);
+        }
+    }
+
+    /// Check any number literals that were postponed since `initial_metas`,
+    /// then report any metavariables created since `initial_metas` that were
+    /// not solved, setting them to errors, and then replace the metavariables
+    /// in the term with their solutions.
+    fn zonk(&mut self, initial_metas: usize, term: &core::Term) -> core::Term {
+        self.check_postponed_literals(initial_metas);
+
+        for (meta, location, source) in self.metas.drain(initial_metas..) {
+            if meta.solve(Arc::new(Value::Error)) {
+                let message = match source {
+                    MetaSource::ImplicitInput(name) => {
+                        SurfaceToCoreMessage::UnsolvedMeta { location, name }
+                    }
+                    MetaSource::Hole(name) => SurfaceToCoreMessage::UnfilledHole { location, name },
+                    // The hole itself will also be unsolved, so there is no
+                    // need to report its type as well.
+                    MetaSource::HoleType => continue,
+                };
+                self.message_tx.send(message.into()).unwrap();
+            }
+        }
+
+        let mut values = core::Env::new();
+        for _ in 0..self.size().to_usize() {
+            values.push(Arc::new(Value::var(values.size().next_level(), [])));
+        }
+        unification::zonk(self.globals, &mut values, term)
+    }
+
     /// Distill a [`core::Term`] into a [`surface::Term`].
     ///
     /// [`core::Term`]: crate::lang::core::Term
@@ -158,14 +357,234 @@ impl<'globals> Context<'globals> {
         self.core_to_surface(&self.read_back(value))
     }
 
+    /// Report the expected type of a hole, along with the types of the local
+    /// variables that are currently in scope.
+    fn report_hole(
+        &mut self,
+        location: Location,
+        name: &Option<String>,
+        expected_type: Option<&Arc<Value>>,
+    ) {
+        let expected_type = expected_type.map(|r#type| self.read_back_to_surface(r#type));
+        let local_types = (self.types.clone().iter())
+            .filter_map(|(name, r#type)| {
+                let name = name.as_ref()?;
+                Some((name.clone(), self.read_back_to_surface(r#type)))
+            })
+            .collect();
+
+        self.report(SurfaceToCoreMessage::HoleGoal {
+            location,
+            name: name.clone(),
+            expected_type,
+            local_types,
+        });
+    }
+
     /// Check that a term is a type, and return the elaborated term.
+    pub fn is_type(&mut self, term: &Term) -> Option<core::Term> {
+        let initial_metas = self.metas.len();
+        let core_term = self.is_type_impl(term);
+        let core_term = self.zonk(initial_metas, &core_term?);
+        Some(core_term)
+    }
+
+    /// Check that a term is an element of a type, and return the elaborated term.
+    pub fn check_type(&mut self, term: &Term, expected_type: &Arc<Value>) -> core::Term {
+        let initial_metas = self.metas.len();
+        let core_term = self.check_type_impl(term, expected_type);
+        self.zonk(initial_metas, &core_term)
+    }
+
+    /// Synthesize the type of a surface term, and return the elaborated term.
+    pub fn synth_type(&mut self, term: &Term) -> (core::Term, Arc<Value>) {
+        let initial_metas = self.metas.len();
+        let (core_term, r#type) = self.synth_type_impl(term);
+        (self.zonk(initial_metas, &core_term), r#type)
+    }
+
+    /// Elaborate an operator declaration, returning `None` if it could not be defined.
+    fn check_operator_decl(
+        &mut self,
+        operator_decl: &OperatorDecl,
+    ) -> Option<(String, Fixity, core::Term, core::Term)> {
+        let operator = &operator_decl.operator;
+        let is_defined = self.globals.get(&operator.data).is_some();
+        if is_defined {
+            self.report(SurfaceToCoreMessage::GlobalAlreadyDefined {
+                location: operator.location,
+                name: operator.data.clone(),
+            });
+        }
+
+        let precedence = &operator_decl.precedence;
+        let precedence =
+            literal::State::new(precedence.location, &precedence.data, &self.message_tx)
+                .number_to_unsigned_int::<u8>();
+        let (term, r#type) = self.synth_type(&operator_decl.term);
+        let r#type = self.read_back(&r#type);
+
+        let fixity = Fixity {
+            associativity: operator_decl.associativity,
+            precedence: precedence?,
+        };
+        match is_defined || matches!(term.data, core::TermData::Error) {
+            true => None,
+            false => Some((operator.data.clone(), fixity, r#type, term)),
+        }
+    }
+
+    /// Elaborate a data declaration in an empty context, returning `None` if
+    /// it could not be defined.
+    ///
+    /// The data type is bound as a local variable while the fields of the
+    /// constructors are elaborated, and is then replaced with a global.
+    fn check_data_decl(&mut self, data_decl: &DataDecl) -> Option<core::data::DataDecl> {
+        use std::collections::BTreeSet;
+
+        let initial_size = self.size();
+        let name = &data_decl.name.data;
+        let mut is_valid = true;
+
+        // Check that the generated globals are not already defined
+        let elim_name = core::data::elim_name(name);
+        let constructor_names = data_decl.constructors.iter().map(|(name, _)| name);
+        let mut defined_names = BTreeSet::new();
+        for (location, defined_name) in std::iter::once(&data_decl.name)
+            .map(|name| (name.location, name.data.as_str()))
+            .chain(std::iter::once((
+                data_decl.name.location,
+                elim_name.as_str(),
+            )))
+            .chain(constructor_names.map(|name| (name.location, name.data.as_str())))
+        {
+            // The eliminator shares the location of the data type's name, so
+            // it is not reported again if the data type's name was already
+            // defined.
+            if defined_name == elim_name && !is_valid {
+                continue;
+            }
+            if self.globals.get(defined_name).is_some() || !defined_names.insert(defined_name) {
+                self.report(SurfaceToCoreMessage::GlobalAlreadyDefined {
+                    location,
+                    name: defined_name.to_owned(),
+                });
+                is_valid = false;
+            }
+        }
+
+        let mut params = Vec::new();
+        for (param_names, param_type) in &data_decl.params {
+            for param_name in param_names {
+                let core_param_type = (self.is_type(param_type))
+                    .unwrap_or_else(|| core::Term::new(param_type.location, core::TermData::Error));
+                let core_param_type_value = self.eval(&core_param_type);
+                self.push_variable(Some(&param_name.data), core_param_type_value);
+                params.push((param_name.data.clone(), Arc::new(core_param_type)));
+            }
+        }
+        self.truncate_scopes(initial_size);
+
+        let mut core_data_decl = core::data::DataDecl {
+            name: name.clone(),
+            params,
+            constructors: Vec::new(),
+        };
+
+        // Bind the data type, followed by the parameters
+        let data_type = self.eval(&core_data_decl.r#type());
+        self.push_variable(Some(name), data_type);
+        for (param_name, param_type) in &core_data_decl.params {
+            let param_type = self.eval(param_type);
+            self.push_variable(Some(param_name), param_type);
+        }
+        let params_size = self.size();
+
+        let mut field_locations = Vec::with_capacity(data_decl.constructors.len());
+        for (constructor_name, field_groups) in &data_decl.constructors {
+            let mut fields = Vec::new();
+            let mut locations = Vec::new();
+
+            for (field_names, field_type) in field_groups {
+                for field_name in field_names {
+                    let core_field_type = (self.is_type(field_type)).unwrap_or_else(|| {
+                        core::Term::new(field_type.location, core::TermData::Error)
+                    });
+                    let core_field_type_value = self.eval(&core_field_type);
+                    self.push_variable(Some(&field_name.data), core_field_type_value);
+
+                    // Replace the local data type with a global, by evaluating
+                    // the field type in an environment where the outermost
+                    // variable is bound to the global.
+                    let field_size = self.size().to_usize() - initial_size.to_usize() - 2;
+                    let mut values = core::Env::new();
+                    values.push(Arc::new(Value::global(name.clone(), [])));
+                    for level in 0..field_size {
+                        let level = core::EnvSize::new(level as u32).next_level();
+                        values.push(Arc::new(Value::var(level, [])));
+                    }
+                    let core_field_type =
+                        semantics::eval(self.globals, &mut values, &core_field_type);
+                    let core_field_type = semantics::read_back(
+                        self.globals,
+                        core::EnvSize::new(field_size as u32),
+                        Unfold::Never,
+                        &core_field_type,
+                    );
+
+                    fields.push((field_name.data.clone(), Arc::new(core_field_type)));
+                    locations.push(field_type.location);
+                }
+            }
+            self.truncate_scopes(params_size);
+
+            core_data_decl
+                .constructors
+                .push(core::data::DataConstructor {
+                    name: constructor_name.data.clone(),
+                    fields,
+                });
+            field_locations.push(locations);
+        }
+        self.truncate_scopes(initial_size);
+
+        if let Err(error) = core_data_decl.check_positivity() {
+            let param_names = core_data_decl.params.iter().map(|(name, _)| name.clone());
+            let expected_type = Term::generated(TermData::Name(name.clone()));
+            let expected_type = match param_names.len() {
+                0 => expected_type,
+                _ => Term::generated(TermData::FunctionElim(
+                    Box::new(expected_type),
+                    param_names
+                        .map(|name| (Plicity::Explicit, Term::generated(TermData::Name(name))))
+                        .collect(),
+                )),
+            };
+
+            self.report(SurfaceToCoreMessage::InvalidDataOccurrence {
+                location: field_locations[error.constructor_index][error.field_index],
+                data_name: name.clone(),
+                occurrence: error.occurrence,
+                expected_type,
+            });
+            is_valid = false;
+        }
+
+        match is_valid {
+            true => Some(core_data_decl),
+            false => None,
+        }
+    }
+
     #[debug_ensures(self.types.len() == old(self.types.len()))]
     #[debug_ensures(self.values.size() == old(self.values.size()))]
-    pub fn is_type(&mut self, term: &Term) -> Option<core::Term> {
-        let (core_term, r#type) = self.synth_type(term);
+    fn is_type_impl(&mut self, term: &Term) -> Option<core::Term> {
+        let (core_term, r#type) = self.synth_type_impl(term);
+        let r#type = self.force_metas(&r#type);
         match r#type.force(self.globals) {
             Value::TypeType => Some(core_term),
             Value::Error => Some(core::Term::new(term.location, core::TermData::Error)),
+            _ if self.unify(&r#type, &Arc::new(Value::TypeType)) => Some(core_term),
             found_type => {
                 let found_type = self.read_back_to_surface(&found_type);
                 self.report(SurfaceToCoreMessage::MismatchedTypes {
@@ -178,24 +597,60 @@ impl<'globals> Context<'globals> {
         }
     }
 
-    /// Check that a term is an element of a type, and return the elaborated term.
     #[debug_ensures(self.types.len() == old(self.types.len()))]
     #[debug_ensures(self.values.size() == old(self.values.size()))]
-    pub fn check_type(&mut self, term: &Term, expected_type: &Arc<Value>) -> core::Term {
-        match (&term.data, expected_type.force(self.globals)) {
+    fn check_type_impl(&mut self, term: &Term, expected_type: &Arc<Value>) -> core::Term {
+        let forced_type = self.force_metas(expected_type);
+        match (&term.data, forced_type.force(self.globals)) {
+            (TermData::Hole(name), _) => {
+                self.report_hole(term.location, name, Some(expected_type));
+                self.push_meta(term.location, MetaSource::Hole(name.clone()))
+            }
             (_, Value::Error) => core::Term::new(term.location, core::TermData::Error),
 
             (TermData::FunctionTerm(input_names, output_term), _) => {
                 let initial_size = self.size();
                 let mut expected_type = expected_type.clone();
-                let mut pending_input_names = input_names.iter();
+                let mut pending_input_names = input_names.iter().peekable();
+                let mut core_inputs = Vec::with_capacity(input_names.len());
+
+                while let Some((_, input_plicity, input_name, input_type_annotation)) =
+                    pending_input_names.peek()
+                {
+                    let forced_type = self.force_metas(&expected_type);
+                    match forced_type.force(self.globals) {
+                        // Insert implicit inputs that were not bound by the function term
+                        Value::FunctionType(Plicity::Implicit, name_hint, input_type, closure)
+                            if *input_plicity == Plicity::Explicit =>
+                        {
+                            let input_value = self.push_variable(None, input_type.clone());
+                            let name_hint = name_hint.clone().unwrap_or_else(|| "t".to_owned());
+                            core_inputs.push((input_name.location, Plicity::Implicit, name_hint));
+                            expected_type = closure.apply(self.globals, input_value);
+                        }
+                        Value::FunctionType(plicity, _, input_type, closure)
+                            if plicity == input_plicity =>
+                        {
+                            if let Some(input_type_annotation) = input_type_annotation {
+                                if !self.check_type_annotation(input_type_annotation, input_type) {
+                                    self.truncate_scopes(initial_size);
+                                    return core::Term::new(term.location, core::TermData::Error);
+                                }
+                            }
 
-                while let Some(input_name) = pending_input_names.next() {
-                    match expected_type.force(self.globals) {
-                        Value::FunctionType(_, input_type, output_closure) => {
                             let input_value =
                                 self.push_variable(Some(&input_name.data), input_type.clone());
-                            expected_type = output_closure.apply(self.globals, input_value);
+                            let name = input_name.data.clone();
+                            core_inputs.push((input_name.location, *plicity, name));
+                            expected_type = closure.apply(self.globals, input_value);
+                            pending_input_names.next();
+                        }
+                        Value::FunctionType(_, _, _, _) => {
+                            self.report(SurfaceToCoreMessage::UnexpectedImplicitInput {
+                                location: input_name.location,
+                            });
+                            self.truncate_scopes(initial_size);
+                            return core::Term::new(term.location, core::TermData::Error);
                         }
                         Value::Error => {
                             self.truncate_scopes(initial_size);
@@ -203,64 +658,129 @@ impl<'globals> Context<'globals> {
                         }
                         _ => {
                             self.report(SurfaceToCoreMessage::TooManyInputsInFunctionTerm {
-                                unexpected_inputs: std::iter::once(input_name.location)
-                                    .chain(
-                                        pending_input_names.map(|input_name| input_name.location),
-                                    )
+                                unexpected_inputs: (pending_input_names)
+                                    .map(|(_, _, input_name, _)| input_name.location)
                                     .collect(),
                             });
-                            self.check_type(output_term, &expected_type);
+                            self.check_type_impl(output_term, &expected_type);
                             self.truncate_scopes(initial_size);
                             return core::Term::new(term.location, core::TermData::Error);
                         }
                     }
                 }
 
-                let core_output_term = self.check_type(output_term, &expected_type);
+                let core_output_term = self.check_type_impl(output_term, &expected_type);
                 self.truncate_scopes(initial_size);
-                (input_names.iter().rev()).fold(core_output_term, |core_output_term, input_name| {
-                    core::Term::new(
-                        Location::merge(input_name.location, core_output_term.location),
-                        core::TermData::FunctionTerm(
-                            input_name.data.clone(),
-                            Arc::new(core_output_term),
-                        ),
-                    )
-                })
+                (core_inputs.into_iter().rev()).fold(
+                    core_output_term,
+                    |core_output_term, (location, plicity, input_name)| {
+                        core::Term::new(
+                            Location::merge(location, core_output_term.location),
+                            core::TermData::FunctionTerm(
+                                plicity,
+                                input_name,
+                                Arc::new(core_output_term),
+                            ),
+                        )
+                    },
+                )
+            }
+            // Insert implicit function terms
+            (_, Value::FunctionType(Plicity::Implicit, name_hint, input_type, closure)) => {
+                let input_value = self.push_variable(None, input_type.clone());
+                let output_type = closure.apply(self.globals, input_value);
+                let core_output_term = self.check_type_impl(term, &output_type);
+                self.pop_scope();
+
+                core::Term::new(
+                    term.location,
+                    core::TermData::FunctionTerm(
+                        Plicity::Implicit,
+                        name_hint.clone().unwrap_or_else(|| "t".to_owned()),
+                        Arc::new(core_output_term),
+                    ),
+                )
             }
 
             (TermData::RecordTerm(term_entries), Value::RecordType(type_labels, closure)) => {
+                use std::collections::btree_map::{BTreeMap, Entry};
+
                 let initial_size = self.size();
-                let mut pending_entries = term_entries.iter();
-                let mut pending_type_labels = type_labels.iter();
-                let mut core_terms = Vec::with_capacity(pending_entries.len());
+                let mut pending_entries = BTreeMap::new();
+                let mut core_terms = Vec::with_capacity(term_entries.len());
 
                 let mut missing_labels = Vec::new();
                 let mut unexpected_labels = Vec::new();
 
+                for term_entry in term_entries {
+                    let (_, label, _, _, _) = term_entry;
+                    match pending_entries.entry(label.data.as_str()) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(term_entry);
+                        }
+                        Entry::Occupied(_) => unexpected_labels.push(label.location),
+                    }
+                }
+
+                // Entries are elaborated in the order given by the type,
+                // regardless of the order that they were written in, so that
+                // the dependencies between the entries are respected.
+                let mut type_labels_iter = type_labels.iter().enumerate();
+                let mut entry_values = Vec::with_capacity(type_labels.len());
                 closure.for_each_entry(self.globals, |r#type| {
-                    if let Some(label) = pending_type_labels.next() {
-                        while let Some((next_label, name, term)) = pending_entries.next() {
-                            if next_label.data == *label {
-                                let name = name.as_ref().unwrap_or(next_label);
-                                let core_term = self.check_type(term, &r#type);
-                                let core_value = self.eval(&core_term);
-
-                                self.push_definition(Some(&name.data), core_value.clone(), r#type);
+                    let (index, label) = type_labels_iter.next().unwrap();
+                    let core_value = match pending_entries.remove(label.as_str()) {
+                        Some((_, label, name, entry_type, term)) => {
+                            let name = name.as_ref().unwrap_or(label);
+                            let initial_metas = self.metas.len();
+                            let core_term = match entry_type {
+                                Some(entry_type)
+                                    if !self.check_type_annotation(entry_type, &r#type) =>
+                                {
+                                    core::Term::new(term.location, core::TermData::Error)
+                                }
+                                _ => self.check_type_impl(term, &r#type),
+                            };
+                            self.check_postponed_literals(initial_metas);
+                            let core_value = self.eval(&core_term);
+
+                            self.push_definition(Some(&name.data), core_value.clone(), r#type);
+                            core_terms.push(Arc::new(core_term));
+
+                            core_value
+                        }
+                        // Fill in omitted entries using the defaults from the type
+                        None => match closure.apply_default(self.globals, index, &entry_values) {
+                            Some(core_value) => {
+                                let core_term = self.read_back(&core_value);
+                                self.push_definition(Some(label), core_value.clone(), r#type);
                                 core_terms.push(Arc::new(core_term));
 
-                                return core_value;
-                            } else {
-                                unexpected_labels.push(next_label.location)
+                                core_value
                             }
-                        }
-                        missing_labels.push(label.to_owned());
-                    }
-                    Arc::new(Value::Error)
+                            None => {
+                                let core_value = Arc::new(Value::Error);
+                                missing_labels.push(label.to_owned());
+                                self.push_definition(Some(label), core_value.clone(), r#type);
+                                core_terms.push(Arc::new(core::Term::new(
+                                    term.location,
+                                    core::TermData::Error,
+                                )));
+
+                                core_value
+                            }
+                        },
+                    };
+                    entry_values.push(core_value.clone());
+                    core_value
                 });
 
                 self.truncate_scopes(initial_size);
-                unexpected_labels.extend(pending_entries.map(|(label, _, _)| label.location));
+                unexpected_labels.extend(
+                    pending_entries
+                        .values()
+                        .map(|(_, label, _, _, _)| label.location),
+                );
 
                 if !missing_labels.is_empty() || !unexpected_labels.is_empty() {
                     self.report(SurfaceToCoreMessage::InvalidRecordTerm {
@@ -276,16 +796,62 @@ impl<'globals> Context<'globals> {
                 )
             }
 
+            (TermData::VariantTerm(label, payload_term), Value::VariantType(labels, types)) => {
+                match labels
+                    .iter()
+                    .position(|type_label| *type_label == label.data)
+                {
+                    Some(index) => {
+                        let payload_type = types[index].clone();
+                        let core_payload_term = match payload_term {
+                            Some(payload_term) => self.check_type_impl(payload_term, &payload_type),
+                            // Variants without payloads are given an empty record
+                            None => self.check_type_impl(
+                                &Term::new(label.location, TermData::RecordTerm(Vec::new())),
+                                &payload_type,
+                            ),
+                        };
+
+                        core::Term::new(
+                            term.location,
+                            core::TermData::VariantTerm(
+                                label.data.clone(),
+                                Arc::new(core_payload_term),
+                            ),
+                        )
+                    }
+                    None => {
+                        let expected_type = self.read_back_to_surface(expected_type);
+                        self.report(SurfaceToCoreMessage::VariantLabelNotFound {
+                            location: label.location,
+                            label: label.data.clone(),
+                            expected_type,
+                        });
+                        core::Term::new(term.location, core::TermData::Error)
+                    }
+                }
+            }
+            (TermData::Match(head_term, branches), _) => {
+                let (core_term, _) =
+                    self.elaborate_match(term.location, head_term, branches, Some(expected_type));
+                core_term
+            }
+
             (TermData::SequenceTerm(entry_terms), forced_type) => match forced_type.try_global() {
-                Some(("Array", [Elim::Function(len), Elim::Function(core_entry_type)])) => {
+                Some((
+                    "Array",
+                    [Elim::Function(Plicity::Explicit, len), Elim::Function(Plicity::Explicit, core_entry_type)],
+                )) => {
                     let core_entry_type = core_entry_type.force(self.globals);
                     let core_entry_terms = entry_terms
                         .iter()
-                        .map(|entry_term| Arc::new(self.check_type(entry_term, core_entry_type)))
+                        .map(|entry_term| {
+                            Arc::new(self.check_type_impl(entry_term, core_entry_type))
+                        })
                         .collect();
 
                     let len = len.force(self.globals);
-                    match len.as_ref() {
+                    match len.force(self.globals) {
                         Value::Constant(core::Constant::U32(len))
                             if *len as usize == entry_terms.len() =>
                         {
@@ -306,11 +872,13 @@ impl<'globals> Context<'globals> {
                         }
                     }
                 }
-                Some(("List", [Elim::Function(core_entry_type)])) => {
+                Some(("List", [Elim::Function(Plicity::Explicit, core_entry_type)])) => {
                     let core_entry_type = core_entry_type.force(self.globals);
                     let core_entry_terms = entry_terms
                         .iter()
-                        .map(|entry_term| Arc::new(self.check_type(entry_term, core_entry_type)))
+                        .map(|entry_term| {
+                            Arc::new(self.check_type_impl(entry_term, core_entry_type))
+                        })
                         .collect();
 
                     core::Term::new(term.location, core::TermData::ListTerm(core_entry_terms))
@@ -324,70 +892,137 @@ impl<'globals> Context<'globals> {
                     core::Term::new(term.location, core::TermData::Error)
                 }
             },
-            (TermData::NumberTerm(data), forced_type) => match forced_type.try_global() {
-                Some(("U8", [])) => self.parse_unsigned(term.location, data, core::Constant::U8),
-                Some(("U16", [])) => self.parse_unsigned(term.location, data, core::Constant::U16),
-                Some(("U32", [])) => self.parse_unsigned(term.location, data, core::Constant::U32),
-                Some(("U64", [])) => self.parse_unsigned(term.location, data, core::Constant::U64),
-                Some(("S8", [])) => self.parse_signed(term.location, data, core::Constant::S8),
-                Some(("S16", [])) => self.parse_signed(term.location, data, core::Constant::S16),
-                Some(("S32", [])) => self.parse_signed(term.location, data, core::Constant::S32),
-                Some(("S64", [])) => self.parse_signed(term.location, data, core::Constant::S64),
-                Some(("F32", [])) => self.parse_float(term.location, data, core::Constant::F32),
-                Some(("F64", [])) => self.parse_float(term.location, data, core::Constant::F64),
-                Some(_) | None => {
-                    let expected_type = self.read_back_to_surface(expected_type);
-                    self.report(SurfaceToCoreMessage::NoLiteralConversion {
-                        location: term.location,
-                        expected_type,
-                    });
-                    core::Term::new(term.location, core::TermData::Error)
-                }
-            },
-            (TermData::CharTerm(data), forced_type) => match forced_type.try_global() {
-                Some(("Char", [])) => self.parse_char(term.location, data),
-                Some(_) | None => {
-                    let expected_type = self.read_back_to_surface(expected_type);
-                    self.report(SurfaceToCoreMessage::NoLiteralConversion {
-                        location: term.location,
-                        expected_type,
-                    });
-                    core::Term::new(term.location, core::TermData::Error)
+            (TermData::NumberTerm(_), Value::Stuck(Head::Meta(_), _)) => {
+                self.postpone_literal(term, expected_type)
+            }
+            (TermData::NumberTerm(_), forced_type)
+            | (TermData::CharTerm(_), forced_type)
+            | (TermData::StringTerm(_), forced_type)
+            | (TermData::BytesTerm(_), forced_type)
+                if !matches!(forced_type, Value::Stuck(Head::Meta(_), _)) =>
+            {
+                self.check_literal(term, expected_type, forced_type)
+            }
+
+            (_, _) => {
+                let (core_term, found_type) = self.synth_type_impl(term);
+                let (core_term, found_type) =
+                    self.insert_implicit_elims(term.location, core_term, found_type);
+
+                match self.unify(&found_type, expected_type) {
+                    true => core_term,
+                    false => {
+                        let found_type = self.read_back_to_surface(&found_type);
+                        let expected_type = self.read_back_to_surface(expected_type);
+                        self.report(SurfaceToCoreMessage::MismatchedTypes {
+                            location: term.location,
+                            found_type,
+                            expected_type: ExpectedType::Type(expected_type),
+                        });
+                        core::Term::new(term.location, core::TermData::Error)
+                    }
                 }
-            },
-            (TermData::StringTerm(data), forced_type) => match forced_type.try_global() {
-                Some(("String", [])) => self.parse_string(term.location, data),
-                Some(_) | None => {
-                    let expected_type = self.read_back_to_surface(expected_type);
-                    self.report(SurfaceToCoreMessage::NoLiteralConversion {
-                        location: term.location,
-                        expected_type,
+            }
+        }
+    }
+
+    /// Suggest a local or global name that is similar to a name that could
+    /// not be found.
+    fn suggest_name(&self, name: &str) -> Option<String> {
+        let local_names = self.types.iter().filter_map(|(name, _)| name.as_deref());
+        let global_names = self.globals.entries().map(|(name, _)| name.as_str());
+        suggestions::suggest_name(name, Iterator::chain(local_names, global_names))
+    }
+
+    /// Group a sequence of infix operator applications according to the
+    /// precedence and associativity of the operators, returning the
+    /// equivalent function elimination.
+    fn group_operators(
+        &mut self,
+        first_term: &Term,
+        rest_terms: &[(Located<String>, Term)],
+    ) -> Option<Term> {
+        use std::cmp::Ordering;
+
+        fn apply_operator(terms: &mut Vec<Term>, operator: &Located<String>) {
+            let input_term1 = terms.pop().unwrap();
+            let input_term0 = terms.pop().unwrap();
+            let head_term = Term::new(operator.location, TermData::Name(operator.data.clone()));
+            terms.push(Term::new(
+                Location::merge(input_term0.location, input_term1.location),
+                TermData::FunctionElim(
+                    Box::new(head_term),
+                    vec![
+                        (Plicity::Explicit, input_term0),
+                        (Plicity::Explicit, input_term1),
+                    ],
+                ),
+            ));
+        }
+
+        let mut fixities = Vec::with_capacity(rest_terms.len());
+        for (operator, _) in rest_terms {
+            match self.globals.get_operator(&operator.data) {
+                Some(fixity) => fixities.push(*fixity),
+                None => {
+                    let operator_names = (self.globals.entries())
+                        .map(|(name, _)| name.as_str())
+                        .filter(|name| self.globals.get_operator(name).is_some());
+                    self.report(SurfaceToCoreMessage::UnboundName {
+                        location: operator.location,
+                        name: operator.data.clone(),
+                        suggestion: suggestions::suggest_name(&operator.data, operator_names),
                     });
-                    core::Term::new(term.location, core::TermData::Error)
+                    return None;
                 }
-            },
+            }
+        }
 
-            (_, _) => match self.synth_type(term) {
-                (term, found_type) if self.is_equal(&found_type, expected_type) => term,
-                (_, found_type) => {
-                    let found_type = self.read_back_to_surface(&found_type);
-                    let expected_type = self.read_back_to_surface(expected_type);
-                    self.report(SurfaceToCoreMessage::MismatchedTypes {
-                        location: term.location,
-                        found_type,
-                        expected_type: ExpectedType::Type(expected_type),
-                    });
-                    core::Term::new(term.location, core::TermData::Error)
+        let mut terms = vec![first_term.clone()];
+        let mut operators = Vec::<(&Located<String>, Fixity)>::new();
+
+        for ((operator, term), fixity) in Iterator::zip(rest_terms.iter(), fixities) {
+            while let Some((previous_operator, previous_fixity)) = operators.last() {
+                let is_grouped = match previous_fixity.precedence.cmp(&fixity.precedence) {
+                    Ordering::Greater => true,
+                    Ordering::Less => false,
+                    Ordering::Equal => {
+                        match (previous_fixity.associativity, fixity.associativity) {
+                            (Associativity::Left, Associativity::Left) => true,
+                            (Associativity::Right, Associativity::Right) => false,
+                            (_, _) => {
+                                self.report(SurfaceToCoreMessage::AmbiguousOperators {
+                                    first_location: previous_operator.location,
+                                    first_operator: previous_operator.data.clone(),
+                                    second_location: operator.location,
+                                    second_operator: operator.data.clone(),
+                                });
+                                return None;
+                            }
+                        }
+                    }
+                };
+                if !is_grouped {
+                    break;
                 }
-            },
+                apply_operator(&mut terms, previous_operator);
+                operators.pop();
+            }
+            operators.push((operator, fixity));
+            terms.push(term.clone());
+        }
+
+        while let Some((operator, _)) = operators.pop() {
+            apply_operator(&mut terms, operator);
         }
+
+        terms.pop()
     }
 
-    /// Synthesize the type of a surface term, and return the elaborated term.
     #[debug_ensures(self.types.len() == old(self.types.len()))]
     #[debug_ensures(self.values.size() == old(self.values.size()))]
-    pub fn synth_type(&mut self, term: &Term) -> (core::Term, Arc<Value>) {
-        use std::collections::BTreeMap;
+    fn synth_type_impl(&mut self, term: &Term) -> (core::Term, Arc<Value>) {
+        use std::collections::{BTreeMap, BTreeSet};
 
         let error_term = || core::Term::new(term.location, core::TermData::Error);
 
@@ -406,17 +1041,18 @@ impl<'globals> Context<'globals> {
                 self.report(SurfaceToCoreMessage::UnboundName {
                     location: term.location,
                     name: name.clone(),
+                    suggestion: self.suggest_name(name),
                 });
                 (error_term(), Arc::new(Value::Error))
             }
 
             TermData::Ann(term, r#type) => {
-                let core_type = match self.is_type(r#type) {
+                let core_type = match self.is_type_impl(r#type) {
                     Some(core_type) => core_type,
                     None => return (error_term(), Arc::new(Value::Error)),
                 };
                 let core_type_value = self.eval(&core_type);
-                let core_term = self.check_type(term, &core_type_value);
+                let core_term = self.check_type_impl(term, &core_type_value);
                 (
                     core::Term::new(
                         term.location,
@@ -430,9 +1066,9 @@ impl<'globals> Context<'globals> {
                 let initial_size = self.size();
                 let mut core_inputs = Vec::new();
 
-                for (input_names, input_type) in input_type_groups {
+                for (_, plicity, input_names, input_type) in input_type_groups {
                     for input_name in input_names {
-                        let core_input_type = match self.is_type(input_type) {
+                        let core_input_type = match self.is_type_impl(input_type) {
                             Some(core_input_type) => core_input_type,
                             None => {
                                 self.truncate_scopes(initial_size);
@@ -442,11 +1078,11 @@ impl<'globals> Context<'globals> {
 
                         let core_input_type_value = self.eval(&core_input_type);
                         self.push_variable(Some(&input_name.data), core_input_type_value);
-                        core_inputs.push((input_name.clone(), core_input_type));
+                        core_inputs.push((*plicity, input_name.clone(), core_input_type));
                     }
                 }
 
-                let core_output_type = match self.is_type(output_type) {
+                let core_output_type = match self.is_type_impl(output_type) {
                     Some(core_output_type) => core_output_type,
                     None => {
                         self.truncate_scopes(initial_size);
@@ -456,10 +1092,11 @@ impl<'globals> Context<'globals> {
                 self.truncate_scopes(initial_size);
 
                 let mut core_type = core_output_type;
-                for (input_name, input_type) in core_inputs.into_iter().rev() {
+                for (plicity, input_name, input_type) in core_inputs.into_iter().rev() {
                     core_type = core::Term::new(
                         Location::merge(input_name.location, output_type.location),
                         core::TermData::FunctionType(
+                            plicity,
                             Some(input_name.data),
                             Arc::new(input_type),
                             Arc::new(core_type),
@@ -470,18 +1107,19 @@ impl<'globals> Context<'globals> {
                 (core_type, Arc::new(Value::TypeType))
             }
             TermData::FunctionArrowType(input_type, output_type) => {
-                let core_input_type = match self.is_type(input_type) {
+                let core_input_type = match self.is_type_impl(input_type) {
                     Some(core_input_type) => core_input_type,
                     None => return (error_term(), Arc::new(Value::Error)),
                 };
                 let core_input_type_value = self.eval(&core_input_type);
 
                 self.push_variable(None, core_input_type_value);
-                let (core_term, r#type) = match self.is_type(output_type) {
+                let (core_term, r#type) = match self.is_type_impl(output_type) {
                     Some(core_output_type) => (
                         core::Term::new(
                             term.location,
                             core::TermData::FunctionType(
+                                Plicity::Explicit,
                                 None,
                                 Arc::new(core_input_type),
                                 Arc::new(core_output_type),
@@ -495,38 +1133,136 @@ impl<'globals> Context<'globals> {
 
                 (core_term, r#type)
             }
-            TermData::FunctionTerm(_, _) => {
-                self.report(SurfaceToCoreMessage::AmbiguousTerm {
-                    location: term.location,
-                    term: AmbiguousTerm::FunctionTerm,
-                });
-                (error_term(), Arc::new(Value::Error))
+            TermData::FunctionTerm(input_names, output_term) => {
+                let initial_size = self.size();
+                let mut core_inputs = Vec::with_capacity(input_names.len());
+
+                for (_, plicity, input_name, input_type) in input_names {
+                    let input_type = match input_type {
+                        Some(input_type) => input_type,
+                        None => {
+                            self.truncate_scopes(initial_size);
+                            self.report(SurfaceToCoreMessage::AmbiguousTerm {
+                                location: term.location,
+                                term: AmbiguousTerm::FunctionTerm,
+                            });
+                            return (error_term(), Arc::new(Value::Error));
+                        }
+                    };
+                    let core_input_type = match self.is_type_impl(input_type) {
+                        Some(core_input_type) => core_input_type,
+                        None => {
+                            self.truncate_scopes(initial_size);
+                            return (error_term(), Arc::new(Value::Error));
+                        }
+                    };
+
+                    let core_input_type_value = self.eval(&core_input_type);
+                    self.push_variable(Some(&input_name.data), core_input_type_value);
+                    core_inputs.push((*plicity, input_name, core_input_type));
+                }
+
+                let (core_output_term, output_type) = self.synth_type_impl(output_term);
+                let core_output_type = self.read_back(&output_type);
+                self.truncate_scopes(initial_size);
+
+                let (core_term, core_type) = (core_inputs.into_iter().rev()).fold(
+                    (core_output_term, core_output_type),
+                    |(core_output_term, core_output_type), (plicity, input_name, input_type)| {
+                        let location = Location::merge(input_name.location, output_term.location);
+                        (
+                            core::Term::new(
+                                location,
+                                core::TermData::FunctionTerm(
+                                    plicity,
+                                    input_name.data.clone(),
+                                    Arc::new(core_output_term),
+                                ),
+                            ),
+                            core::Term::new(
+                                location,
+                                core::TermData::FunctionType(
+                                    plicity,
+                                    Some(input_name.data.clone()),
+                                    Arc::new(input_type),
+                                    Arc::new(core_output_type),
+                                ),
+                            ),
+                        )
+                    },
+                );
+
+                let core_type_value = self.eval(&core_type);
+                (
+                    core::Term::new(
+                        term.location,
+                        core::TermData::Ann(Arc::new(core_term), Arc::new(core_type)),
+                    ),
+                    core_type_value,
+                )
+            }
+            TermData::Operators(first_term, rest_terms) => {
+                match self.group_operators(first_term, rest_terms) {
+                    Some(term) => self.synth_type_impl(&term),
+                    None => (error_term(), Arc::new(Value::Error)),
+                }
             }
             TermData::FunctionElim(head_term, input_terms) => {
                 let mut head_location = head_term.location;
-                let (mut core_head_term, mut head_type) = self.synth_type(head_term);
-                let mut input_terms = input_terms.iter();
-
-                while let Some(input) = input_terms.next() {
-                    match head_type.force(self.globals) {
-                        Value::FunctionType(_, input_type, output_closure) => {
+                let (mut core_head_term, mut head_type) = self.synth_type_impl(head_term);
+                let mut input_terms = input_terms.iter().peekable();
+
+                while let Some((input_plicity, input)) = input_terms.peek() {
+                    let forced_type = self.force_metas(&head_type);
+                    match forced_type.force(self.globals) {
+                        // Insert implicit inputs that were not supplied explicitly
+                        Value::FunctionType(Plicity::Implicit, name_hint, _, output_closure)
+                            if *input_plicity == Plicity::Explicit =>
+                        {
+                            let core_input = self.push_meta(
+                                head_term.location,
+                                MetaSource::ImplicitInput(name_hint.clone()),
+                            );
+                            let core_input_value = self.eval(&core_input);
+                            core_head_term = core::Term::new(
+                                core_head_term.location,
+                                core::TermData::FunctionElim(
+                                    Arc::new(core_head_term),
+                                    Plicity::Implicit,
+                                    Arc::new(core_input),
+                                ),
+                            );
+                            head_type = output_closure.apply(self.globals, core_input_value);
+                        }
+                        Value::FunctionType(plicity, _, input_type, output_closure)
+                            if plicity == input_plicity =>
+                        {
                             head_location = input.location;
-                            let core_input = self.check_type(input, &input_type);
+                            let core_input = self.check_type_impl(input, &input_type);
                             let core_input_value = self.eval(&core_input);
                             core_head_term = core::Term::new(
                                 Location::merge(head_location, input.location),
                                 core::TermData::FunctionElim(
                                     Arc::new(core_head_term),
+                                    *plicity,
                                     Arc::new(core_input),
                                 ),
                             );
                             head_type = output_closure.apply(self.globals, core_input_value);
+                            input_terms.next();
+                        }
+                        Value::FunctionType(_, _, _, _) => {
+                            self.report(SurfaceToCoreMessage::UnexpectedImplicitInput {
+                                location: input.location,
+                            });
+                            return (error_term(), Arc::new(Value::Error));
                         }
                         Value::Error => return (error_term(), Arc::new(Value::Error)),
                         _ => {
                             let head_type = self.read_back_to_surface(&head_type);
+                            input_terms.next();
                             let unexpected_input_terms =
-                                input_terms.map(|arg| arg.location).collect();
+                                input_terms.map(|(_, input)| input.location).collect();
                             self.report(SurfaceToCoreMessage::TooManyInputsInFunctionElim {
                                 head_location,
                                 head_type,
@@ -540,30 +1276,87 @@ impl<'globals> Context<'globals> {
                 (core_head_term, head_type)
             }
 
-            TermData::RecordTerm(term_entries) if term_entries.is_empty() => {
-                let labels = Arc::new([]);
-                let entries = Arc::new([]);
-
-                let record_term_data = core::TermData::RecordTerm(labels.clone(), entries.clone());
-                let record_type_data = core::TermData::RecordType(labels.clone(), entries.clone());
-                let term_data = core::TermData::Ann(
-                    Arc::new(core::Term::new(term.location, record_term_data)),
-                    Arc::new(core::Term::new(term.location, record_type_data)),
-                );
-                let closure = RecordClosure::new(core::Env::new(), entries);
+            TermData::RecordTerm(term_entries) => {
+                let initial_size = self.size();
+                let mut seen_labels = BTreeSet::new();
+                let mut unexpected_labels = Vec::new();
+                let mut labels = Vec::with_capacity(term_entries.len());
+                let mut docs = Vec::with_capacity(term_entries.len());
+                let mut core_terms = Vec::with_capacity(term_entries.len());
+                let mut core_types = Vec::with_capacity(term_entries.len());
+
+                for (doc_lines, label, name, entry_type, entry_term) in term_entries {
+                    if !seen_labels.insert(label.data.as_str()) {
+                        unexpected_labels.push(label.location);
+                        continue;
+                    }
+
+                    let initial_metas = self.metas.len();
+                    let (core_term, core_type, r#type) = match entry_type {
+                        Some(entry_type) => match self.is_type_impl(entry_type) {
+                            Some(core_type) => {
+                                let r#type = self.eval(&core_type);
+                                let core_term = self.check_type_impl(entry_term, &r#type);
+                                (core_term, core_type, r#type)
+                            }
+                            None => {
+                                self.truncate_scopes(initial_size);
+                                return (error_term(), Arc::new(Value::Error));
+                            }
+                        },
+                        None => {
+                            let (core_term, r#type) = self.synth_type_impl(entry_term);
+                            let core_type = self.read_back(&r#type);
+                            (core_term, core_type, r#type)
+                        }
+                    };
+                    self.check_postponed_literals(initial_metas);
+
+                    let name = name.as_ref().unwrap_or(label);
+                    let value = self.eval(&core_term);
+                    self.push_definition(Some(&name.data), value, r#type);
+
+                    labels.push(label.data.clone());
+                    docs.push(doc_lines.join("\n"));
+                    core_terms.push(Arc::new(core_term));
+                    core_types.push(Arc::new(core_type));
+                }
+
+                self.truncate_scopes(initial_size);
+
+                if !unexpected_labels.is_empty() {
+                    self.report(SurfaceToCoreMessage::InvalidRecordTerm {
+                        location: term.location,
+                        missing_labels: Vec::new(),
+                        unexpected_labels,
+                    });
+                }
+
+                let labels = Arc::<[_]>::from(labels);
+                let defaults = vec![None; labels.len()];
+                let core_type = core::Term::new(
+                    term.location,
+                    core::TermData::RecordType(
+                        labels.clone(),
+                        docs.into(),
+                        core_types.into(),
+                        defaults.into(),
+                    ),
+                );
+                let core_type_value = self.eval(&core_type);
+                let core_term = core::Term::new(
+                    term.location,
+                    core::TermData::RecordTerm(labels, core_terms.into()),
+                );
 
                 (
-                    core::Term::new(term.location, term_data),
-                    Arc::from(Value::RecordType(labels, closure)),
+                    core::Term::new(
+                        term.location,
+                        core::TermData::Ann(Arc::new(core_term), Arc::new(core_type)),
+                    ),
+                    core_type_value,
                 )
             }
-            TermData::RecordTerm(_) => {
-                self.report(SurfaceToCoreMessage::AmbiguousTerm {
-                    location: term.location,
-                    term: AmbiguousTerm::RecordTerm,
-                });
-                (error_term(), Arc::new(Value::Error))
-            }
             TermData::RecordType(type_entries) => {
                 use std::collections::btree_map::Entry;
 
@@ -571,18 +1364,25 @@ impl<'globals> Context<'globals> {
                 let mut duplicate_labels = Vec::new();
                 let mut seen_labels = BTreeMap::new();
                 let mut labels = Vec::with_capacity(type_entries.len());
+                let mut docs = Vec::with_capacity(type_entries.len());
                 let mut core_types = Vec::with_capacity(type_entries.len());
+                let mut core_defaults = Vec::with_capacity(type_entries.len());
 
-                for (label, name, entry_type) in type_entries {
+                for (doc_lines, label, name, entry_type, default_term) in type_entries {
                     match seen_labels.entry(label.data.as_str()) {
-                        Entry::Vacant(entry) => match self.is_type(entry_type) {
+                        Entry::Vacant(entry) => match self.is_type_impl(entry_type) {
                             Some(core_type) => {
                                 let param_name = name.as_ref().unwrap_or(label);
                                 let core_type = Arc::new(core_type);
                                 let core_type_value = self.eval(&core_type);
+                                let core_default = (default_term.as_ref()).map(|default_term| {
+                                    Arc::new(self.check_type_impl(default_term, &core_type_value))
+                                });
 
                                 labels.push(label.data.clone());
+                                docs.push(doc_lines.join("\n"));
                                 core_types.push(core_type);
+                                core_defaults.push(core_default);
                                 self.push_variable(Some(&param_name.data), core_type_value);
                                 entry.insert(label.location);
                             }
@@ -595,7 +1395,7 @@ impl<'globals> Context<'globals> {
                             let seen_range = *entry.get();
                             let current_range = label.location;
                             duplicate_labels.push((label.data.clone(), seen_range, current_range));
-                            self.is_type(entry_type);
+                            self.is_type_impl(entry_type);
                         }
                     }
                 }
@@ -608,13 +1408,199 @@ impl<'globals> Context<'globals> {
                 (
                     core::Term::new(
                         term.location,
-                        core::TermData::RecordType(labels.into(), core_types.into()),
+                        core::TermData::RecordType(
+                            labels.into(),
+                            docs.into(),
+                            core_types.into(),
+                            core_defaults.into(),
+                        ),
                     ),
                     Arc::new(Value::TypeType),
                 )
             }
+            TermData::RecordUpdate(head_term, term_entries) => {
+                use std::collections::btree_map::Entry;
+
+                let (core_head_term, head_type) = self.synth_type_impl(head_term);
+                let (labels, closure) = match self.expect_record_type(head_term, &head_type) {
+                    Some(record_type) => record_type,
+                    None => return (error_term(), Arc::new(Value::Error)),
+                };
+
+                let mut updates = BTreeMap::new();
+                let mut duplicate_labels = Vec::new();
+
+                for (_, label, _, entry_type, entry_term) in term_entries {
+                    if !labels.contains(&label.data) {
+                        let head_type = self.read_back_to_surface(&head_type);
+                        let labels = labels.iter().map(String::as_str);
+                        self.report(SurfaceToCoreMessage::LabelNotFound {
+                            head_location: head_term.location,
+                            label_location: label.location,
+                            expected_label: label.data.clone(),
+                            head_type,
+                            suggestion: suggestions::suggest_name(&label.data, labels),
+                        });
+                        return (error_term(), Arc::new(Value::Error));
+                    }
+
+                    match updates.entry(label.data.as_str()) {
+                        Entry::Vacant(entry) => {
+                            entry.insert((label.location, entry_type, entry_term));
+                        }
+                        Entry::Occupied(entry) => {
+                            let seen_location = entry.get().0;
+                            duplicate_labels.push((
+                                label.data.clone(),
+                                seen_location,
+                                label.location,
+                            ));
+                        }
+                    }
+                }
+
+                if !duplicate_labels.is_empty() {
+                    self.report(SurfaceToCoreMessage::InvalidRecordUpdate { duplicate_labels });
+                    return (error_term(), Arc::new(Value::Error));
+                }
+
+                // Evaluate the head once, projecting the old entries from its value
+                let initial_size = self.size();
+                let head_value = self.eval(&core_head_term);
+
+                let mut is_ok = true;
+                let mut old_values = Vec::with_capacity(labels.len());
+                let mut new_values = Vec::with_capacity(labels.len());
+                let mut core_terms = Vec::with_capacity(labels.len());
+                let mut core_types = Vec::with_capacity(labels.len());
+
+                for (index, label) in labels.iter().enumerate() {
+                    let old_value = semantics::record_elim(self.globals, head_value.clone(), label);
+                    let new_type = closure.apply_entry(self.globals, index, &new_values);
+
+                    let (core_term, new_value) = match updates.get(label.as_str()) {
+                        Some((_, entry_type, entry_term)) => {
+                            let core_term = match entry_type {
+                                Some(entry_type)
+                                    if !self.check_type_annotation(entry_type, &new_type) =>
+                                {
+                                    is_ok = false;
+                                    core::Term::new(entry_term.location, core::TermData::Error)
+                                }
+                                _ => self.check_type_impl(entry_term, &new_type),
+                            };
+                            let new_value = self.eval(&core_term);
+                            (core_term, new_value)
+                        }
+                        None => {
+                            let old_type = closure.apply_entry(self.globals, index, &old_values);
+                            is_ok &=
+                                self.check_entry_type(term.location, label, &old_type, &new_type);
+                            (self.read_back(&old_value), old_value.clone())
+                        }
+                    };
+
+                    core_terms.push(Arc::new(core_term));
+                    core_types.push(Arc::new(self.read_back(&new_type)));
+                    old_values.push(old_value);
+                    new_values.push(new_value.clone());
+                    self.push_definition(None, new_value, new_type);
+                }
+
+                self.truncate_scopes(initial_size);
+                if !is_ok {
+                    return (error_term(), Arc::new(Value::Error));
+                }
+
+                self.annotated_record_term(term.location, labels, core_terms, core_types)
+            }
+            TermData::RecordMerge(left_term, right_term) => {
+                let (core_left_term, left_type) = self.synth_type_impl(left_term);
+                let (core_right_term, right_type) = self.synth_type_impl(right_term);
+                let left_record_type = self.expect_record_type(left_term, &left_type);
+                let right_record_type = self.expect_record_type(right_term, &right_type);
+                let ((left_labels, left_closure), (right_labels, right_closure)) =
+                    match (left_record_type, right_record_type) {
+                        (Some(left_record_type), Some(right_record_type)) => {
+                            (left_record_type, right_record_type)
+                        }
+                        (_, _) => return (error_term(), Arc::new(Value::Error)),
+                    };
+
+                // Evaluate each side once, projecting the entries from their values
+                let initial_size = self.size();
+                let left_value = self.eval(&core_left_term);
+                let right_value = self.eval(&core_right_term);
+                let left_values = (left_labels.iter())
+                    .map(|label| semantics::record_elim(self.globals, left_value.clone(), label))
+                    .collect::<Vec<_>>();
+                let right_values = (right_labels.iter())
+                    .map(|label| semantics::record_elim(self.globals, right_value.clone(), label))
+                    .collect::<Vec<_>>();
+
+                let labels = (left_labels.iter())
+                    .chain(
+                        right_labels
+                            .iter()
+                            .filter(|label| !left_labels.contains(label)),
+                    )
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                let mut is_ok = true;
+                let mut new_values = Vec::with_capacity(labels.len());
+                let mut core_terms = Vec::with_capacity(labels.len());
+                let mut core_types = Vec::with_capacity(labels.len());
+
+                for (index, label) in labels.iter().enumerate() {
+                    let (core_term, new_value, new_type) = match right_labels
+                        .iter()
+                        .position(|l| l == label)
+                    {
+                        Some(right_index) => (
+                            self.read_back(&right_values[right_index]),
+                            right_values[right_index].clone(),
+                            (right_closure).apply_entry(
+                                self.globals,
+                                right_index,
+                                &right_values[..right_index],
+                            ),
+                        ),
+                        // Entries that are only in the left record appear in the
+                        // same positions that they did in the left record.
+                        None => {
+                            let old_type = (left_closure).apply_entry(
+                                self.globals,
+                                index,
+                                &left_values[..index],
+                            );
+                            let new_type =
+                                left_closure.apply_entry(self.globals, index, &new_values);
+                            is_ok &=
+                                self.check_entry_type(term.location, label, &old_type, &new_type);
+                            (
+                                self.read_back(&left_values[index]),
+                                left_values[index].clone(),
+                                new_type,
+                            )
+                        }
+                    };
+
+                    core_terms.push(Arc::new(core_term));
+                    core_types.push(Arc::new(self.read_back(&new_type)));
+                    new_values.push(new_value.clone());
+                    self.push_definition(None, new_value, new_type);
+                }
+
+                self.truncate_scopes(initial_size);
+                if !is_ok {
+                    return (error_term(), Arc::new(Value::Error));
+                }
+
+                self.annotated_record_term(term.location, labels.into(), core_terms, core_types)
+            }
             TermData::RecordElim(head_term, label) => {
-                let (core_head_term, head_type) = self.synth_type(head_term);
+                let (core_head_term, head_type) = self.synth_type_impl(head_term);
 
                 match self.record_elim_type(&core_head_term, &head_type, &label.data) {
                     Some(entry_type) => match entry_type.as_ref() {
@@ -627,33 +1613,112 @@ impl<'globals> Context<'globals> {
                         }
                     },
                     None => {
+                        let suggestion = match self.force_metas(&head_type).force(self.globals) {
+                            Value::RecordType(labels, _) => {
+                                let labels = labels.iter().map(String::as_str);
+                                suggestions::suggest_name(&label.data, labels)
+                            }
+                            _ => None,
+                        };
                         let head_type = self.read_back_to_surface(&head_type);
                         self.report(SurfaceToCoreMessage::LabelNotFound {
                             head_location: head_term.location,
                             label_location: label.location,
                             expected_label: label.data.clone(),
                             head_type,
+                            suggestion,
                         });
                         (error_term(), Arc::new(Value::Error))
                     }
                 }
             }
 
-            TermData::SequenceTerm(_) => {
+            TermData::VariantType(variant_entries) => {
+                use std::collections::btree_map::Entry;
+
+                let mut duplicate_labels = Vec::new();
+                let mut seen_labels = BTreeMap::new();
+                let mut labels = Vec::with_capacity(variant_entries.len());
+                let mut core_types = Vec::with_capacity(variant_entries.len());
+
+                for (label, payload_type) in variant_entries {
+                    let core_type = match payload_type {
+                        Some(payload_type) => match self.is_type_impl(payload_type) {
+                            Some(core_type) => core_type,
+                            None => return (error_term(), Arc::new(Value::Error)),
+                        },
+                        // Variants without payloads are given an empty record type
+                        None => core::Term::new(
+                            label.location,
+                            core::TermData::RecordType(
+                                Vec::new().into(),
+                                Vec::new().into(),
+                                Vec::new().into(),
+                                Vec::new().into(),
+                            ),
+                        ),
+                    };
+
+                    match seen_labels.entry(label.data.as_str()) {
+                        Entry::Vacant(entry) => {
+                            labels.push(label.data.clone());
+                            core_types.push(Arc::new(core_type));
+                            entry.insert(label.location);
+                        }
+                        Entry::Occupied(entry) => {
+                            let seen_range = *entry.get();
+                            let current_range = label.location;
+                            duplicate_labels.push((label.data.clone(), seen_range, current_range));
+                        }
+                    }
+                }
+
+                if !duplicate_labels.is_empty() {
+                    self.report(SurfaceToCoreMessage::InvalidVariantType { duplicate_labels });
+                }
+
+                (
+                    core::Term::new(
+                        term.location,
+                        core::TermData::VariantType(labels.into(), core_types.into()),
+                    ),
+                    Arc::new(Value::TypeType),
+                )
+            }
+            TermData::VariantTerm(_, _) => {
                 self.report(SurfaceToCoreMessage::AmbiguousTerm {
                     location: term.location,
-                    term: AmbiguousTerm::Sequence,
+                    term: AmbiguousTerm::VariantTerm,
                 });
                 (error_term(), Arc::new(Value::Error))
             }
+            TermData::Match(head_term, branches) => {
+                self.elaborate_match(term.location, head_term, branches, None)
+            }
 
-            TermData::NumberTerm(_) => {
+            TermData::SequenceTerm(_) => {
                 self.report(SurfaceToCoreMessage::AmbiguousTerm {
                     location: term.location,
-                    term: AmbiguousTerm::NumberLiteral,
+                    term: AmbiguousTerm::Sequence,
                 });
                 (error_term(), Arc::new(Value::Error))
             }
+
+            TermData::NumberTerm(data) => {
+                let literal_type = self.default_literal_type(term.location, data);
+                let r#type = Arc::new(Value::global(literal_type.global_name(), []));
+
+                let default_type = self.read_back_to_surface(&r#type);
+                self.report(SurfaceToCoreMessage::DefaultedNumberLiteral {
+                    location: term.location,
+                    default_type,
+                });
+
+                match self.parse_literal(term, literal_type) {
+                    Some(core_term) => (core_term, r#type),
+                    None => (error_term(), Arc::new(Value::Error)),
+                }
+            }
             TermData::CharTerm(data) => (
                 self.parse_char(term.location, data),
                 Arc::new(Value::global("Char", [])),
@@ -662,12 +1727,406 @@ impl<'globals> Context<'globals> {
                 self.parse_string(term.location, data),
                 Arc::new(Value::global("String", [])),
             ),
+            TermData::BytesTerm(data) => (
+                self.parse_bytes(term.location, data),
+                Arc::new(Value::global("Bytes", [])),
+            ),
+
+            TermData::Hole(name) => {
+                self.report_hole(term.location, name, None);
+                let r#type = self.push_meta(term.location, MetaSource::HoleType);
+                let r#type = self.eval(&r#type);
+                let core_term = self.push_meta(term.location, MetaSource::Hole(name.clone()));
+                (core_term, r#type)
+            }
 
             TermData::Error => (error_term(), Arc::new(Value::Error)),
         }
     }
 
-    fn parse_float<T: Float + From<u8>>(
+    /// Check that a type annotation, for example on a function input or a
+    /// record entry, matches the expected type.
+    fn check_type_annotation(
+        &mut self,
+        type_annotation: &Term,
+        expected_type: &Arc<Value>,
+    ) -> bool {
+        let core_type = match self.is_type_impl(type_annotation) {
+            Some(core_type) => core_type,
+            None => return false,
+        };
+        let r#type = self.eval(&core_type);
+
+        match self.unify(&r#type, expected_type) {
+            true => true,
+            false => {
+                let expected_type = self.read_back_to_surface(expected_type);
+                self.report(SurfaceToCoreMessage::MismatchedTypes {
+                    location: type_annotation.location,
+                    found_type: type_annotation.clone(),
+                    expected_type: ExpectedType::Type(expected_type),
+                });
+                false
+            }
+        }
+    }
+
+    /// Apply a term to fresh metavariables for each of the leading implicit
+    /// inputs in its type.
+    fn insert_implicit_elims(
+        &mut self,
+        location: Location,
+        mut core_term: core::Term,
+        mut r#type: Arc<Value>,
+    ) -> (core::Term, Arc<Value>) {
+        loop {
+            let forced_type = self.force_metas(&r#type);
+            match forced_type.force(self.globals) {
+                Value::FunctionType(Plicity::Implicit, name_hint, _, output_closure) => {
+                    let core_input =
+                        self.push_meta(location, MetaSource::ImplicitInput(name_hint.clone()));
+                    let core_input_value = self.eval(&core_input);
+                    core_term = core::Term::new(
+                        core_term.location,
+                        core::TermData::FunctionElim(
+                            Arc::new(core_term),
+                            Plicity::Implicit,
+                            Arc::new(core_input),
+                        ),
+                    );
+                    r#type = output_closure.apply(self.globals, core_input_value);
+                }
+                _ => return (core_term, r#type),
+            }
+        }
+    }
+
+    /// Check that a term is a record, returning its labels and entry types.
+    fn expect_record_type(
+        &mut self,
+        term: &Term,
+        r#type: &Arc<Value>,
+    ) -> Option<(Arc<[String]>, RecordClosure)> {
+        let r#type = self.force_metas(r#type);
+        match r#type.force(self.globals) {
+            Value::RecordType(labels, closure) => Some((labels.clone(), closure.clone())),
+            Value::Error => None,
+            _ => {
+                let found_type = self.read_back_to_surface(&r#type);
+                self.report(SurfaceToCoreMessage::ExpectedRecord {
+                    location: term.location,
+                    found_type,
+                });
+                None
+            }
+        }
+    }
+
+    /// Check that the type of an entry that was copied from an existing record
+    /// was not changed by replacing the entries that it depends on.
+    fn check_entry_type(
+        &mut self,
+        location: Location,
+        label: &str,
+        old_type: &Arc<Value>,
+        new_type: &Arc<Value>,
+    ) -> bool {
+        match self.unify(old_type, new_type) {
+            true => true,
+            false => {
+                let found_type = self.read_back_to_surface(old_type);
+                let expected_type = self.read_back_to_surface(new_type);
+                self.report(SurfaceToCoreMessage::ChangedEntryType {
+                    location,
+                    label: label.to_owned(),
+                    found_type,
+                    expected_type,
+                });
+                false
+            }
+        }
+    }
+
+    /// Elaborate a match term, checking each of its branches against the
+    /// expected type. If no type is expected, the type of the branches is
+    /// inferred from their bodies.
+    fn elaborate_match(
+        &mut self,
+        location: Location,
+        head_term: &Term,
+        branches: &[Branch],
+        expected_type: Option<&Arc<Value>>,
+    ) -> (core::Term, Arc<Value>) {
+        use std::collections::btree_map::{BTreeMap, Entry};
+
+        let error_term = || core::Term::new(location, core::TermData::Error);
+
+        let (core_head_term, head_type) = self.synth_type_impl(head_term);
+        let head_type = self.force_metas(&head_type);
+        let (labels, types) = match head_type.force(self.globals) {
+            Value::VariantType(labels, types) => (labels.clone(), types.clone()),
+            Value::Error => return (error_term(), Arc::new(Value::Error)),
+            _ => {
+                let found_type = self.read_back_to_surface(&head_type);
+                self.report(SurfaceToCoreMessage::ExpectedVariant {
+                    location: head_term.location,
+                    found_type,
+                });
+                return (error_term(), Arc::new(Value::Error));
+            }
+        };
+
+        let (core_output_type, output_type) = match expected_type {
+            Some(expected_type) => (None, expected_type.clone()),
+            None => {
+                let core_output_type = self.push_meta(location, MetaSource::ImplicitInput(None));
+                let output_type = self.eval(&core_output_type);
+                (Some(core_output_type), output_type)
+            }
+        };
+
+        let mut pending_branches = BTreeMap::new();
+        let mut core_branches = Vec::with_capacity(labels.len());
+
+        let mut missing_labels = Vec::new();
+        let mut unexpected_labels = Vec::new();
+
+        for branch in branches {
+            let (label, _, _) = branch;
+            match pending_branches.entry(label.data.as_str()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(branch);
+                }
+                Entry::Occupied(_) => unexpected_labels.push(label.location),
+            }
+        }
+
+        // Branches are elaborated in the order given by the variant type
+        for (label, payload_type) in Iterator::zip(labels.iter(), types.iter()) {
+            match pending_branches.remove(label.as_str()) {
+                Some((_, name, branch_term)) => {
+                    let name = name.as_ref().map(|name| name.data.as_str());
+                    self.push_variable(name, payload_type.clone());
+                    let core_branch_term = self.check_type_impl(branch_term, &output_type);
+                    self.pop_scope();
+                    core_branches.push(Arc::new(core_branch_term));
+                }
+                None => {
+                    missing_labels.push(label.clone());
+                    core_branches.push(Arc::new(error_term()));
+                }
+            }
+        }
+
+        unexpected_labels.extend(
+            pending_branches
+                .values()
+                .map(|(label, _, _)| label.location),
+        );
+
+        if !missing_labels.is_empty() || !unexpected_labels.is_empty() {
+            self.report(SurfaceToCoreMessage::InvalidMatch {
+                location,
+                missing_labels,
+                unexpected_labels,
+            });
+        }
+
+        let core_term = core::Term::new(
+            location,
+            core::TermData::VariantElim(Arc::new(core_head_term), labels, core_branches.into()),
+        );
+
+        match core_output_type {
+            // Annotate inferred match terms so that they can be synthesized
+            // when validating the core term.
+            Some(core_output_type) => (
+                core::Term::new(
+                    location,
+                    core::TermData::Ann(Arc::new(core_term), Arc::new(core_output_type)),
+                ),
+                output_type,
+            ),
+            None => (core_term, output_type),
+        }
+    }
+
+    /// Construct a record term, annotated with its type so that it can be
+    /// synthesized when validating the core term.
+    fn annotated_record_term(
+        &mut self,
+        location: Location,
+        labels: Arc<[String]>,
+        core_terms: Vec<Arc<core::Term>>,
+        core_types: Vec<Arc<core::Term>>,
+    ) -> (core::Term, Arc<Value>) {
+        let docs = labels.iter().map(|_| String::new()).collect::<Vec<_>>();
+        let defaults = vec![None; labels.len()];
+        let core_type = core::Term::new(
+            location,
+            core::TermData::RecordType(
+                labels.clone(),
+                docs.into(),
+                core_types.into(),
+                defaults.into(),
+            ),
+        );
+        let core_type_value = self.eval(&core_type);
+        let core_term = core::Term::new(
+            location,
+            core::TermData::RecordTerm(labels, core_terms.into()),
+        );
+
+        (
+            core::Term::new(
+                location,
+                core::TermData::Ann(Arc::new(core_term), Arc::new(core_type)),
+            ),
+            core_type_value,
+        )
+    }
+
+    /// Check a literal against a built-in type, or against a global type that
+    /// has a literal conversion registered in the globals.
+    fn check_literal(
+        &mut self,
+        term: &Term,
+        expected_type: &Arc<Value>,
+        forced_type: &Value,
+    ) -> core::Term {
+        match self.convert_literal(term, forced_type) {
+            Some(core_term) => core_term,
+            None => {
+                let expected_type = self.read_back_to_surface(expected_type);
+                self.report(SurfaceToCoreMessage::NoLiteralConversion {
+                    location: term.location,
+                    expected_type,
+                });
+                core::Term::new(term.location, core::TermData::Error)
+            }
+        }
+    }
+
+    /// Convert a literal to a built-in type, or to a global type that has a
+    /// literal conversion registered in the globals, returning `None` if no
+    /// conversion could be made.
+    fn convert_literal(&mut self, term: &Term, forced_type: &Value) -> Option<core::Term> {
+        let conversion = match forced_type.try_global() {
+            Some((name, [])) => match core::LiteralType::from_global(name) {
+                Some(literal_type) => Some((literal_type, None)),
+                None => (self.globals.get_literal_conversion(name))
+                    .map(|(literal_type, function_name)| (*literal_type, Some(function_name))),
+            },
+            Some(_) | None => None,
+        };
+
+        let conversion = conversion.and_then(|(literal_type, function_name)| {
+            Some((self.parse_literal(term, literal_type)?, function_name))
+        });
+
+        match conversion? {
+            (core_term, None) => Some(core_term),
+            (core_term, Some(function_name)) => {
+                let function_term = core::TermData::Global(function_name.clone());
+                Some(core::Term::new(
+                    term.location,
+                    core::TermData::FunctionElim(
+                        Arc::new(core::Term::new(term.location, function_term)),
+                        Plicity::Explicit,
+                        Arc::new(core_term),
+                    ),
+                ))
+            }
+        }
+    }
+
+    /// The type that a number literal defaults to when it is unconstrained.
+    ///
+    /// Integer literals default to the smallest of `S32`, `S64`, and `Int`
+    /// that can hold them.
+    fn default_literal_type(&self, location: Location, source: &str) -> core::LiteralType {
+        if literal::State::new(location, source, &self.message_tx).number_is_float() {
+            return core::LiteralType::F64;
+        }
+
+        // Errors are reported later, when the literal is parsed as the default
+        // type, so they are ignored while finding a type that fits.
+        let (messages_tx, _messages_rx) = crossbeam_channel::unbounded();
+        let state = || literal::State::new(location, source, &messages_tx);
+        if state().number_to_signed_int::<i32>().is_some() {
+            core::LiteralType::S32
+        } else if state().number_to_signed_int::<i64>().is_some() {
+            core::LiteralType::S64
+        } else {
+            core::LiteralType::Int
+        }
+    }
+
+    /// Parse a literal as a built-in literal type, returning `None` if the
+    /// literal cannot be parsed as that type.
+    fn parse_literal(
+        &mut self,
+        term: &Term,
+        literal_type: core::LiteralType,
+    ) -> Option<core::Term> {
+        use crate::lang::core::{Constant, LiteralType};
+
+        let location = term.location;
+        match (&term.data, literal_type) {
+            (TermData::NumberTerm(data), LiteralType::U8) => {
+                Some(self.parse_unsigned(location, data, Constant::U8))
+            }
+            (TermData::NumberTerm(data), LiteralType::U16) => {
+                Some(self.parse_unsigned(location, data, Constant::U16))
+            }
+            (TermData::NumberTerm(data), LiteralType::U32) => {
+                Some(self.parse_unsigned(location, data, Constant::U32))
+            }
+            (TermData::NumberTerm(data), LiteralType::U64) => {
+                Some(self.parse_unsigned(location, data, Constant::U64))
+            }
+            (TermData::NumberTerm(data), LiteralType::U128) => {
+                Some(self.parse_unsigned(location, data, Constant::U128))
+            }
+            (TermData::NumberTerm(data), LiteralType::S8) => {
+                Some(self.parse_signed(location, data, Constant::S8))
+            }
+            (TermData::NumberTerm(data), LiteralType::S16) => {
+                Some(self.parse_signed(location, data, Constant::S16))
+            }
+            (TermData::NumberTerm(data), LiteralType::S32) => {
+                Some(self.parse_signed(location, data, Constant::S32))
+            }
+            (TermData::NumberTerm(data), LiteralType::S64) => {
+                Some(self.parse_signed(location, data, Constant::S64))
+            }
+            (TermData::NumberTerm(data), LiteralType::S128) => {
+                Some(self.parse_signed(location, data, Constant::S128))
+            }
+            (TermData::NumberTerm(data), LiteralType::Nat) => {
+                Some(self.parse_unsigned(location, data, Constant::Nat))
+            }
+            (TermData::NumberTerm(data), LiteralType::Int) => {
+                Some(self.parse_signed(location, data, Constant::Int))
+            }
+            (TermData::NumberTerm(data), LiteralType::F32) => {
+                Some(self.parse_float(location, data, Constant::F32))
+            }
+            (TermData::NumberTerm(data), LiteralType::F64) => {
+                Some(self.parse_float(location, data, Constant::F64))
+            }
+            (TermData::CharTerm(data), LiteralType::Char) => Some(self.parse_char(location, data)),
+            (TermData::StringTerm(data), LiteralType::String) => {
+                Some(self.parse_string(location, data))
+            }
+            (TermData::BytesTerm(data), LiteralType::Bytes) => {
+                Some(self.parse_bytes(location, data))
+            }
+            (_, _) => None,
+        }
+    }
+
+    fn parse_float<T: literal::FloatLiteral>(
         &mut self,
         location: Location,
         data: &str,
@@ -681,7 +2140,7 @@ impl<'globals> Context<'globals> {
         core::Term::new(location, term_data)
     }
 
-    fn parse_unsigned<T: PrimInt + Unsigned>(
+    fn parse_unsigned<T: literal::IntegerLiteral + Unsigned>(
         &mut self,
         location: Location,
         source: &str,
@@ -695,7 +2154,7 @@ impl<'globals> Context<'globals> {
         core::Term::new(location, term_data)
     }
 
-    fn parse_signed<T: PrimInt + Signed>(
+    fn parse_signed<T: literal::IntegerLiteral + Signed>(
         &mut self,
         location: Location,
         source: &str,
@@ -718,12 +2177,241 @@ impl<'globals> Context<'globals> {
         core::Term::new(location, term_data)
     }
 
-    fn parse_string(&mut self, location: Location, source: &str) -> core::Term {
+    fn parse_bytes(&mut self, location: Location, source: &str) -> core::Term {
         let term_data = literal::State::new(location, source, &self.message_tx)
-            .quoted_to_utf8_string()
-            .map(core::Constant::String)
+            .quoted_to_bytes()
+            .map(core::Constant::Bytes)
             .map_or(core::TermData::Error, core::TermData::from);
 
         core::Term::new(location, term_data)
     }
+
+    /// Parse a string literal, desugaring interpolated terms into applications
+    /// of `string-append`.
+    fn parse_string(&mut self, location: Location, source: &str) -> core::Term {
+        let parts = match literal::State::new(location, source, &self.message_tx)
+            .quoted_to_utf8_string_parts()
+        {
+            Some(parts) => parts,
+            None => return core::Term::new(location, core::TermData::Error),
+        };
+
+        let mut core_terms = parts.into_iter().map(|part| match part {
+            StringPart::Text(text) => {
+                core::Term::new(location, core::TermData::from(core::Constant::String(text)))
+            }
+            StringPart::Interpolation(location, source) => {
+                self.elaborate_interpolation(location, source)
+            }
+        });
+
+        let first_term = core_terms.next().unwrap();
+        core_terms.fold(first_term, |core_term0, core_term1| {
+            let append_term =
+                core::Term::generated(core::TermData::Global("string-append".to_owned()));
+            core::Term::new(
+                location,
+                core::TermData::FunctionElim(
+                    Arc::new(core::Term::new(
+                        location,
+                        core::TermData::FunctionElim(
+                            Arc::new(append_term),
+                            Plicity::Explicit,
+                            Arc::new(core_term0),
+                        ),
+                    )),
+                    Plicity::Explicit,
+                    Arc::new(core_term1),
+                ),
+            )
+        })
+    }
+
+    /// Elaborate a term that was interpolated in a string literal, converting
+    /// it to a string if it is an element of a built-in type.
+    fn elaborate_interpolation(&mut self, location: Location, source: &str) -> core::Term {
+        let term = match location {
+            Location::FileRange(file_id, range) => {
+                Term::from_str_at(file_id, range.start, source, &self.message_tx)
+            }
+            // Generated string literals never contain interpolated terms
+            Location::Generated => return core::Term::new(location, core::TermData::Error),
+        };
+
+        let (core_term, r#type) = self.synth_type_impl(&term);
+        let (core_term, r#type) = self.insert_implicit_elims(location, core_term, r#type);
+        let r#type = self.force_metas(&r#type);
+
+        let conversion = match r#type.force(self.globals) {
+            Value::Error => return core::Term::new(location, core::TermData::Error),
+            forced_type => match forced_type.try_global() {
+                Some(("String", [])) => Some(None),
+                Some((name, [])) => builtins::to_string_name(name).map(Some),
+                Some(_) | None => None,
+            },
+        };
+
+        match conversion {
+            Some(None) => core_term,
+            Some(Some(function_name)) => {
+                let function_term = core::TermData::Global(function_name.to_owned());
+                core::Term::new(
+                    location,
+                    core::TermData::FunctionElim(
+                        Arc::new(core::Term::new(location, function_term)),
+                        Plicity::Explicit,
+                        Arc::new(core_term),
+                    ),
+                )
+            }
+            None => {
+                let found_type = self.read_back_to_surface(&r#type);
+                self.report(SurfaceToCoreMessage::NoStringConversion {
+                    location,
+                    found_type,
+                });
+                core::Term::new(location, core::TermData::Error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::surface;
+
+    /// Elaborate a surface term, returning the elaborated term along with the
+    /// messages that were reported.
+    fn synth_term(globals: &core::Globals, source: &str) -> (core::Term, Vec<Message>) {
+        let (messages_tx, messages_rx) = crossbeam_channel::unbounded();
+        let surface_term = surface::Term::from_str(0, source, &messages_tx);
+        let mut context = Context::new(globals, messages_tx);
+        let (core_term, _) = context.synth_type(&surface_term);
+        let core_term = context.normalize(&core_term);
+        (core_term, messages_rx.try_iter().collect())
+    }
+
+    #[test]
+    fn foreign_function_stuck_on_variable() {
+        let mut globals = core::Globals::default();
+        globals.define_foreign("path-join", |base: String, path: String| {
+            format!("{}/{}", base, path)
+        });
+
+        let source = r#"(fun x => path-join x "b") : String -> String"#;
+        let (core_term, messages) = synth_term(&globals, source);
+
+        assert!(messages.is_empty());
+        match &core_term.data {
+            core::TermData::FunctionTerm(_, _, output_term) => {
+                assert!(matches!(
+                    output_term.data,
+                    core::TermData::FunctionElim(_, _, _)
+                ));
+            }
+            _ => panic!("expected a function term, found {:?}", core_term),
+        }
+    }
+
+    fn literal_conversion_globals() -> core::Globals {
+        let global = |name: &str| {
+            Arc::new(core::Term::generated(core::TermData::Global(
+                name.to_owned(),
+            )))
+        };
+
+        let mut globals = core::Globals::default();
+        globals.define_type("Percent");
+        globals.define_primitive(
+            "percent",
+            Arc::new(core::Term::generated(core::TermData::FunctionType(
+                Plicity::Explicit,
+                None,
+                global("U8"),
+                global("Percent"),
+            ))),
+            core::Primitive::new(1, |_, _| None),
+        );
+        globals.define_literal_conversion("Percent", core::LiteralType::U8, "percent");
+        globals
+    }
+
+    #[test]
+    fn literal_conversion() {
+        let globals = literal_conversion_globals();
+        let (core_term, messages) = synth_term(&globals, "50 : Percent");
+
+        assert!(messages.is_empty());
+        match &core_term.data {
+            core::TermData::FunctionElim(head_term, _, input_term) => {
+                assert!(
+                    matches!(&head_term.data, core::TermData::Global(name) if name == "percent")
+                );
+                assert!(matches!(
+                    input_term.data,
+                    core::TermData::Constant(core::Constant::U8(50)),
+                ));
+            }
+            _ => panic!("expected a function elimination, found {:?}", core_term),
+        }
+    }
+
+    #[test]
+    fn postponed_literal_conversion() {
+        let globals = literal_conversion_globals();
+        let source = "(fun {A : Type} (a : A) => a) 50 : Percent";
+        let (core_term, messages) = synth_term(&globals, source);
+
+        assert!(messages.is_empty());
+        assert!(matches!(
+            &core_term.data,
+            core::TermData::FunctionElim(head_term, _, _)
+                if matches!(&head_term.data, core::TermData::Global(name) if name == "percent")
+        ));
+    }
+
+    #[test]
+    fn literal_conversion_mismatched_literal() {
+        let globals = literal_conversion_globals();
+        let (_, messages) = synth_term(&globals, r#""hello" : Percent"#);
+
+        assert!(matches!(
+            &messages[..],
+            [Message::SurfaceToCore(
+                SurfaceToCoreMessage::NoLiteralConversion { .. }
+            )],
+        ));
+    }
+
+    #[test]
+    fn large_literals_default_to_types_that_can_hold_them() {
+        let globals = core::Globals::default();
+        let defaulted_constant = |source| {
+            let (core_term, messages) = synth_term(&globals, source);
+            assert!(matches!(
+                &messages[..],
+                [Message::SurfaceToCore(
+                    SurfaceToCoreMessage::DefaultedNumberLiteral { .. }
+                )],
+            ));
+            match core_term.data {
+                core::TermData::Constant(constant) => constant,
+                _ => panic!("expected a constant, found {:?}", core_term),
+            }
+        };
+
+        assert!(matches!(
+            defaulted_constant("2147483647"),
+            core::Constant::S32(2147483647),
+        ));
+        assert!(matches!(
+            defaulted_constant("5000000000"),
+            core::Constant::S64(5000000000),
+        ));
+        assert!(matches!(
+            defaulted_constant("10000000000000000000"),
+            core::Constant::Int(_),
+        ));
+    }
 }
@@ -0,0 +1,98 @@
+//! Suggestions for names that were not found, based on [edit distance].
+//!
+//! [edit distance]: https://en.wikipedia.org/wiki/Levenshtein_distance
+
+/// Find the candidate that is most similar to a name, if any are close
+/// enough to be a likely typo. Ties are broken by picking the candidate that
+/// comes first alphabetically, so that suggestions are deterministic.
+pub fn suggest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let max_distance = std::cmp::max(name.chars().count(), 3) / 3;
+
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate.to_owned())
+}
+
+/// The number of single character insertions, deletions, substitutions, or
+/// transpositions of adjacent characters required to turn one string into
+/// another.
+fn edit_distance(source: &str, target: &str) -> usize {
+    let source = source.chars().collect::<Vec<_>>();
+    let target = target.chars().collect::<Vec<_>>();
+
+    let mut previous_distances = Vec::new();
+    let mut distances = (0..=target.len()).collect::<Vec<_>>();
+
+    for source_index in 0..source.len() {
+        let mut next_distances = vec![source_index + 1; target.len() + 1];
+
+        for target_index in 0..target.len() {
+            let cost = match source[source_index] == target[target_index] {
+                true => 0,
+                false => 1,
+            };
+            let mut distance = (distances[target_index] + cost)
+                .min(distances[target_index + 1] + 1)
+                .min(next_distances[target_index] + 1);
+
+            if source_index > 0
+                && target_index > 0
+                && source[source_index] == target[target_index - 1]
+                && source[source_index - 1] == target[target_index]
+            {
+                distance = distance.min(previous_distances[target_index - 1] + 1);
+            }
+
+            next_distances[target_index + 1] = distance;
+        }
+
+        previous_distances = std::mem::replace(&mut distances, next_distances);
+    }
+
+    distances[target.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("größe", "grösse"), 2);
+        assert_eq!(edit_distance("prot", "port"), 1);
+    }
+
+    #[test]
+    fn suggestions() {
+        let candidates = ["string-length", "string-eq", "list-length", "U32", "port"];
+
+        assert_eq!(
+            suggest_name("strng-length", candidates.iter().copied()),
+            Some("string-length".to_owned()),
+        );
+        assert_eq!(
+            suggest_name("u32", candidates.iter().copied()),
+            Some("U32".to_owned()),
+        );
+        assert_eq!(
+            suggest_name("prot", candidates.iter().copied()),
+            Some("port".to_owned()),
+        );
+        assert_eq!(suggest_name("x", candidates.iter().copied()), None);
+        assert_eq!(
+            suggest_name("array-length", candidates.iter().copied()),
+            None
+        );
+    }
+}
@@ -0,0 +1,628 @@
+//! Unification of values, solving metavariables along the way.
+//!
+//! This is an implementation of [pattern unification], where metavariables
+//! can only be solved when they are applied to a spine of distinct local
+//! variables. For example `?0 x y = F x` can be solved with
+//! `?0 = fun x y => F x`, but `?0 x x = F x` and `?0 (F x) = x` cannot.
+//!
+//! See the following for more information:
+//!
+//! - [AndrasKovacs/elaboration-zoo](https://github.com/AndrasKovacs/elaboration-zoo/)
+//!
+//! [pattern unification]: https://www.cse.chalmers.se/~abela/unif-sigma-long.pdf
+
+use std::sync::Arc;
+
+use crate::lang::core::semantics::{
+    self, BranchClosure, Elim, FunctionClosure, Head, RecordClosure, Unfold, Value,
+};
+use crate::lang::core::{Env, EnvSize, Globals, MetaVar, Term, TermData, VarLevel};
+
+/// Unify two values, solving any metavariables that are required to make
+/// them equal. Returns `false` if the values could not be unified.
+pub fn unify(globals: &Globals, size: EnvSize, value0: &Arc<Value>, value1: &Arc<Value>) -> bool {
+    let value0 = semantics::force_metas(globals, value0);
+    let value1 = semantics::force_metas(globals, value1);
+
+    match (value0.as_ref(), value1.as_ref()) {
+        (Value::Stuck(Head::Meta(meta0), spine0), Value::Stuck(Head::Meta(meta1), spine1))
+            if meta0 == meta1 =>
+        {
+            unify_spines(globals, size, spine0, spine1)
+        }
+        // Either metavariable might have fewer variables in scope than the
+        // other, so attempt to solve them in both directions.
+        (Value::Stuck(Head::Meta(meta0), spine0), Value::Stuck(Head::Meta(meta1), spine1)) => {
+            solve(globals, size, meta0, spine0, &value1)
+                || solve(globals, size, meta1, spine1, &value0)
+        }
+        (Value::Stuck(Head::Meta(meta), spine), _) => solve(globals, size, meta, spine, &value1),
+        (_, Value::Stuck(Head::Meta(meta), spine)) => solve(globals, size, meta, spine, &value0),
+
+        (Value::Stuck(head0, spine0), Value::Stuck(head1, spine1)) => {
+            head0 == head1 && unify_spines(globals, size, spine0, spine1)
+        }
+        (Value::Unstuck(head0, _, _), Value::Unstuck(head1, _, _))
+            if head0 == head1 && semantics::is_equal(globals, size, &value0, &value1) =>
+        {
+            true
+        }
+        (Value::Unstuck(_, _, value0), _) => unify(globals, size, value0.force(globals), &value1),
+        (_, Value::Unstuck(_, _, value1)) => unify(globals, size, &value0, value1.force(globals)),
+
+        (Value::TypeType, Value::TypeType) => true,
+
+        (
+            Value::FunctionType(plicity0, _, input_type0, output_closure0),
+            Value::FunctionType(plicity1, _, input_type1, output_closure1),
+        ) => {
+            plicity0 == plicity1
+                && unify(globals, size, input_type0, input_type1)
+                && unify_function_closures(globals, size, output_closure0, output_closure1)
+        }
+        (
+            Value::FunctionTerm(plicity0, _, output_closure0),
+            Value::FunctionTerm(plicity1, _, output_closure1),
+        ) => {
+            plicity0 == plicity1
+                && unify_function_closures(globals, size, output_closure0, output_closure1)
+        }
+        // Eta-conversion for functions
+        (Value::FunctionTerm(plicity, _, output_closure), Value::Stuck(_, _)) => {
+            let var = Arc::new(Value::var(size.next_level(), []));
+            let output0 = output_closure.apply(globals, var.clone());
+            let output1 = semantics::apply_function_elim(globals, value1.clone(), *plicity, var);
+            unify(globals, size.next_size(), &output0, &output1)
+        }
+        (Value::Stuck(_, _), Value::FunctionTerm(plicity, _, output_closure)) => {
+            let var = Arc::new(Value::var(size.next_level(), []));
+            let output0 =
+                semantics::apply_function_elim(globals, value0.clone(), *plicity, var.clone());
+            let output1 = output_closure.apply(globals, var);
+            unify(globals, size.next_size(), &output0, &output1)
+        }
+
+        (Value::RecordType(labels0, closure0), Value::RecordType(labels1, closure1))
+        | (Value::RecordTerm(labels0, closure0), Value::RecordTerm(labels1, closure1)) => {
+            labels0 == labels1 && unify_record_closures(globals, size, closure0, closure1)
+        }
+
+        (Value::VariantType(labels0, types0), Value::VariantType(labels1, types1)) => {
+            labels0 == labels1
+                && Iterator::zip(types0.iter(), types1.iter())
+                    .all(|(type0, type1)| unify(globals, size, type0, type1))
+        }
+        (Value::VariantTerm(label0, value0), Value::VariantTerm(label1, value1)) => {
+            label0 == label1 && unify(globals, size, value0, value1)
+        }
+
+        (Value::ArrayTerm(value_entries0), Value::ArrayTerm(value_entries1))
+        | (Value::ListTerm(value_entries0), Value::ListTerm(value_entries1)) => {
+            value_entries0.len() == value_entries1.len()
+                && Iterator::zip(value_entries0.iter(), value_entries1.iter()).all(
+                    |(value_entry0, value_entry1)| unify(globals, size, value_entry0, value_entry1),
+                )
+        }
+
+        (Value::Constant(constant0), Value::Constant(constant1)) => constant0 == constant1,
+
+        // Errors always unify, in order to avoid cascading errors.
+        (Value::Error, _) | (_, Value::Error) => true,
+        // Anything else does not unify!
+        (_, _) => false,
+    }
+}
+
+/// Unify the spines of two stuck values.
+fn unify_spines(globals: &Globals, size: EnvSize, spine0: &[Elim], spine1: &[Elim]) -> bool {
+    spine0.len() == spine1.len()
+        && Iterator::zip(spine0.iter(), spine1.iter()).all(|elims| match elims {
+            (Elim::Function(plicity0, input0), Elim::Function(plicity1, input1)) => {
+                plicity0 == plicity1
+                    && unify(globals, size, input0.force(globals), input1.force(globals))
+            }
+            (Elim::Record(label0), Elim::Record(label1)) => label0 == label1,
+            (Elim::Variant(closure0), Elim::Variant(closure1)) => {
+                unify_branch_closures(globals, size, closure0, closure1)
+            }
+            (Elim::Data(name0, motive0, methods0), Elim::Data(name1, motive1, methods1)) => {
+                name0 == name1
+                    && unify(globals, size, motive0, motive1)
+                    && Iterator::zip(methods0.iter(), methods1.iter())
+                        .all(|(method0, method1)| unify(globals, size, method0, method1))
+            }
+            (_, _) => false,
+        })
+}
+
+/// Unify the outputs of two function closures.
+fn unify_function_closures(
+    globals: &Globals,
+    size: EnvSize,
+    closure0: &FunctionClosure,
+    closure1: &FunctionClosure,
+) -> bool {
+    let var = Arc::new(Value::var(size.next_level(), []));
+    let output0 = closure0.apply(globals, var.clone());
+    let output1 = closure1.apply(globals, var);
+    unify(globals, size.next_size(), &output0, &output1)
+}
+
+/// Unify the entries of two record closures.
+fn unify_record_closures(
+    globals: &Globals,
+    mut size: EnvSize,
+    closure0: &RecordClosure,
+    closure1: &RecordClosure,
+) -> bool {
+    if closure0.entry_count() != closure1.entry_count() {
+        return false;
+    }
+
+    let mut vars = Vec::with_capacity(closure0.entry_count());
+    for index in 0..closure0.entry_count() {
+        let entry0 = closure0.apply_entry(globals, index, &vars);
+        let entry1 = closure1.apply_entry(globals, index, &vars);
+        if !unify(globals, size, &entry0, &entry1) {
+            return false;
+        }
+
+        vars.push(Arc::new(Value::var(size.next_level(), [])));
+        size = size.next_size();
+    }
+
+    true
+}
+
+/// Unify the branches of two branch closures.
+fn unify_branch_closures(
+    globals: &Globals,
+    size: EnvSize,
+    closure0: &BranchClosure,
+    closure1: &BranchClosure,
+) -> bool {
+    closure0.labels() == closure1.labels()
+        && (0..closure0.labels().len()).all(|index| {
+            let var = Arc::new(Value::var(size.next_level(), []));
+            let output0 = closure0.apply_branch(globals, index, var.clone());
+            let output1 = closure1.apply_branch(globals, index, var);
+            unify(globals, size.next_size(), &output0, &output1)
+        })
+}
+
+/// Attempt to solve the metavariable in the problem `?meta spine = value`.
+fn solve(
+    globals: &Globals,
+    size: EnvSize,
+    meta: &Arc<MetaVar>,
+    spine: &[Elim],
+    value: &Arc<Value>,
+) -> bool {
+    let mut renaming = Renaming {
+        meta,
+        dom: EnvSize::new(0),
+        levels: vec![None; size.to_usize()],
+    };
+    let mut plicities = Vec::with_capacity(spine.len());
+
+    // The spine must be made up of distinct local variables
+    for elim in spine {
+        let (plicity, input) = match elim {
+            Elim::Function(plicity, input) => (*plicity, input.force(globals)),
+            Elim::Record(_) | Elim::Variant(_) | Elim::Data(_, _, _) => return false,
+        };
+        match semantics::force_metas(globals, input).as_ref() {
+            Value::Stuck(Head::Var(level), input_spine) if input_spine.is_empty() => {
+                match renaming.levels.get_mut(level.to_usize()) {
+                    Some(entry @ None) => *entry = Some(renaming.dom.next_level()),
+                    Some(Some(_)) | None => return false,
+                }
+            }
+            _ => return false,
+        }
+        renaming.dom = renaming.dom.next_size();
+        plicities.push(plicity);
+    }
+
+    let solution = match renaming.rename(globals, value) {
+        Some(term) => term,
+        None => return false,
+    };
+    let solution = (plicities.iter().rev()).fold(solution, |output_term, plicity| {
+        Term::generated(TermData::FunctionTerm(
+            *plicity,
+            "x".to_owned(),
+            Arc::new(output_term),
+        ))
+    });
+
+    let solution = semantics::eval(globals, &mut Env::new(), &solution);
+    meta.solve(solution)
+}
+
+/// A partial renaming from the variables in the scope of a unification
+/// problem to the inputs of a metavariable solution.
+struct Renaming<'a> {
+    /// The metavariable being solved, used for the occurs check.
+    meta: &'a Arc<MetaVar>,
+    /// The size of the environment of the solution.
+    dom: EnvSize,
+    /// The level in the solution of each variable in the problem, if it is
+    /// in scope.
+    levels: Vec<Option<VarLevel>>,
+}
+
+impl<'a> Renaming<'a> {
+    fn cod(&self) -> EnvSize {
+        EnvSize::new(self.levels.len() as u32)
+    }
+
+    /// Push a variable that is in scope in both the problem and the solution.
+    fn push_var(&mut self) -> Arc<Value> {
+        let var = Arc::new(Value::var(self.cod().next_level(), []));
+        self.levels.push(Some(self.dom.next_level()));
+        self.dom = self.dom.next_size();
+        var
+    }
+
+    /// Truncate the renaming to the given sizes.
+    fn truncate(&mut self, dom: EnvSize, cod: EnvSize) {
+        self.dom = dom;
+        self.levels.truncate(cod.to_usize());
+    }
+
+    /// Read back a value into a term in the scope of the solution, returning
+    /// `None` if the value refers to variables that are not in scope, or to
+    /// the metavariable being solved.
+    fn rename(&mut self, globals: &Globals, value: &Arc<Value>) -> Option<Term> {
+        let value = semantics::force_metas(globals, value);
+
+        match value.as_ref() {
+            Value::Stuck(head, spine) => self.rename_stuck(globals, head, spine),
+            // Avoid unfolding definitions if possible
+            Value::Unstuck(head, spine, value) => (self.rename_stuck(globals, head, spine))
+                .or_else(|| self.rename(globals, value.force(globals))),
+
+            Value::TypeType => Some(Term::generated(TermData::TypeType)),
+
+            Value::FunctionType(plicity, input_name_hint, input_type, output_closure) => {
+                let (dom, cod) = (self.dom, self.cod());
+                let input_type = self.rename(globals, input_type)?;
+                let var = self.push_var();
+                let output_type = self.rename(globals, &output_closure.apply(globals, var));
+                self.truncate(dom, cod);
+
+                Some(Term::generated(TermData::FunctionType(
+                    *plicity,
+                    input_name_hint.clone(),
+                    Arc::new(input_type),
+                    Arc::new(output_type?),
+                )))
+            }
+            Value::FunctionTerm(plicity, input_name_hint, output_closure) => {
+                let (dom, cod) = (self.dom, self.cod());
+                let var = self.push_var();
+                let output_term = self.rename(globals, &output_closure.apply(globals, var));
+                self.truncate(dom, cod);
+
+                Some(Term::generated(TermData::FunctionTerm(
+                    *plicity,
+                    input_name_hint.clone(),
+                    Arc::new(output_term?),
+                )))
+            }
+
+            Value::RecordType(labels, closure) => {
+                let entries = self.rename_record_closure(globals, closure)?;
+                let defaults = self.rename_record_defaults(globals, closure)?;
+                // Documentation is not preserved in values
+                let docs = vec![String::new(); labels.len()];
+                Some(Term::generated(TermData::RecordType(
+                    labels.clone(),
+                    docs.into(),
+                    entries.into(),
+                    defaults.into(),
+                )))
+            }
+            Value::RecordTerm(labels, closure) => {
+                let entries = self.rename_record_closure(globals, closure)?;
+                Some(Term::generated(TermData::RecordTerm(
+                    labels.clone(),
+                    entries.into(),
+                )))
+            }
+
+            Value::VariantType(labels, value_types) => {
+                let term_types = (value_types.iter())
+                    .map(|value_type| Some(Arc::new(self.rename(globals, value_type)?)))
+                    .collect::<Option<_>>()?;
+                Some(Term::generated(TermData::VariantType(
+                    labels.clone(),
+                    term_types,
+                )))
+            }
+            Value::VariantTerm(label, value) => Some(Term::generated(TermData::VariantTerm(
+                label.clone(),
+                Arc::new(self.rename(globals, value)?),
+            ))),
+
+            Value::ArrayTerm(value_entries) => {
+                let term_entries = (value_entries.iter())
+                    .map(|value_entry| Some(Arc::new(self.rename(globals, value_entry)?)))
+                    .collect::<Option<_>>()?;
+                Some(Term::generated(TermData::ArrayTerm(term_entries)))
+            }
+            Value::ListTerm(value_entries) => {
+                let term_entries = (value_entries.iter())
+                    .map(|value_entry| Some(Arc::new(self.rename(globals, value_entry)?)))
+                    .collect::<Option<_>>()?;
+                Some(Term::generated(TermData::ListTerm(term_entries)))
+            }
+
+            Value::Constant(constant) => Some(Term::generated(TermData::from(constant.clone()))),
+
+            Value::Error => Some(Term::generated(TermData::Error)),
+        }
+    }
+
+    fn rename_stuck(&mut self, globals: &Globals, head: &Head, spine: &[Elim]) -> Option<Term> {
+        let head = match head {
+            Head::Global(name) => Term::generated(TermData::Global(name.clone())),
+            Head::Var(level) => {
+                let level = (*self.levels.get(level.to_usize())?)?;
+                Term::generated(TermData::Var(self.dom.level_to_index(level)?))
+            }
+            Head::Meta(meta) if meta == self.meta => return None,
+            Head::Meta(meta) => Term::generated(TermData::Meta(meta.clone())),
+        };
+
+        spine.iter().try_fold(head, |head, elim| match elim {
+            Elim::Function(plicity, input) => {
+                let input = self.rename(globals, input.force(globals))?;
+                Some(Term::generated(TermData::FunctionElim(
+                    Arc::new(head),
+                    *plicity,
+                    Arc::new(input),
+                )))
+            }
+            Elim::Record(label) => Some(Term::generated(TermData::RecordElim(
+                Arc::new(head),
+                label.clone(),
+            ))),
+            Elim::Variant(closure) => {
+                let (dom, cod) = (self.dom, self.cod());
+                let mut branches = Vec::with_capacity(closure.labels().len());
+                for index in 0..closure.labels().len() {
+                    let var = self.push_var();
+                    let branch = self.rename(globals, &closure.apply_branch(globals, index, var));
+                    self.truncate(dom, cod);
+                    branches.push(Arc::new(branch?));
+                }
+
+                Some(Term::generated(TermData::VariantElim(
+                    Arc::new(head),
+                    closure.labels().clone(),
+                    branches.into(),
+                )))
+            }
+            Elim::Data(name, motive, methods) => {
+                let motive = self.rename(globals, motive)?;
+                let methods = (methods.iter())
+                    .map(|method| Some(Arc::new(self.rename(globals, method)?)))
+                    .collect::<Option<Vec<_>>>()?;
+
+                Some(Term::generated(TermData::DataElim(
+                    Arc::new(head),
+                    name.clone(),
+                    Arc::new(motive),
+                    methods.into(),
+                )))
+            }
+        })
+    }
+
+    fn rename_record_closure(
+        &mut self,
+        globals: &Globals,
+        closure: &RecordClosure,
+    ) -> Option<Vec<Arc<Term>>> {
+        let (dom, cod) = (self.dom, self.cod());
+        let mut vars = Vec::with_capacity(closure.entry_count());
+        let mut entries = Vec::with_capacity(closure.entry_count());
+
+        for index in 0..closure.entry_count() {
+            let entry = closure.apply_entry(globals, index, &vars);
+            match self.rename(globals, &entry) {
+                Some(entry) => entries.push(Arc::new(entry)),
+                None => break,
+            }
+            vars.push(self.push_var());
+        }
+        self.truncate(dom, cod);
+
+        match entries.len() == closure.entry_count() {
+            true => Some(entries),
+            false => None,
+        }
+    }
+
+    fn rename_record_defaults(
+        &mut self,
+        globals: &Globals,
+        closure: &RecordClosure,
+    ) -> Option<Vec<Option<Arc<Term>>>> {
+        let (dom, cod) = (self.dom, self.cod());
+        let mut vars = Vec::with_capacity(closure.entry_count());
+        let mut defaults = Vec::with_capacity(closure.entry_count());
+
+        for index in 0..closure.entry_count() {
+            match closure.apply_default(globals, index, &vars) {
+                None => defaults.push(None),
+                Some(default) => match self.rename(globals, &default) {
+                    Some(default) => defaults.push(Some(Arc::new(default))),
+                    None => break,
+                },
+            }
+            vars.push(self.push_var());
+        }
+        self.truncate(dom, cod);
+
+        match defaults.len() == closure.entry_count() {
+            true => Some(defaults),
+            false => None,
+        }
+    }
+}
+
+/// Replace the metavariables in a term with their solutions, beta-reducing
+/// any applications of the solutions that result.
+///
+/// The `values` should contain a variable for each entry in scope.
+pub fn zonk(globals: &Globals, values: &mut Env<Arc<Value>>, term: &Term) -> Term {
+    let zonk_with_var = |values: &mut Env<Arc<Value>>, term: &Term| {
+        values.push(Arc::new(Value::var(values.size().next_level(), [])));
+        let term = zonk(globals, values, term);
+        values.pop();
+        term
+    };
+
+    let term_data = match &term.data {
+        _ if is_meta_spine(term) => {
+            let value = semantics::eval(globals, values, term);
+            semantics::read_back(globals, values.size(), Unfold::Never, &value).data
+        }
+
+        TermData::Global(_) | TermData::Var(_) | TermData::TypeType | TermData::Error => {
+            return term.clone();
+        }
+        TermData::Constant(_) => return term.clone(),
+
+        TermData::Ann(term, r#type) => TermData::Ann(
+            Arc::new(zonk(globals, values, term)),
+            Arc::new(zonk(globals, values, r#type)),
+        ),
+
+        TermData::FunctionType(plicity, input_name_hint, input_type, output_type) => {
+            TermData::FunctionType(
+                *plicity,
+                input_name_hint.clone(),
+                Arc::new(zonk(globals, values, input_type)),
+                Arc::new(zonk_with_var(values, output_type)),
+            )
+        }
+        TermData::FunctionTerm(plicity, input_name_hint, output_term) => TermData::FunctionTerm(
+            *plicity,
+            input_name_hint.clone(),
+            Arc::new(zonk_with_var(values, output_term)),
+        ),
+        TermData::FunctionElim(head_term, plicity, input_term) => TermData::FunctionElim(
+            Arc::new(zonk(globals, values, head_term)),
+            *plicity,
+            Arc::new(zonk(globals, values, input_term)),
+        ),
+
+        TermData::RecordType(labels, docs, types, defaults) => TermData::RecordType(
+            labels.clone(),
+            docs.clone(),
+            zonk_entries(globals, values, types).into(),
+            zonk_defaults(globals, values, defaults).into(),
+        ),
+        TermData::RecordTerm(labels, terms) => {
+            TermData::RecordTerm(labels.clone(), zonk_entries(globals, values, terms).into())
+        }
+        TermData::RecordElim(head_term, label) => {
+            TermData::RecordElim(Arc::new(zonk(globals, values, head_term)), label.clone())
+        }
+
+        TermData::VariantType(labels, types) => TermData::VariantType(
+            labels.clone(),
+            (types.iter())
+                .map(|r#type| Arc::new(zonk(globals, values, r#type)))
+                .collect(),
+        ),
+        TermData::VariantTerm(label, term) => {
+            TermData::VariantTerm(label.clone(), Arc::new(zonk(globals, values, term)))
+        }
+        TermData::VariantElim(head_term, labels, branches) => TermData::VariantElim(
+            Arc::new(zonk(globals, values, head_term)),
+            labels.clone(),
+            (branches.iter())
+                .map(|branch| Arc::new(zonk_with_var(values, branch)))
+                .collect(),
+        ),
+        TermData::DataElim(head_term, name, motive, methods) => TermData::DataElim(
+            Arc::new(zonk(globals, values, head_term)),
+            name.clone(),
+            Arc::new(zonk(globals, values, motive)),
+            (methods.iter())
+                .map(|method| Arc::new(zonk(globals, values, method)))
+                .collect(),
+        ),
+
+        TermData::ArrayTerm(entry_terms) => TermData::ArrayTerm(
+            (entry_terms.iter())
+                .map(|entry_term| Arc::new(zonk(globals, values, entry_term)))
+                .collect(),
+        ),
+        TermData::ListTerm(entry_terms) => TermData::ListTerm(
+            (entry_terms.iter())
+                .map(|entry_term| Arc::new(zonk(globals, values, entry_term)))
+                .collect(),
+        ),
+
+        TermData::Meta(_) => unreachable!("metavariables are handled as spines"),
+    };
+
+    Term::new(term.location, term_data)
+}
+
+/// Zonk the defaults of a record type, each of which is in the scope of the
+/// preceding entries.
+fn zonk_defaults(
+    globals: &Globals,
+    values: &mut Env<Arc<Value>>,
+    defaults: &[Option<Arc<Term>>],
+) -> Vec<Option<Arc<Term>>> {
+    let initial_size = values.size();
+    let defaults = (defaults.iter())
+        .map(|default| {
+            let default =
+                (default.as_ref()).map(|default| Arc::new(zonk(globals, values, default)));
+            values.push(Arc::new(Value::var(values.size().next_level(), [])));
+            default
+        })
+        .collect();
+    values.truncate(initial_size);
+    defaults
+}
+
+/// Zonk the entries of a record, each of which binds a variable in the
+/// subsequent entries.
+fn zonk_entries(
+    globals: &Globals,
+    values: &mut Env<Arc<Value>>,
+    entries: &[Arc<Term>],
+) -> Vec<Arc<Term>> {
+    let initial_size = values.size();
+    let entries = (entries.iter())
+        .map(|entry| {
+            let entry = Arc::new(zonk(globals, values, entry));
+            values.push(Arc::new(Value::var(values.size().next_level(), [])));
+            entry
+        })
+        .collect();
+    values.truncate(initial_size);
+    entries
+}
+
+/// Check if a term is a metavariable, or an elimination of a metavariable.
+fn is_meta_spine(term: &Term) -> bool {
+    match &term.data {
+        TermData::Meta(_) => true,
+        TermData::FunctionElim(head_term, _, _)
+        | TermData::RecordElim(head_term, _)
+        | TermData::VariantElim(head_term, _, _)
+        | TermData::DataElim(head_term, _, _, _) => is_meta_spine(head_term),
+        _ => false,
+    }
+}
@@ -4,7 +4,8 @@
 
 use pretty::{DocAllocator, DocBuilder};
 
-use crate::lang::surface::{Term, TermData};
+use crate::lang::surface::{DocLines, Term, TermData, TermEntry};
+use crate::lang::Plicity;
 
 /// The precedence of a term.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -12,6 +13,8 @@ pub enum Prec {
     Term = 0,
     Expr,
     Arrow,
+    Operator,
+    Merge,
     App,
     Atomic,
 }
@@ -53,22 +56,28 @@ where
             (alloc.nil())
                 .append("Fun")
                 .append(alloc.space())
-                .append(alloc.intersperse(
-                    input_type_groups.iter().map(|(input_names, input_type)| {
-                        (alloc.nil())
-                            .append("(")
-                            .append(alloc.intersperse(
-                                input_names.iter().map(|input_name| &input_name.data),
-                                alloc.space(),
-                            ))
-                            .append(alloc.space())
-                            .append(":")
-                            .append(alloc.space())
-                            .append(from_term_prec(alloc, input_type, Prec::Term))
-                            .append(")")
-                    }),
-                    alloc.space(),
-                ))
+                .append(
+                    alloc.intersperse(
+                        input_type_groups
+                            .iter()
+                            .map(|(docs, plicity, input_names, input_type)| {
+                                let (open, close) = delimiters(*plicity);
+                                (alloc.nil())
+                                    .append(from_docs(alloc, docs))
+                                    .append(open)
+                                    .append(alloc.intersperse(
+                                        input_names.iter().map(|input_name| &input_name.data),
+                                        alloc.space(),
+                                    ))
+                                    .append(alloc.space())
+                                    .append(":")
+                                    .append(alloc.space())
+                                    .append(from_term_prec(alloc, input_type, Prec::Term))
+                                    .append(close)
+                            }),
+                        alloc.space(),
+                    ),
+                )
                 .append(alloc.space())
                 .append("->")
                 .group()
@@ -90,16 +99,51 @@ where
                 .append(alloc.space())
                 .append(from_term_prec(alloc, output_type, Prec::Arrow)),
         ),
+        TermData::Operators(first_term, rest_terms) => paren(
+            alloc,
+            prec > Prec::Operator,
+            (alloc.nil())
+                .append(from_term_prec(alloc, first_term, Prec::Merge))
+                .append(alloc.concat(rest_terms.iter().map(|(operator, term)| {
+                    (alloc.nil())
+                        .append(alloc.space())
+                        .append(&operator.data)
+                        .append(alloc.space())
+                        .append(from_term_prec(alloc, term, Prec::Merge))
+                }))),
+        ),
         TermData::FunctionTerm(input_names, output_term) => paren(
             alloc,
             prec > Prec::Expr,
             (alloc.nil())
                 .append("fun")
                 .append(alloc.space())
-                .append(alloc.intersperse(
-                    input_names.iter().map(|input_name| &input_name.data),
-                    alloc.space(),
-                ))
+                .append(
+                    alloc.intersperse(
+                        input_names
+                            .iter()
+                            .map(|(docs, plicity, input_name, input_type)| {
+                                from_docs(alloc, docs).append(match (plicity, input_type) {
+                                    (Plicity::Explicit, None) => alloc.text(&input_name.data),
+                                    (Plicity::Implicit, None) => {
+                                        alloc.text("{").append(&input_name.data).append("}")
+                                    }
+                                    (_, Some(input_type)) => {
+                                        let (open, close) = delimiters(*plicity);
+                                        (alloc.nil())
+                                            .append(open)
+                                            .append(&input_name.data)
+                                            .append(alloc.space())
+                                            .append(":")
+                                            .append(alloc.space())
+                                            .append(from_term_prec(alloc, input_type, Prec::Term))
+                                            .append(close)
+                                    }
+                                })
+                            }),
+                        alloc.space(),
+                    ),
+                )
                 .append(alloc.space())
                 .append("=>")
                 .group()
@@ -116,11 +160,19 @@ where
             prec > Prec::App,
             from_term_prec(alloc, head_term, Prec::App).append(
                 (alloc.nil())
-                    .append(alloc.concat(input_terms.iter().map(|input_term| {
-                        alloc
-                            .space()
-                            .append(from_term_prec(alloc, input_term, Prec::Arrow))
-                    })))
+                    .append(
+                        alloc.concat(input_terms.iter().map(|(plicity, input_term)| {
+                            alloc.space().append(match plicity {
+                                Plicity::Explicit => {
+                                    from_term_prec(alloc, input_term, Prec::Atomic)
+                                }
+                                Plicity::Implicit => (alloc.nil())
+                                    .append("{")
+                                    .append(from_term_prec(alloc, input_term, Prec::Term))
+                                    .append("}"),
+                            })
+                        })),
+                    )
                     .group()
                     .nest(4),
             ),
@@ -131,10 +183,11 @@ where
             .append(alloc.space())
             .append("{")
             .group()
-            .append(
-                alloc.concat(type_entries.iter().map(|(label, name, entry_type)| {
+            .append(alloc.concat(type_entries.iter().map(
+                |(docs, label, name, entry_type, default_term)| {
                     (alloc.nil())
                         .append(alloc.hardline())
+                        .append(from_docs(alloc, docs))
                         .append(match name {
                             None => alloc.text(&label.data).append(alloc.space()),
                             Some(name) => alloc
@@ -150,39 +203,113 @@ where
                         .append(
                             (alloc.space())
                                 .append(from_term_prec(alloc, entry_type, Prec::Term))
+                                .append(match default_term {
+                                    None => alloc.nil(),
+                                    Some(default_term) => (alloc.space())
+                                        .append("=")
+                                        .append(alloc.space())
+                                        .append(from_term_prec(alloc, default_term, Prec::Term)),
+                                })
                                 .append(",")
                                 .group()
                                 .nest(4),
                         )
                         .nest(4)
                         .group()
-                })),
-            )
+                },
+            )))
             .append("}"),
         TermData::RecordTerm(term_entries) => (alloc.nil())
             .append("record")
             .append(alloc.space())
+            .append(from_term_entries(alloc, term_entries)),
+        TermData::RecordUpdate(head_term, term_entries) => paren(
+            alloc,
+            prec > Prec::Merge,
+            (alloc.nil())
+                .append(from_term_prec(alloc, head_term, Prec::Merge))
+                .append(alloc.space())
+                .append("with")
+                .append(alloc.space())
+                .append(from_term_entries(alloc, term_entries)),
+        ),
+        TermData::RecordMerge(left_term, right_term) => paren(
+            alloc,
+            prec > Prec::Merge,
+            (alloc.nil())
+                .append(from_term_prec(alloc, left_term, Prec::Merge))
+                .append(alloc.space())
+                .append("//")
+                .append(alloc.space())
+                .append(from_term_prec(alloc, right_term, Prec::App)),
+        ),
+        TermData::RecordElim(head_term, label) => (alloc.nil())
+            .append(from_term_prec(alloc, head_term, Prec::Atomic))
+            .append(".")
+            .append(&label.data),
+
+        TermData::VariantType(variant_entries) => (alloc.nil())
+            .append("Variant")
+            .append(alloc.space())
+            .append("{")
+            .group()
+            .append(
+                alloc.concat(variant_entries.iter().map(|(label, payload_type)| {
+                    (alloc.nil())
+                        .append(alloc.hardline())
+                        .append(&label.data)
+                        .append(match payload_type {
+                            None => alloc.nil(),
+                            Some(payload_type) => (alloc.space()).append(":").group().append(
+                                (alloc.space())
+                                    .append(from_term_prec(alloc, payload_type, Prec::Term))
+                                    .group()
+                                    .nest(4),
+                            ),
+                        })
+                        .append(",")
+                        .nest(4)
+                        .group()
+                })),
+            )
+            .append("}"),
+        TermData::VariantTerm(label, payload_term) => paren(
+            alloc,
+            prec > Prec::App,
+            (alloc.nil())
+                .append("variant")
+                .append(alloc.space())
+                .append(&label.data)
+                .append(match payload_term {
+                    None => alloc.nil(),
+                    Some(payload_term) => (alloc.space())
+                        .append(from_term_prec(alloc, payload_term, Prec::Atomic))
+                        .group()
+                        .nest(4),
+                }),
+        ),
+        TermData::Match(head_term, branches) => (alloc.nil())
+            .append("match")
+            .append(alloc.space())
+            .append(from_term_prec(alloc, head_term, Prec::Atomic))
+            .append(alloc.space())
             .append("{")
             .group()
             .append(
-                alloc.concat(term_entries.iter().map(|(label, name, entry_term)| {
+                alloc.concat(branches.iter().map(|(label, name, branch_term)| {
                     (alloc.nil())
                         .append(alloc.hardline())
+                        .append(&label.data)
+                        .append(alloc.space())
                         .append(match name {
-                            None => alloc.text(&label.data).append(alloc.space()),
-                            Some(name) => alloc
-                                .text(&label.data)
-                                .append(alloc.space())
-                                .append("as")
-                                .append(alloc.space())
-                                .append(&name.data)
-                                .append(alloc.space()),
+                            None => alloc.nil(),
+                            Some(name) => alloc.text(&name.data).append(alloc.space()),
                         })
-                        .append("=")
+                        .append("=>")
                         .group()
                         .append(
                             (alloc.space())
-                                .append(from_term_prec(alloc, entry_term, Prec::Term))
+                                .append(from_term_prec(alloc, branch_term, Prec::Term))
                                 .append(",")
                                 .group()
                                 .nest(4),
@@ -192,10 +319,6 @@ where
                 })),
             )
             .append("}"),
-        TermData::RecordElim(head_term, label) => (alloc.nil())
-            .append(from_term_prec(alloc, head_term, Prec::Atomic))
-            .append(".")
-            .append(&label.data),
 
         TermData::SequenceTerm(term_entries) => (alloc.nil())
             .append("[")
@@ -210,14 +333,33 @@ where
             )
             .append("]"),
 
-        TermData::CharTerm(text) | TermData::StringTerm(text) | TermData::NumberTerm(text) => {
-            alloc.text(text)
-        }
+        // Multi-line literals are split on hard line breaks, so that they
+        // remain valid when indented.
+        TermData::StringTerm(text) if text.starts_with(r#"""""#) => alloc.intersperse(
+            text.split('\n').map(|line| alloc.text(line)),
+            alloc.hardline(),
+        ),
+        TermData::CharTerm(text)
+        | TermData::StringTerm(text)
+        | TermData::BytesTerm(text)
+        | TermData::NumberTerm(text) => alloc.text(text),
+
+        TermData::Hole(name) => (alloc.nil())
+            .append("?")
+            .append(name.as_ref().map_or(alloc.nil(), |name| alloc.text(name))),
 
         TermData::Error => alloc.text("!"),
     }
 }
 
+/// The delimiters used for groups of function inputs.
+fn delimiters(plicity: Plicity) -> (&'static str, &'static str) {
+    match plicity {
+        Plicity::Explicit => ("(", ")"),
+        Plicity::Implicit => ("{", "}"),
+    }
+}
+
 fn paren<'a, D>(alloc: &'a D, b: bool, doc: DocBuilder<'a, D>) -> DocBuilder<'a, D>
 where
     D: DocAllocator<'a>,
@@ -229,3 +371,67 @@ where
         doc
     }
 }
+
+fn from_term_entries<'a, D>(alloc: &'a D, term_entries: &'a [TermEntry]) -> DocBuilder<'a, D>
+where
+    D: DocAllocator<'a>,
+    D::Doc: Clone,
+{
+    (alloc.nil())
+        .append("{")
+        .group()
+        .append(
+            alloc.concat(
+                term_entries
+                    .iter()
+                    .map(|(docs, label, name, entry_type, entry_term)| {
+                        (alloc.nil())
+                            .append(alloc.hardline())
+                            .append(from_docs(alloc, docs))
+                            .append(match name {
+                                None => alloc.text(&label.data).append(alloc.space()),
+                                Some(name) => alloc
+                                    .text(&label.data)
+                                    .append(alloc.space())
+                                    .append("as")
+                                    .append(alloc.space())
+                                    .append(&name.data)
+                                    .append(alloc.space()),
+                            })
+                            .append(match entry_type {
+                                None => alloc.nil(),
+                                Some(entry_type) => (alloc.nil())
+                                    .append(":")
+                                    .append(alloc.space())
+                                    .append(from_term_prec(alloc, entry_type, Prec::Term))
+                                    .append(alloc.space()),
+                            })
+                            .append("=")
+                            .group()
+                            .append(
+                                (alloc.space())
+                                    .append(from_term_prec(alloc, entry_term, Prec::Term))
+                                    .append(",")
+                                    .group()
+                                    .nest(4),
+                            )
+                            .nest(4)
+                            .group()
+                    }),
+            ),
+        )
+        .append("}")
+}
+
+fn from_docs<'a, D>(alloc: &'a D, docs: &'a DocLines) -> DocBuilder<'a, D>
+where
+    D: DocAllocator<'a>,
+    D::Doc: Clone,
+{
+    alloc.concat(docs.iter().map(|line| {
+        (alloc.nil())
+            .append("|||")
+            .append(alloc.text(line))
+            .append(alloc.hardline())
+    }))
+}
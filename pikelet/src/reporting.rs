@@ -175,7 +175,6 @@ pub enum LiteralParseMessage {
     ExpectedDigitOrSeparator(Location, literal::Base),
     ExpectedDigitSeparatorOrExp(Location, literal::Base),
     ExpectedDigitSeparatorFracOrExp(Location, literal::Base),
-    FloatLiteralExponentNotSupported(Location),
     UnsupportedFloatLiteralBase(Location, literal::Base),
     LiteralOutOfRange(Location),
     OverlongCharLiteral(Location),
@@ -188,6 +187,10 @@ pub enum LiteralParseMessage {
     OversizedAsciiEscapeCode(Location),
     InvalidAsciiEscape(Location),
     UnknownEscapeSequence(Location),
+    UnterminatedInterpolation(Location),
+    ExpectedLineBreakAfterMultilineQuote(Location),
+    ExpectedHexDigit(Location),
+    IncompleteHexByte(Location),
     InvalidToken(Location),
     ExpectedEndOfLiteral(Location),
     UnexpectedEndOfLiteral(Location),
@@ -228,9 +231,6 @@ impl LiteralParseMessage {
                     ))
                     .with_labels(option_to_vec(primary(location)))
             }
-            LiteralParseMessage::FloatLiteralExponentNotSupported(location) => Diagnostic::error()
-                .with_message("exponents are not yet supported for float literals")
-                .with_labels(option_to_vec(primary(location))),
             LiteralParseMessage::UnsupportedFloatLiteralBase(location, base) => Diagnostic::error()
                 .with_message(format!(
                     "base {} float literals are not yet supported",
@@ -238,7 +238,7 @@ impl LiteralParseMessage {
                 ))
                 .with_labels(option_to_vec(primary(location)))
                 .with_notes(vec![
-                    "only base 10 float literals are currently supported".to_owned()
+                    "only base 10 and base 16 float literals are currently supported".to_owned(),
                 ]),
             LiteralParseMessage::LiteralOutOfRange(location) => Diagnostic::error()
                 .with_message("literal out of range")
@@ -287,6 +287,30 @@ impl LiteralParseMessage {
             LiteralParseMessage::UnknownEscapeSequence(location) => Diagnostic::error()
                 .with_message("unknown escape sequence")
                 .with_labels(option_to_vec(primary(location))),
+            LiteralParseMessage::UnterminatedInterpolation(location) => Diagnostic::error()
+                .with_message("unterminated string interpolation")
+                .with_labels(option_to_vec(primary(location)))
+                .with_notes(vec!["interpolated terms must end with a `}`".to_owned()]),
+            LiteralParseMessage::ExpectedLineBreakAfterMultilineQuote(location) => {
+                Diagnostic::error()
+                    .with_message("expected a line break after the start of a multi-line string")
+                    .with_labels(option_to_vec(primary(location)))
+                    .with_notes(vec![
+                        "the contents of multi-line strings must start on a new line".to_owned(),
+                    ])
+            }
+            LiteralParseMessage::ExpectedHexDigit(location) => Diagnostic::error()
+                .with_message("expected a hex digit")
+                .with_labels(option_to_vec(primary(location)))
+                .with_notes(vec![
+                    "bytes may be separated by whitespace or underscores".to_owned()
+                ]),
+            LiteralParseMessage::IncompleteHexByte(location) => Diagnostic::error()
+                .with_message("incomplete byte in hexadecimal literal")
+                .with_labels(option_to_vec(primary(location)))
+                .with_notes(vec![
+                    "each byte must contain exactly two hex digits".to_owned()
+                ]),
             LiteralParseMessage::InvalidToken(location) => Diagnostic::error()
                 .with_message("invalid token")
                 .with_labels(option_to_vec(primary(location))),
@@ -302,19 +326,21 @@ impl LiteralParseMessage {
 
 #[derive(Clone, Debug)]
 pub enum AmbiguousTerm {
-    NumberLiteral,
     Sequence,
     FunctionTerm,
     RecordTerm,
+    VariantTerm,
+    MatchTerm,
 }
 
 impl AmbiguousTerm {
     fn description(&self) -> &'static str {
         match self {
-            AmbiguousTerm::NumberLiteral => "numeric literal",
             AmbiguousTerm::Sequence => "sequence",
             AmbiguousTerm::FunctionTerm => "function term",
             AmbiguousTerm::RecordTerm => "record term",
+            AmbiguousTerm::VariantTerm => "variant term",
+            AmbiguousTerm::MatchTerm => "match term",
         }
     }
 }
@@ -345,10 +371,31 @@ pub enum CoreTypingMessage {
         expected_label: String,
         head_type: core::Term,
     },
+    InvalidVariantType {
+        duplicate_labels: Vec<String>,
+    },
+    InvalidVariantTypeLabelCount,
+    UnexpectedVariantTerm {
+        label: String,
+        expected_type: core::Term,
+    },
+    UnexpectedMatchLabels {
+        found_labels: Arc<[String]>,
+        expected_labels: Arc<[String]>,
+    },
+    ExpectedVariant {
+        head_type: core::Term,
+    },
+    ExpectedData {
+        data_name: String,
+        head_type: core::Term,
+    },
     TooManyInputsInFunctionTerm,
     TooManyInputsInFunctionElim {
         head_type: core::Term,
     },
+    MismatchedPlicity,
+    UnexpectedMeta,
     UnexpectedArrayTerm {
         expected_type: core::Term,
     },
@@ -413,7 +460,7 @@ impl CoreTypingMessage {
             CoreTypingMessage::InvalidRecordTypeLabelCount => Diagnostic::bug()
                 .with_message("invalid record type")
                 .with_notes(vec![
-                    "number of record labels does not match the number of types".to_owned(),
+                    "number of record labels does not match the number of entries".to_owned(),
                 ]),
             CoreTypingMessage::InvalidRecordTermLabelCount => Diagnostic::bug()
                 .with_message("invalid record term")
@@ -429,6 +476,65 @@ impl CoreTypingMessage {
                     "eliminating a term of type `{}`",
                     to_doc(head_type).pretty(std::usize::MAX),
                 )]),
+            CoreTypingMessage::InvalidVariantType { duplicate_labels } => Diagnostic::bug()
+                .with_message("invalid variant type")
+                .with_notes(
+                    duplicate_labels
+                        .iter()
+                        .map(|name| format!("label `{}` was used more than once", name))
+                        .collect(),
+                ),
+            CoreTypingMessage::InvalidVariantTypeLabelCount => Diagnostic::bug()
+                .with_message("invalid variant type")
+                .with_notes(vec![
+                    "number of variant labels does not match the number of types".to_owned(),
+                ]),
+            CoreTypingMessage::UnexpectedVariantTerm {
+                label,
+                expected_type,
+            } => Diagnostic::bug()
+                .with_message("unexpected variant term")
+                .with_notes(vec![format!(
+                    "expected `{}`, found a variant with the label `{}`",
+                    to_doc(expected_type).pretty(usize::MAX),
+                    label,
+                )]),
+            CoreTypingMessage::UnexpectedMatchLabels {
+                expected_labels,
+                found_labels,
+            } => Diagnostic::bug()
+                .with_message("unexpected match term labels")
+                .with_notes(vec![
+                    format!(
+                        "expected labels: {}",
+                        expected_labels
+                            .iter()
+                            .map(|label| format!("`{}`", label))
+                            .format(", "),
+                    ),
+                    format!(
+                        "found labels: {}",
+                        found_labels
+                            .iter()
+                            .map(|label| format!("`{}`", label))
+                            .format(", "),
+                    ),
+                ]),
+            CoreTypingMessage::ExpectedVariant { head_type } => Diagnostic::bug()
+                .with_message("expected a variant")
+                .with_notes(vec![format!(
+                    "matching on a term of type `{}`",
+                    to_doc(head_type).pretty(usize::MAX),
+                )]),
+            CoreTypingMessage::ExpectedData {
+                data_name,
+                head_type,
+            } => Diagnostic::bug()
+                .with_message(format!("expected an element of `{}`", data_name))
+                .with_notes(vec![format!(
+                    "eliminating a term of type `{}`",
+                    to_doc(head_type).pretty(usize::MAX),
+                )]),
             CoreTypingMessage::TooManyInputsInFunctionTerm => {
                 Diagnostic::bug().with_message("too many inputs in function term")
             }
@@ -438,6 +544,14 @@ impl CoreTypingMessage {
                     "eliminating a term of type `{}`",
                     to_doc(head_type).pretty(std::usize::MAX),
                 )]),
+            CoreTypingMessage::MismatchedPlicity => Diagnostic::bug()
+                .with_message("mismatched plicity")
+                .with_notes(vec![
+                    "implicit and explicit function inputs were mixed up".to_owned()
+                ]),
+            CoreTypingMessage::UnexpectedMeta => {
+                Diagnostic::bug().with_message("unexpected metavariable in core term")
+            }
             CoreTypingMessage::UnexpectedArrayTerm { expected_type } => Diagnostic::bug()
                 .with_message("unexpected array term")
                 .with_notes(vec![format!(
@@ -479,6 +593,17 @@ pub enum SurfaceToCoreMessage {
     UnboundName {
         location: Location,
         name: String,
+        suggestion: Option<String>,
+    },
+    GlobalAlreadyDefined {
+        location: Location,
+        name: String,
+    },
+    InvalidDataOccurrence {
+        location: Location,
+        data_name: String,
+        occurrence: core::data::Occurrence,
+        expected_type: surface::Term,
     },
     InvalidRecordType {
         duplicate_labels: Vec<(String, Location, Location)>,
@@ -493,6 +618,37 @@ pub enum SurfaceToCoreMessage {
         label_location: Location,
         expected_label: String,
         head_type: surface::Term,
+        suggestion: Option<String>,
+    },
+    ExpectedRecord {
+        location: Location,
+        found_type: surface::Term,
+    },
+    InvalidRecordUpdate {
+        duplicate_labels: Vec<(String, Location, Location)>,
+    },
+    ChangedEntryType {
+        location: Location,
+        label: String,
+        found_type: surface::Term,
+        expected_type: surface::Term,
+    },
+    InvalidVariantType {
+        duplicate_labels: Vec<(String, Location, Location)>,
+    },
+    VariantLabelNotFound {
+        location: Location,
+        label: String,
+        expected_type: surface::Term,
+    },
+    ExpectedVariant {
+        location: Location,
+        found_type: surface::Term,
+    },
+    InvalidMatch {
+        location: Location,
+        missing_labels: Vec<String>,
+        unexpected_labels: Vec<Location>,
     },
     TooManyInputsInFunctionTerm {
         unexpected_inputs: Vec<Location>,
@@ -502,10 +658,29 @@ pub enum SurfaceToCoreMessage {
         head_type: surface::Term,
         unexpected_input_terms: Vec<Location>,
     },
+    UnexpectedImplicitInput {
+        location: Location,
+    },
+    UnsolvedMeta {
+        location: Location,
+        name: Option<String>,
+    },
+    UnfilledHole {
+        location: Location,
+        name: Option<String>,
+    },
     NoLiteralConversion {
         location: Location,
         expected_type: surface::Term,
     },
+    DefaultedNumberLiteral {
+        location: Location,
+        default_type: surface::Term,
+    },
+    NoStringConversion {
+        location: Location,
+        found_type: surface::Term,
+    },
     MismatchedSequenceLength {
         location: Location,
         found_len: usize,
@@ -519,11 +694,23 @@ pub enum SurfaceToCoreMessage {
         location: Location,
         term: AmbiguousTerm,
     },
+    AmbiguousOperators {
+        first_location: Location,
+        first_operator: String,
+        second_location: Location,
+        second_operator: String,
+    },
     MismatchedTypes {
         location: Location,
         found_type: surface::Term,
         expected_type: ExpectedType<surface::Term>,
     },
+    HoleGoal {
+        location: Location,
+        name: Option<String>,
+        expected_type: Option<surface::Term>,
+        local_types: Vec<(String, surface::Term)>,
+    },
 }
 
 impl SurfaceToCoreMessage {
@@ -539,13 +726,51 @@ impl SurfaceToCoreMessage {
         let to_doc = |term| surface_to_pretty::from_term(pretty_alloc, term).1;
 
         match self {
-            SurfaceToCoreMessage::UnboundName { location, name } => Diagnostic::error()
+            SurfaceToCoreMessage::UnboundName {
+                location,
+                name,
+                suggestion,
+            } => Diagnostic::error()
                 .with_message(format!("cannot find `{}` in this scope", name))
-                // TODO: name suggestions?
                 .with_labels(option_to_vec(
                     primary(location).map(|label| label.with_message("not found in this scope")),
+                ))
+                .with_notes(option_to_vec(suggestion.as_deref().map(format_suggestion))),
+
+            SurfaceToCoreMessage::GlobalAlreadyDefined { location, name } => Diagnostic::error()
+                .with_message(format!("the name `{}` is defined multiple times", name))
+                .with_labels(option_to_vec(
+                    primary(location).map(|label| label.with_message("already defined")),
                 )),
 
+            SurfaceToCoreMessage::InvalidDataOccurrence {
+                location,
+                data_name,
+                occurrence,
+                expected_type,
+            } => match occurrence {
+                core::data::Occurrence::Negative => Diagnostic::error()
+                    .with_message(format!("`{}` occurs in a negative position", data_name))
+                    .with_labels(option_to_vec(primary(location).map(|label| {
+                        label.with_message(format!(
+                            "`{}` occurs to the left of a function arrow",
+                            data_name,
+                        ))
+                    })))
+                    .with_notes(vec![
+                        "data types must only occur strictly positively in their fields".to_owned(),
+                    ]),
+                core::data::Occurrence::Nested => Diagnostic::error()
+                    .with_message(format!("unsupported occurrence of `{}`", data_name))
+                    .with_labels(option_to_vec(primary(location).map(|label| {
+                        label.with_message(format!("`{}` occurs nested in this type", data_name))
+                    })))
+                    .with_notes(vec![format!(
+                        "recursive fields must have the type `{}`",
+                        to_doc(expected_type).pretty(usize::MAX),
+                    )]),
+            },
+
             SurfaceToCoreMessage::InvalidRecordType { duplicate_labels } => Diagnostic::error()
                 .with_message("invalid record type")
                 .with_labels({
@@ -603,6 +828,7 @@ impl SurfaceToCoreMessage {
                 label_location,
                 expected_label,
                 head_type,
+                suggestion,
             } => Diagnostic::error()
                 .with_message(format!(
                     "no entry with label `{}` in type `{}`",
@@ -620,7 +846,132 @@ impl SurfaceToCoreMessage {
                             ))
                         }))
                         .collect(),
-                ),
+                )
+                .with_notes(option_to_vec(suggestion.as_deref().map(format_suggestion))),
+
+            SurfaceToCoreMessage::ExpectedRecord {
+                location,
+                found_type,
+            } => Diagnostic::error()
+                .with_message("expected a record")
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message(format!(
+                        "expected a record, found `{}`",
+                        to_doc(found_type).pretty(usize::MAX),
+                    ))
+                }))),
+
+            SurfaceToCoreMessage::InvalidRecordUpdate { duplicate_labels } => Diagnostic::error()
+                .with_message("invalid record update")
+                .with_labels({
+                    let mut labels = Vec::with_capacity(duplicate_labels.len() * 2);
+
+                    for (label_name, label_location1, label_location2) in duplicate_labels {
+                        labels.extend(secondary(label_location1).map(|label| {
+                            label.with_message(format!("first update of `{}`", label_name))
+                        }));
+                        labels.extend(primary(label_location2).map(|label| {
+                            label.with_message("entry label updated more than once")
+                        }));
+                    }
+
+                    labels
+                }),
+
+            SurfaceToCoreMessage::ChangedEntryType {
+                location,
+                label,
+                found_type,
+                expected_type,
+            } => Diagnostic::error()
+                .with_message(format!("the type of the entry `{}` was changed", label))
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message(format!(
+                        "expected `{}`, found `{}`",
+                        to_doc(expected_type).pretty(usize::MAX),
+                        to_doc(found_type).pretty(usize::MAX),
+                    ))
+                })))
+                .with_notes(vec![format!(
+                    "the entry `{}` depends on an entry that was replaced, and must also be replaced",
+                    label,
+                )]),
+
+            SurfaceToCoreMessage::InvalidVariantType { duplicate_labels } => Diagnostic::error()
+                .with_message("invalid variant type")
+                .with_labels({
+                    let mut labels = Vec::with_capacity(duplicate_labels.len() * 2);
+
+                    for (label_name, label_location1, label_location2) in duplicate_labels {
+                        labels.extend(secondary(label_location1).map(|label| {
+                            label.with_message(format!("first use of `{}`", label_name))
+                        }));
+                        labels.extend(primary(label_location2).map(|label| {
+                            label.with_message("variant label used more than once")
+                        }));
+                    }
+
+                    labels
+                }),
+
+            SurfaceToCoreMessage::VariantLabelNotFound {
+                location,
+                label,
+                expected_type,
+            } => Diagnostic::error()
+                .with_message(format!(
+                    "no variant with label `{}` in type `{}`",
+                    label,
+                    to_doc(expected_type).pretty(usize::MAX),
+                ))
+                .with_labels(option_to_vec(
+                    primary(location).map(|label| label.with_message("unknown variant label")),
+                )),
+
+            SurfaceToCoreMessage::ExpectedVariant {
+                location,
+                found_type,
+            } => Diagnostic::error()
+                .with_message("expected a variant")
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message(format!(
+                        "expected a variant, found `{}`",
+                        to_doc(found_type).pretty(usize::MAX),
+                    ))
+                }))),
+
+            SurfaceToCoreMessage::InvalidMatch {
+                location,
+                missing_labels,
+                unexpected_labels,
+            } => Diagnostic::error()
+                .with_message("invalid match term")
+                .with_labels({
+                    let mut labels = Vec::with_capacity(
+                        unexpected_labels.len() + if missing_labels.is_empty() { 0 } else { 1 },
+                    );
+
+                    for label_location in unexpected_labels {
+                        labels.extend(
+                            primary(label_location)
+                                .map(|label| label.with_message("unexpected branch label")),
+                        );
+                    }
+
+                    if !missing_labels.is_empty() {
+                        labels.extend(primary(location).map(|label| {
+                            label.with_message(format!(
+                                "missing branches for the labels {} in this match term",
+                                missing_labels
+                                    .iter()
+                                    .map(|label| format!("`{}`", label))
+                                    .format(", "),
+                            ))
+                        }));
+                    }
+
+                    labels
+                }),
 
             SurfaceToCoreMessage::TooManyInputsInFunctionTerm { unexpected_inputs } => {
                 Diagnostic::error()
@@ -659,6 +1010,27 @@ impl SurfaceToCoreMessage {
                         .collect(),
                 ),
 
+            SurfaceToCoreMessage::UnexpectedImplicitInput { location } => Diagnostic::error()
+                .with_message("unexpected implicit input")
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message("the function does not expect an implicit input here")
+                }))),
+
+            SurfaceToCoreMessage::UnsolvedMeta { location, name } => Diagnostic::error()
+                .with_message(match name {
+                    Some(name) => format!("failed to infer the implicit input `{}`", name),
+                    None => "failed to infer an implicit input".to_owned(),
+                })
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message("could not infer this implicit input")
+                }))),
+
+            SurfaceToCoreMessage::UnfilledHole { location, name } => Diagnostic::error()
+                .with_message(format!("unfilled hole `?{}`", name.as_deref().unwrap_or("")))
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message("a term must be written in place of this hole")
+                }))),
+
             SurfaceToCoreMessage::NoLiteralConversion {
                 location,
                 expected_type,
@@ -672,6 +1044,30 @@ impl SurfaceToCoreMessage {
                     ))
                 }))),
 
+            SurfaceToCoreMessage::NoStringConversion {
+                location,
+                found_type,
+            } => Diagnostic::error()
+                .with_message("no known string conversion")
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message(format!(
+                        "cannot interpolate an element of `{}` into a string",
+                        to_doc(found_type).pretty(usize::MAX),
+                    ))
+                }))),
+
+            SurfaceToCoreMessage::DefaultedNumberLiteral {
+                location,
+                default_type,
+            } => Diagnostic::warning()
+                .with_message(format!(
+                    "numeric literal defaulted to `{}`",
+                    to_doc(default_type).pretty(usize::MAX),
+                ))
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message("add a type annotation to use a different type")
+                }))),
+
             SurfaceToCoreMessage::MismatchedSequenceLength {
                 location,
                 found_len,
@@ -706,6 +1102,29 @@ impl SurfaceToCoreMessage {
                     primary(location).map(|label| label.with_message("type annotations needed")),
                 )),
 
+            SurfaceToCoreMessage::AmbiguousOperators {
+                first_location,
+                first_operator,
+                second_location,
+                second_operator,
+            } => Diagnostic::error()
+                .with_message(format!(
+                    "ambiguous use of the operators `{}` and `{}`",
+                    first_operator, second_operator,
+                ))
+                .with_labels(
+                    primary(second_location)
+                        .map(|label| label.with_message("cannot be grouped with the previous operator"))
+                        .into_iter()
+                        .chain(secondary(first_location).map(|label| {
+                            label.with_message("operator with the same precedence")
+                        }))
+                        .collect(),
+                )
+                .with_notes(vec![
+                    "use parentheses to group the operators explicitly".to_owned(),
+                ]),
+
             SurfaceToCoreMessage::MismatchedTypes {
                 location,
                 found_type,
@@ -727,6 +1146,35 @@ impl SurfaceToCoreMessage {
                         ),
                     })
                 }))),
+
+            SurfaceToCoreMessage::HoleGoal {
+                location,
+                name,
+                expected_type,
+                local_types,
+            } => Diagnostic::note()
+                .with_message(format!("found hole `?{}`", name.as_deref().unwrap_or("")))
+                .with_labels(option_to_vec(primary(location).map(|label| {
+                    label.with_message(match expected_type {
+                        None => "the type of this hole could not be inferred".to_owned(),
+                        Some(expected_type) => {
+                            format!("expected `{}`", to_doc(expected_type).pretty(usize::MAX),)
+                        }
+                    })
+                })))
+                .with_notes(match local_types.as_slice() {
+                    [] => Vec::new(),
+                    local_types => vec![format!(
+                        "local variables in scope:\n{}",
+                        local_types.iter().format_with("\n", |(name, r#type), f| {
+                            f(&format_args!(
+                                "    {} : {}",
+                                name,
+                                to_doc(r#type).pretty(usize::MAX),
+                            ))
+                        }),
+                    )],
+                }),
         }
     }
 }
@@ -758,6 +1206,10 @@ fn option_to_vec<T>(option: Option<T>) -> Vec<T> {
     }
 }
 
+fn format_suggestion(suggestion: &str) -> String {
+    format!("help: did you mean `{}`?", suggestion)
+}
+
 fn format_expected(expected: &[String]) -> Option<String> {
     use itertools::Itertools;
 